@@ -11,7 +11,7 @@ extern crate rkyv;
 #[cfg(feature = "speedy")]
 extern crate speedy;
 
-use alkahest::{Deserialize, Formula, Lazy, Ref, SerIter, Serialize};
+use alkahest::{Deserialize, Formula, Lazy, SerIter, Serialize};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 #[cfg(feature = "rkyv")]
@@ -19,7 +19,7 @@ use bytecheck::CheckBytes;
 use rand::{
     distributions::{Alphanumeric, DistString},
     rngs::SmallRng,
-    thread_rng, Rng, SeedableRng,
+    Rng, SeedableRng,
 };
 
 #[derive(Debug, Clone, Formula, Serialize, Deserialize)]
@@ -121,9 +121,8 @@ fn messages<'a>(mut rng: impl Rng + 'a, len: usize) -> impl Iterator<Item = Game
 }
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let mut buffer = Vec::with_capacity(1 << 14);
-    buffer.resize(buffer.capacity(), 0);
-    let mut rng = SmallRng::seed_from_u64(42);
+    let mut buffer = vec![0; 1 << 14];
+    let rng = SmallRng::seed_from_u64(42);
 
     const LEN: usize = 200;
 
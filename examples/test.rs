@@ -63,8 +63,8 @@ fn main() {
     let size = serialize::<[Foo], _>([value], &mut buffer).unwrap();
     assert_eq!(size, buffer.len());
 
-    let foo = deserialize::<[Foo], Vec<(&str, &str)>>(&buffer).unwrap().0;
-    assert_eq!(foo, vec![("qwe", "rty")]);
+    let fields = deserialize::<[Foo], Vec<(&str, &str)>>(&buffer).unwrap().0;
+    assert_eq!(fields, vec![("qwe", "rty")]);
 
     type MyFormula = Test<Vec<Vec<u32>>>;
 
@@ -1,4 +1,4 @@
-use proc_easy::{EasyArgument, EasyAttributes, EasyPeek, EasyToken};
+use proc_easy::{EasyArgument, EasyAttributes, EasyPeek, EasySeparated, EasyToken};
 use proc_macro2::{Span, TokenStream};
 use quote::ToTokens;
 use syn::{
@@ -9,7 +9,13 @@ use syn::{
 proc_easy::easy_token!(owned);
 proc_easy::easy_token!(serialize);
 proc_easy::easy_token!(deserialize);
-// proc_easy::easy_token!(non_exhaustive);
+proc_easy::easy_token!(non_exhaustive);
+proc_easy::easy_token!(extensible);
+proc_easy::easy_token!(packed);
+proc_easy::easy_token!(unknown);
+proc_easy::easy_token!(variant);
+proc_easy::easy_token!(reserved);
+proc_easy::easy_token!(id);
 
 proc_easy::easy_parse! {
     struct FormulaParams {
@@ -104,6 +110,27 @@ proc_easy::easy_argument! {
     }
 }
 
+proc_easy::easy_argument_value! {
+    struct VariantFormula {
+        token: variant,
+        formula: syn::Type,
+    }
+}
+
+proc_easy::easy_argument_value! {
+    struct Reserved {
+        token: reserved,
+        ids: EasySeparated<syn::LitInt>,
+    }
+}
+
+proc_easy::easy_argument_value! {
+    struct VariantId {
+        token: id,
+        id: syn::LitInt,
+    }
+}
+
 proc_easy::easy_argument_tuple! {
     struct NoReferenceRef {
         token: owned,
@@ -123,18 +150,21 @@ proc_easy::easy_argument_tuple! {
     struct DeserializeArg {
         token: deserialize,
         formula: Option<FormulaRef>,
-        // non_exhaustive: Option<non_exhaustive>,
     }
 }
 
 proc_easy::easy_attributes! {
     @(alkahest)
     struct Attrs {
-        // non_exhaustive: Option<non_exhaustive>,
+        non_exhaustive: Option<non_exhaustive>,
+        extensible: Option<extensible>,
+        packed: Option<packed>,
+        reserved: Option<Reserved>,
         owned: Option<NoReferenceRef>,
         serialize: Vec<SerializeArg>,
         deserialize: Vec<DeserializeArg>,
         variant: Option<Variant>,
+        variant_formula: Option<VariantFormula>,
         formula: Option<FormulaRef>,
     }
 }
@@ -146,13 +176,17 @@ pub struct Formula {
 }
 
 pub struct Args {
-    // pub non_exhaustive: Option<non_exhaustive>,
+    pub non_exhaustive: Option<non_exhaustive>,
+    pub extensible: Option<extensible>,
+    pub packed: Option<packed>,
     #[allow(clippy::option_option)]
     pub owned: Option<Option<Formula>>,
     pub common: Option<Formula>,
     pub serialize: Option<Formula>,
     pub deserialize: Option<Formula>,
     pub variant: Option<syn::Ident>,
+    pub variant_formula: Option<syn::Type>,
+    pub reserved: Vec<syn::LitInt>,
 }
 
 pub fn parse_attributes(attrs: &[syn::Attribute]) -> syn::Result<Args> {
@@ -161,7 +195,6 @@ pub fn parse_attributes(attrs: &[syn::Attribute]) -> syn::Result<Args> {
     let mut serialize_opt = None;
     let mut deserialize_opt = None;
     let common_opt = attrs.formula.map(Formula::from);
-    // let mut non_exhaustive_opt = attrs.non_exhaustive;
     let mut owned_opt = attrs.owned;
 
     for serialize in attrs.serialize {
@@ -197,29 +230,143 @@ pub fn parse_attributes(attrs: &[syn::Attribute]) -> syn::Result<Args> {
             }
             deserialize_opt = Some(Formula::from(formula));
         }
-
-        // if let Some(non_exhaustive) = deserialize.non_exhaustive {
-        //     if non_exhaustive_opt.is_some() {
-        //         return Err(syn::Error::new(
-        //             non_exhaustive.span(),
-        //             "Non-exhaustive already specified",
-        //         ));
-        //     }
-
-        //     non_exhaustive_opt = Some(non_exhaustive);
-        // }
     }
 
     Ok(Args {
         common: common_opt,
         serialize: serialize_opt,
         deserialize: deserialize_opt,
-        // non_exhaustive: non_exhaustive_opt,
+        non_exhaustive: attrs.non_exhaustive,
+        extensible: attrs.extensible,
+        packed: attrs.packed,
         owned: owned_opt.map(|owned| owned.formula.map(Formula::from)),
         variant: attrs.variant.map(|v| v.variant),
+        variant_formula: attrs.variant_formula.map(|v| v.formula),
+        reserved: attrs
+            .reserved
+            .map_or_else(Vec::new, |reserved| reserved.ids.to_vec()),
     })
 }
 
+proc_easy::easy_attributes! {
+    @(alkahest)
+    struct VariantAttrs {
+        unknown: Option<unknown>,
+        id: Option<VariantId>,
+    }
+}
+
+pub struct VariantArgs {
+    pub unknown: Option<unknown>,
+    pub id: Option<syn::LitInt>,
+}
+
+pub fn parse_variant_attributes(attrs: &[syn::Attribute]) -> syn::Result<VariantArgs> {
+    let attrs = VariantAttrs::parse(attrs, Span::call_site())?;
+
+    Ok(VariantArgs {
+        unknown: attrs.unknown,
+        id: attrs.id.map(|id| id.id),
+    })
+}
+
+/// Assigns ids to variants of enum formula.
+///
+/// Id is taken from `#[alkahest(id = N)]` attribute or Rust discriminant.
+/// Otherwise it is one more than id of the previous variant, like Rust discriminants.
+/// Ids must be unique and must not be reserved with `#[alkahest(reserved(N, ...))]`.
+pub fn variant_ids(data: &syn::DataEnum, reserved: &[syn::LitInt]) -> syn::Result<Vec<u32>> {
+    let reserved = reserved
+        .iter()
+        .map(syn::LitInt::base10_parse::<u32>)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let mut ids = Vec::with_capacity(data.variants.len());
+    let mut next = Some(0u32);
+
+    for variant in &data.variants {
+        let args = parse_variant_attributes(&variant.attrs)?;
+
+        let discriminant = match &variant.discriminant {
+            None => None,
+            Some((
+                _,
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(lit),
+                    ..
+                }),
+            )) => Some(lit.base10_parse::<u32>()?),
+            Some((_, expr)) => {
+                return Err(syn::Error::new_spanned(
+                    expr,
+                    "Discriminant of enum formula must be an integer literal",
+                ))
+            }
+        };
+
+        let id = match (args.id, discriminant) {
+            (Some(lit), Some(discriminant)) => {
+                let id = lit.base10_parse::<u32>()?;
+                if id != discriminant {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "Variant id does not match discriminant",
+                    ));
+                }
+                id
+            }
+            (Some(lit), None) => lit.base10_parse::<u32>()?,
+            (None, Some(discriminant)) => discriminant,
+            (None, None) => match next {
+                Some(next) => next,
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        &variant.ident,
+                        "Variant id overflows `u32`",
+                    ))
+                }
+            },
+        };
+
+        if reserved.contains(&id) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!("Variant id {id} is reserved"),
+            ));
+        }
+
+        if ids.contains(&id) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!("Variant id {id} is already used"),
+            ));
+        }
+
+        ids.push(id);
+        next = id.checked_add(1);
+    }
+
+    Ok(ids)
+}
+
+/// Finds variant marked with `#[alkahest(unknown)]`.
+pub fn find_unknown_variant(data: &syn::DataEnum) -> syn::Result<Option<&syn::Variant>> {
+    let mut unknown = None;
+    for variant in &data.variants {
+        let args = parse_variant_attributes(&variant.attrs)?;
+        if let Some(token) = args.unknown {
+            if unknown.is_some() {
+                return Err(syn::Error::new(
+                    token.span(),
+                    "Unknown variant already specified",
+                ));
+            }
+            unknown = Some(variant);
+        }
+    }
+    Ok(unknown)
+}
+
 pub fn path_make_expr_style(mut path: syn::Path) -> syn::Path {
     for seg in &mut path.segments {
         if let syn::PathArguments::AngleBracketed(ref mut args) = seg.arguments {
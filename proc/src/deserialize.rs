@@ -1,7 +1,7 @@
 use proc_macro2::TokenStream;
 
 use crate::{
-    attrs::{parse_attributes, Args, Formula},
+    attrs::{find_unknown_variant, parse_attributes, Args, Formula},
     enum_field_order_checks, struct_field_order_checks,
 };
 
@@ -28,6 +28,26 @@ fn de_lifetime(formula: &mut Formula, generics: &syn::Generics) -> syn::Lifetime
     }
 }
 
+/// Returns formula to read payload of unknown variant with.
+/// `Lazy<'de, F>` payload is read with formula `F`,
+/// any other payload type is read with `Bytes` formula.
+fn unknown_payload_formula(ty: &syn::Type) -> syn::Type {
+    if let syn::Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            if segment.ident == "Lazy" {
+                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                    for arg in &args.args {
+                        if let syn::GenericArgument::Type(formula) = arg {
+                            return formula.clone();
+                        }
+                    }
+                }
+            }
+        }
+    }
+    syn::parse_quote!(::alkahest::private::Bytes)
+}
+
 struct Config {
     formula: Formula,
 
@@ -149,6 +169,10 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let input = syn::parse::<syn::DeriveInput>(input)?;
     let args = parse_attributes(&input.attrs)?;
 
+    if args.packed.is_some() {
+        return crate::packed::derive_deserialize(&input, args);
+    }
+
     let ident = &input.ident;
 
     match input.data {
@@ -157,10 +181,26 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
             "Deserialize cannot be derived for unions",
         )),
         syn::Data::Struct(data) => {
+            let extensible = args.extensible.is_some();
             let cfg = Config::for_struct(args, &data, &input.generics);
 
             let field_checks = if cfg.check_fields {
-                struct_field_order_checks(&data, None, &input.ident, &cfg.formula.path)
+                let formula_path = &cfg.formula.path;
+                let error = format!(
+                    "`{}` must be extensible if and only if formula is extensible",
+                    input.ident
+                );
+                let mut checks =
+                    struct_field_order_checks(&data, None, &input.ident, &cfg.formula.path);
+                let packed_error = format!(
+                    "`{}` must be packed if and only if formula is packed",
+                    input.ident
+                );
+                checks.extend(quote::quote! {
+                    const { ::alkahest::private::assert!(#extensible == #formula_path::__ALKAHEST_EXTENSIBLE, #error) };
+                    const { ::alkahest::private::assert!(!#formula_path::__ALKAHEST_PACKED, #packed_error) };
+                });
+                checks
             } else {
                 TokenStream::new()
             };
@@ -179,7 +219,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 .or(cfg.formula.generics.gt_token);
             deserialize_generics
                 .params
-                .extend(cfg.formula.generics.params.into_iter());
+                .extend(cfg.formula.generics.params);
 
             if let Some(where_clause) = cfg.formula.generics.where_clause {
                 deserialize_generics
@@ -277,6 +317,52 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
 
             let field_count = data.fields.len();
 
+            // Path segments of fields reported by errors with context.
+            let field_segments =
+                data.fields
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, field)| match &field.ident {
+                        Some(name) => format!("{ident}.{name}"),
+                        None => format!("{ident}.{idx}"),
+                    });
+
+            // Fields of extensible structure are always written with their sizes.
+            // Fields missing in data written with older formula are set to default.
+            let (read_fields, read_in_place_fields): (Vec<_>, Vec<_>) = field_ids
+                .iter()
+                .zip(field_segments)
+                .map(|(field_id, segment)| {
+                    if extensible {
+                        (
+                            quote::quote! {
+                                if de.is_empty() {
+                                    ::alkahest::private::Default::default()
+                                } else {
+                                    with_formula.read_field(&mut de, false, #segment)?
+                                }
+                            },
+                            quote::quote! {
+                                if de.is_empty() {
+                                    *place = ::alkahest::private::Default::default();
+                                } else {
+                                    with_formula.read_in_place(place, &mut de, false, #segment)?;
+                                }
+                            },
+                        )
+                    } else {
+                        (
+                            quote::quote! {
+                                with_formula.read_field(&mut de, #field_count == 1 + #field_id, #segment)?
+                            },
+                            quote::quote! {
+                                with_formula.read_in_place(place, &mut de, #field_count == 1 + #field_id, #segment)?;
+                            },
+                        )
+                    }
+                })
+                .unzip();
+
             let (_impl_generics, type_generics, _where_clause) = input.generics.split_for_impl();
             let (impl_deserialize_generics, _type_deserialize_generics, where_serialize_clause) =
                 deserialize_generics.split_for_impl();
@@ -291,10 +377,10 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                 #formula_path #bind_ref_names => #bound_names,
                                 _ => unreachable!(),
                             });
-                            let #bound_names = with_formula.read_field(&mut de, #field_count == 1 + #field_ids)?;
+                            let #bound_names = #read_fields;
                         )*
                         // #consume_tail
-                        // de.finish()?;
+                        de.finish()?;
 
                         let value = #ident #bind_names;
                         ::alkahest::private::Result::Ok(value)
@@ -311,16 +397,41 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                 #formula_path #bind_ref_names => #bound_names,
                                 _ => unreachable!(),
                             });
-                            with_formula.read_in_place(#bound_names, &mut de, #field_count == 1 + #field_ids)?;
+                            let place = #bound_names;
+                            #read_in_place_fields
                         )*
                         // #consume_tail
-                        // de.finish()?;
+                        de.finish()?;
                         ::alkahest::private::Result::Ok(())
                     }
                 }
             })
         }
-        syn::Data::Enum(data) => {
+        syn::Data::Enum(mut data) => {
+            let unknown = find_unknown_variant(&data)?.cloned();
+            if let Some(unknown) = &unknown {
+                if args.deserialize.is_none() && args.common.is_none() {
+                    return Err(syn::Error::new_spanned(
+                        unknown,
+                        "Unknown variant requires explicit formula",
+                    ));
+                }
+
+                if unknown.fields.len() != 2 {
+                    return Err(syn::Error::new_spanned(
+                        unknown,
+                        "Unknown variant must have two fields: variant index and payload",
+                    ));
+                }
+
+                // Unknown variant does not match any formula variant.
+                data.variants = data
+                    .variants
+                    .into_iter()
+                    .filter(|variant| variant.ident != unknown.ident)
+                    .collect();
+            }
+
             let cfg = Config::for_enum(args, &data, &input.generics);
 
             let field_checks = if cfg.check_fields {
@@ -343,7 +454,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 .or(cfg.formula.generics.gt_token);
             deserialize_generics
                 .params
-                .extend(cfg.formula.generics.params.into_iter());
+                .extend(cfg.formula.generics.params);
 
             if let Some(where_clause) = cfg.formula.generics.where_clause {
                 deserialize_generics
@@ -360,6 +471,23 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
 
             let field_counts: Vec<_> = data.variants.iter().map(|v| v.fields.len()).collect();
 
+            // Path segments of fields reported by errors with context.
+            let name = ident.to_string();
+            let field_segments: Vec<Vec<_>> = data
+                .variants
+                .iter()
+                .map(|v| {
+                    v.fields
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, field)| match &field.ident {
+                            Some(field) => format!("{ident}::{}.{field}", v.ident),
+                            None => format!("{ident}::{}.{idx}", v.ident),
+                        })
+                        .collect()
+                })
+                .collect();
+
             let variant_names = data.variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
 
             let bound_names = data
@@ -472,6 +600,60 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 })
                 .collect();
 
+            // Unknown variant receives variant index
+            // and the rest of the variant's data as payload.
+            let (unknown_check, unknown_arm, unknown_in_place_arm) = match &unknown {
+                None => (
+                    TokenStream::new(),
+                    quote::quote! {
+                        invalid => ::alkahest::private::Result::Err(::alkahest::private::trace_error(&de, ::alkahest::private::DeserializeError::WrongVariant(invalid), #name, __end)),
+                    },
+                    quote::quote! {
+                        (invalid, _) => ::alkahest::private::Result::Err(::alkahest::private::trace_error(&de, ::alkahest::private::DeserializeError::WrongVariant(invalid), #name, __end)),
+                    },
+                ),
+                Some(unknown) => {
+                    let name = &unknown.ident;
+                    let mut members =
+                        unknown
+                            .fields
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, field)| match &field.ident {
+                                Some(ident) => syn::Member::Named(ident.clone()),
+                                None => syn::Member::Unnamed(syn::Index::from(idx)),
+                            });
+                    let variant = members.next().unwrap();
+                    let payload = members.next().unwrap();
+                    let payload_formula =
+                        unknown_payload_formula(&unknown.fields.iter().nth(1).unwrap().ty);
+
+                    let error = format!(
+                        "Unknown variant `{}::{}` requires non-exhaustive formula",
+                        ident, name,
+                    );
+
+                    (
+                        quote::quote! {
+                            const { ::alkahest::private::assert!(#formula_path::__ALKAHEST_NON_EXHAUSTIVE, #error) };
+                        },
+                        quote::quote! {
+                            invalid => {
+                                let payload = de.read_value::<#payload_formula, _>(true)?;
+                                ::alkahest::private::Result::Ok(#ident::#name { #variant: invalid, #payload: payload })
+                            }
+                        },
+                        quote::quote! {
+                            (invalid, me) => {
+                                let payload = de.read_value::<#payload_formula, _>(true)?;
+                                *me = #ident::#name { #variant: invalid, #payload: payload };
+                                ::alkahest::private::Result::Ok(())
+                            }
+                        },
+                    )
+                }
+            };
+
             let (_impl_generics, type_generics, _where_clause) = input.generics.split_for_impl();
             let (impl_deserialize_generics, _type_deserialize_generics, where_serialize_clause) =
                 deserialize_generics.split_for_impl();
@@ -480,8 +662,11 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                     #[inline(always)]
                     fn deserialize(mut de: ::alkahest::private::Deserializer<#de>) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
                         #field_checks
+                        #unknown_check
 
-                        let variant_idx = de.read_value::<::alkahest::private::u32, _>(false)?;
+                        let __end = ::alkahest::private::stack_end(&de);
+                        let variant_idx = #formula_path::__alkahest_read_variant(&mut de)
+                            .map_err(|err| ::alkahest::private::trace_error(&de, err, #name, __end))?;
                         match variant_idx {
                             #(
                                 #formula_path::#variant_name_ids => {
@@ -491,22 +676,25 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                             #formula_path::#variant_names #bind_ref_names => #bound_names,
                                             _ => unreachable!(),
                                         });
-                                        let #bound_names = with_formula.read_field(&mut de, #field_counts == 1 + #field_ids)?;
+                                        let #bound_names = with_formula.read_field(&mut de, #field_counts == 1 + #field_ids, #field_segments)?;
                                     )*
                                     // #consume_tail
-                                    // de.finish()?;
+                                    de.finish()?;
                                     ::alkahest::private::Result::Ok(#ident::#variant_names #bind_names)
                                 }
                             )*
-                            invalid => ::alkahest::private::Result::Err(::alkahest::private::DeserializeError::WrongVariant(invalid)),
+                            #unknown_arm
                         }
                     }
 
                     #[inline(always)]
                     fn deserialize_in_place(&mut self, mut de: ::alkahest::private::Deserializer<#de>) -> Result<(), ::alkahest::private::DeserializeError> {
                         #field_checks
+                        #unknown_check
 
-                        let variant_idx = de.read_value::<::alkahest::private::u32, _>(false)?;
+                        let __end = ::alkahest::private::stack_end(&de);
+                        let variant_idx = #formula_path::__alkahest_read_variant(&mut de)
+                            .map_err(|err| ::alkahest::private::trace_error(&de, err, #name, __end))?;
                         match (variant_idx, self) {
                             #(
                                 (#formula_path::#variant_name_ids, #ident::#variant_names #bind_ref_mut_names) => {
@@ -516,10 +704,10 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                             #formula_path::#variant_names #bind_ref_names => #bound_names,
                                             _ => unreachable!(),
                                         });
-                                        with_formula.read_in_place(#bound_names, &mut de, #field_counts == 1 + #field_ids)?;
+                                        with_formula.read_in_place(#bound_names, &mut de, #field_counts == 1 + #field_ids, #field_segments)?;
                                     )*
                                     // #consume_tail
-                                    // de.finish()?;
+                                    de.finish()?;
                                     ::alkahest::private::Result::Ok(())
                                 }
                             )*
@@ -531,15 +719,15 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                             #formula_path::#variant_names #bind_ref_names => #bound_names,
                                             _ => unreachable!(),
                                         });
-                                        let #bound_names = with_formula.read_field(&mut de, #field_counts == 1 + #field_ids)?;
+                                        let #bound_names = with_formula.read_field(&mut de, #field_counts == 1 + #field_ids, #field_segments)?;
                                     )*
                                     // #consume_tail
-                                    // de.finish()?;
+                                    de.finish()?;
                                     *me = #ident::#variant_names #bind_names;
                                     ::alkahest::private::Result::Ok(())
                                 }
                             )*
-                            (invalid, _) => ::alkahest::private::Result::Err(::alkahest::private::DeserializeError::WrongVariant(invalid)),
+                            #unknown_in_place_arm
                         }
                     }
                 }
@@ -3,7 +3,10 @@ use std::collections::HashSet;
 use proc_macro2::TokenStream;
 use syn::spanned::Spanned;
 
-use crate::{attrs::parse_attributes, filter_type_param, is_generic_ty};
+use crate::{
+    attrs::{find_unknown_variant, parse_attributes, variant_ids},
+    filter_type_param, is_generic_ty,
+};
 
 #[allow(clippy::too_many_lines)]
 pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
@@ -11,7 +14,18 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let ident = &input.ident;
 
     let args = parse_attributes(&input.attrs)?;
-    // let non_exhaustive = args.non_exhaustive.is_some();
+    let non_exhaustive = args.non_exhaustive.is_some();
+    let extensible = args.extensible.is_some();
+    let packed = args.packed.is_some();
+
+    if let Some(packed) = args.packed {
+        if !input.generics.params.is_empty() {
+            return Err(syn::Error::new(
+                packed.span(),
+                "Packed formula cannot be generic",
+            ));
+        }
+    }
 
     if let Some(formula) = args
         .serialize
@@ -38,6 +52,44 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
             "Formula cannot be derived for unions",
         )),
         syn::Data::Struct(data) => {
+            if let Some(non_exhaustive) = args.non_exhaustive {
+                return Err(syn::Error::new(
+                    non_exhaustive.span(),
+                    "Non-exhaustive formula must be an enum",
+                ));
+            }
+
+            if let (Some(extensible), true) = (args.extensible, packed) {
+                return Err(syn::Error::new(
+                    extensible.span(),
+                    "Packed formula cannot be extensible",
+                ));
+            }
+
+            if let Some(variant_formula) = &args.variant_formula {
+                return Err(syn::Error::new_spanned(
+                    variant_formula,
+                    "Variant formula can be specified only for enums",
+                ));
+            }
+
+            if let Some(reserved) = args.reserved.first() {
+                return Err(syn::Error::new_spanned(
+                    reserved,
+                    "Variant ids can be reserved only for enums",
+                ));
+            }
+
+            // Newer formula may append fields to extensible structure,
+            // so its size is recorded with the value.
+            let expand_size = if extensible {
+                quote::quote! {
+                    max_size = ::alkahest::private::Option::None;
+                }
+            } else {
+                quote::quote! {}
+            };
+
             let all_field_types: Vec<_> = data.fields.iter().map(|field| &field.ty).collect();
             let last_field_type = all_field_types.last().copied().into_iter();
             let mut all_generic_field_types: HashSet<_> = all_field_types.iter().copied().collect();
@@ -74,6 +126,34 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
             let (formula_impl_generics, formula_type_generics, formula_where_clause) =
                 formula_generics.split_for_impl();
 
+            // Validation is available when every field can be validated.
+            let mut validate_generics = formula_generics.clone();
+            validate_generics.params.insert(0, syn::parse_quote!('__de));
+            validate_generics
+                .make_where_clause()
+                .predicates
+                .extend(all_field_types.iter().map(|ty| -> syn::WherePredicate {
+                    syn::parse_quote! { ::alkahest::private::Validated: ::alkahest::private::Deserialize<'__de, #ty> }
+                }));
+            let (validate_impl_generics, _, validate_where_clause) =
+                validate_generics.split_for_impl();
+
+            let field_count = all_field_types.len();
+            let validate_fields = all_field_types.iter().enumerate().map(|(idx, ty)| {
+                if extensible {
+                    quote::quote! {
+                        if !de.is_empty() {
+                            de.read_value::<#ty, ::alkahest::private::Validated>(false)?;
+                        }
+                    }
+                } else {
+                    let last = field_count == 1 + idx;
+                    quote::quote! {
+                        de.read_value::<#ty, ::alkahest::private::Validated>(#last)?;
+                    }
+                }
+            });
+
             let touch_fields = match &data.fields {
                 syn::Fields::Unit => quote::quote! {},
                 syn::Fields::Unnamed(fields) => {
@@ -92,7 +172,18 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 }
             };
 
-            let tokens = quote::quote! {
+            let name = ident.to_string();
+            let field_names: Vec<_> = data
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(idx, field)| match &field.ident {
+                    Some(ident) => ident.to_string(),
+                    None => idx.to_string(),
+                })
+                .collect();
+
+            let mut tokens = quote::quote! {
                 impl #formula_impl_generics #ident #formula_type_generics #formula_where_clause {
                     #(
                         #[doc(hidden)]
@@ -102,12 +193,85 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
 
                     // #(#with_fields)*
 
+                    #[doc(hidden)]
+                    pub const __ALKAHEST_EXTENSIBLE: bool = #extensible;
+
+                    #[doc(hidden)]
+                    pub const __ALKAHEST_PACKED: bool = #packed;
+
                     #[doc(hidden)]
                     #[allow(dead_code, unused_variables)]
                     fn __alkahest_touch(&self) {
                         let Self #touch_fields = self;
                     }
                 }
+            };
+
+            if packed {
+                tokens.extend(quote::quote! {
+                    impl ::alkahest::private::BitFormula for #ident {
+                        const BITS: ::alkahest::private::u32 = {
+                            let bits = 0 #(+ <#all_field_types as ::alkahest::private::BitFormula>::BITS)*;
+                            ::alkahest::private::assert!(bits <= 128, "Packed formula cannot exceed 128 bits");
+                            bits
+                        };
+                    }
+
+                    impl ::alkahest::private::Formula for #ident {
+                        const MAX_STACK_SIZE: ::alkahest::private::Option<::alkahest::private::usize> =
+                            ::alkahest::private::Option::Some(::alkahest::private::bits_size(<#ident as ::alkahest::private::BitFormula>::BITS));
+                        const EXACT_SIZE: ::alkahest::private::bool = true;
+                        const HEAPLESS: ::alkahest::private::bool = true;
+
+                        ::alkahest::private::with_alloc! {
+                            fn descriptor() -> ::alkahest::private::Descriptor {
+                                ::alkahest::private::Descriptor::Packed(::alkahest::private::Box::new(::alkahest::private::Descriptor::Struct {
+                                    name: ::alkahest::private::Into::into(#name),
+                                    fields: ::alkahest::private::Vec::from([#(
+                                        ::alkahest::private::Field::new(#field_names, <#all_field_types as ::alkahest::private::Formula>::descriptor()),
+                                    )*]),
+                                    extensible: false,
+                                }))
+                            }
+                        }
+                    }
+
+                    impl ::alkahest::private::BareFormula for #ident {}
+
+                    impl ::alkahest::private::DeserializeBits<#ident> for ::alkahest::private::Validated {
+                        #[inline(always)]
+                        #[allow(unused_mut, unused_variables)]
+                        fn deserialize_bits(__bits: ::alkahest::private::u128) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
+                            let mut __offset = 0u32;
+                            #(
+                                let __field = __bits.checked_shr(__offset).unwrap_or(0) & ::alkahest::private::bits_mask(<#all_field_types as ::alkahest::private::BitFormula>::BITS);
+                                <::alkahest::private::Validated as ::alkahest::private::DeserializeBits<#all_field_types>>::deserialize_bits(__field)?;
+                                __offset += <#all_field_types as ::alkahest::private::BitFormula>::BITS;
+                            )*
+                            ::alkahest::private::Result::Ok(::alkahest::private::Validated)
+                        }
+                    }
+                });
+                tokens.extend(validate_packed(ident));
+                return Ok(tokens);
+            }
+
+            tokens.extend(quote::quote! {
+                impl #validate_impl_generics ::alkahest::private::Deserialize<'__de, #ident #formula_type_generics> for ::alkahest::private::Validated #validate_where_clause {
+                    #[inline(always)]
+                    #[allow(unused_mut)]
+                    fn deserialize(mut de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
+                        #(#validate_fields)*
+                        de.finish()?;
+                        ::alkahest::private::Result::Ok(::alkahest::private::Validated)
+                    }
+
+                    #[inline(always)]
+                    fn deserialize_in_place(&mut self, de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<(), ::alkahest::private::DeserializeError> {
+                        <Self as ::alkahest::private::Deserialize<'__de, #ident #formula_type_generics>>::deserialize(de)?;
+                        ::alkahest::private::Result::Ok(())
+                    }
+                }
 
                 impl #formula_impl_generics ::alkahest::private::Formula for #ident #formula_type_generics #formula_where_clause {
                     const MAX_STACK_SIZE: ::alkahest::private::Option<::alkahest::private::usize> = {
@@ -116,21 +280,103 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                         #(
                             max_size = ::alkahest::private::sum_size(max_size, <#all_field_types as ::alkahest::private::Formula>::MAX_STACK_SIZE);
                         )*;
-                        // #expand_size
+                        #expand_size
                         max_size
                     };
 
-                    const EXACT_SIZE: ::alkahest::private::bool = {true #(; <#last_field_type as ::alkahest::private::Formula>::EXACT_SIZE)*};
+                    const EXACT_SIZE: ::alkahest::private::bool = !#extensible && {true #(; <#last_field_type as ::alkahest::private::Formula>::EXACT_SIZE)*};
 
                     const HEAPLESS: ::alkahest::private::bool = true #(&& <#all_field_types as ::alkahest::private::Formula>::HEAPLESS)*;
+
+                    ::alkahest::private::with_alloc! {
+                        fn descriptor() -> ::alkahest::private::Descriptor {
+                            ::alkahest::private::Descriptor::Struct {
+                                name: ::alkahest::private::Into::into(#name),
+                                fields: ::alkahest::private::Vec::from([#(
+                                    ::alkahest::private::Field::new(#field_names, <#all_field_types as ::alkahest::private::Formula>::descriptor()),
+                                )*]),
+                                extensible: #extensible,
+                            }
+                        }
+                    }
                 }
 
                 impl #formula_impl_generics ::alkahest::private::BareFormula for #ident #formula_type_generics #formula_where_clause {}
-            };
+            });
 
             Ok(tokens)
         }
         syn::Data::Enum(data) => {
+            if let Some(extensible) = args.extensible {
+                return Err(syn::Error::new(
+                    extensible.span(),
+                    "Extensible formula must be a struct",
+                ));
+            }
+
+            if let Some(unknown) = find_unknown_variant(data)? {
+                return Err(syn::Error::new_spanned(
+                    unknown,
+                    "Unknown variant can be specified only for `Deserialize` with explicit formula",
+                ));
+            }
+
+            if packed {
+                if let Some(non_exhaustive) = args.non_exhaustive {
+                    return Err(syn::Error::new(
+                        non_exhaustive.span(),
+                        "Packed formula cannot be non-exhaustive",
+                    ));
+                }
+
+                if let Some(variant) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+                    return Err(syn::Error::new_spanned(
+                        &variant.fields,
+                        "Variants of packed enum cannot have fields",
+                    ));
+                }
+
+                if let Some(variant_formula) = &args.variant_formula {
+                    return Err(syn::Error::new_spanned(
+                        variant_formula,
+                        "Packed enum cannot specify variant formula",
+                    ));
+                }
+            }
+
+            let variant_ids = variant_ids(data, &args.reserved)?;
+
+            // Number of indices required to represent all variant ids.
+            let variant_range = variant_ids.iter().max().map_or(0, |id| u64::from(*id) + 1);
+
+            // Variant index is written as `u32` unless specified otherwise.
+            // Inferred formula is the smallest integer that fits all variants.
+            let variant_formula: syn::Type = match args.variant_formula {
+                None => syn::parse_quote!(::alkahest::private::u32),
+                Some(syn::Type::Infer(infer)) => {
+                    // Inferred width grows with new variants,
+                    // so older readers could not read newer data.
+                    if args.non_exhaustive.is_some() {
+                        return Err(syn::Error::new(
+                            infer.span(),
+                            "Variant formula of non-exhaustive enum cannot be inferred, \
+                             specify it explicitly, e.g. `#[alkahest(variant = u8)]`",
+                        ));
+                    }
+
+                    match variant_range {
+                        0..=0x100 => {
+                            syn::parse_quote_spanned!(infer.span() => ::alkahest::private::u8)
+                        }
+                        0x101..=0x10000 => {
+                            syn::parse_quote_spanned!(infer.span() => ::alkahest::private::u16)
+                        }
+                        _ => syn::parse_quote_spanned!(infer.span() => ::alkahest::private::u32),
+                    }
+                }
+                Some(ty) => ty,
+            };
+
             let all_field_types: Vec<Vec<&syn::Type>> = data
                 .variants
                 .iter()
@@ -196,21 +442,71 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 .map(|v| quote::format_ident!("__ALKAHEST_FORMULA_VARIANT_{}_IDX", v.ident))
                 .collect();
 
-            #[allow(clippy::cast_possible_truncation)]
-            let variant_ids: Vec<_> = (0..data.variants.len() as u32).collect();
+            let name = ident.to_string();
+            let variant_names: Vec<_> = data.variants.iter().map(|v| v.ident.to_string()).collect();
+            let field_names: Vec<Vec<_>> = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    variant
+                        .fields
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, field)| match &field.ident {
+                            Some(ident) => ident.to_string(),
+                            None => idx.to_string(),
+                        })
+                        .collect()
+                })
+                .collect();
 
             let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
 
             let (formula_impl_generics, formula_type_generics, formula_where_clause) =
                 formula_generics.split_for_impl();
 
-            // let expand_size = if non_exhaustive {
-            //     quote::quote! {
-            //         max_size = ::alkahest::private::Option::None;
-            //     }
-            // } else {
-            //     quote::quote! {}
-            // };
+            let mut validate_generics = formula_generics.clone();
+            validate_generics.params.insert(0, syn::parse_quote!('__de));
+            validate_generics
+                .make_where_clause()
+                .predicates
+                .extend(all_field_types_flat.iter().map(|ty| -> syn::WherePredicate {
+                    syn::parse_quote! { ::alkahest::private::Validated: ::alkahest::private::Deserialize<'__de, #ty> }
+                }));
+            let (validate_impl_generics, _, validate_where_clause) =
+                validate_generics.split_for_impl();
+
+            let validate_variants = all_field_types.iter().map(|field_types| {
+                let field_count = field_types.len();
+                let fields = field_types.iter().enumerate().map(|(idx, ty)| {
+                    let last = field_count == 1 + idx;
+                    quote::quote! {
+                        de.read_value::<#ty, ::alkahest::private::Validated>(#last)?;
+                    }
+                });
+                quote::quote! { #(#fields)* }
+            });
+
+            // Unknown variants of non-exhaustive formula are not an error,
+            // but are never canonical.
+            let validate_unknown = if non_exhaustive {
+                quote::quote! {
+                    _ if de.is_strict() => ::alkahest::private::Result::Err(::alkahest::private::DeserializeError::NonCanonical),
+                    _ => ::alkahest::private::Result::Ok(::alkahest::private::Validated),
+                }
+            } else {
+                quote::quote! {
+                    invalid => ::alkahest::private::Result::Err(::alkahest::private::DeserializeError::WrongVariant(invalid)),
+                }
+            };
+
+            let expand_size = if non_exhaustive {
+                quote::quote! {
+                    max_size = ::alkahest::private::Option::None;
+                }
+            } else {
+                quote::quote! {}
+            };
 
             let touch_variants = data
                 .variants
@@ -264,7 +560,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 })
                 .collect::<Vec<_>>();
 
-            Ok(quote::quote! {
+            let mut tokens = quote::quote! {
                 impl #impl_generics #ident #type_generics #where_clause {
                     #(#(
                         #[doc(hidden)]
@@ -278,6 +574,33 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                         pub const #variant_name_ids: u32 = #variant_ids;
                     )*
 
+                    #[doc(hidden)]
+                    pub const __ALKAHEST_NON_EXHAUSTIVE: bool = #non_exhaustive;
+
+                    #[doc(hidden)]
+                    pub const __ALKAHEST_PACKED: bool = #packed;
+
+                    #[doc(hidden)]
+                    #[inline(always)]
+                    pub fn __alkahest_variant_size(idx: u32) -> ::alkahest::private::usize {
+                        <#variant_formula as ::alkahest::private::VariantFormula>::variant_size(idx)
+                    }
+
+                    #[doc(hidden)]
+                    #[inline(always)]
+                    pub fn __alkahest_write_variant<B>(idx: u32, sizes: &mut ::alkahest::private::Sizes, buffer: B) -> ::alkahest::private::Result<(), B::Error>
+                    where
+                        B: ::alkahest::private::Buffer,
+                    {
+                        <#variant_formula as ::alkahest::private::VariantFormula>::write_variant(idx, sizes, buffer)
+                    }
+
+                    #[doc(hidden)]
+                    #[inline(always)]
+                    pub fn __alkahest_read_variant(de: &mut ::alkahest::private::Deserializer) -> ::alkahest::private::Result<u32, ::alkahest::private::DeserializeError> {
+                        <#variant_formula as ::alkahest::private::VariantFormula>::read_variant(de)
+                    }
+
                     #[doc(hidden)]
                     #[allow(dead_code, unused_variables)]
                     fn __alkahest_touch(&self) {
@@ -293,6 +616,80 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                         #(#construct_variants)*
                     }
                 }
+            };
+
+            if packed {
+                // Variant index of packed enum occupies as few bits as possible.
+                let bits = u64::BITS - variant_range.saturating_sub(1).leading_zeros();
+
+                tokens.extend(quote::quote! {
+                    impl ::alkahest::private::BitFormula for #ident {
+                        const BITS: ::alkahest::private::u32 = #bits;
+                    }
+
+                    impl ::alkahest::private::Formula for #ident {
+                        const MAX_STACK_SIZE: ::alkahest::private::Option<::alkahest::private::usize> =
+                            ::alkahest::private::Option::Some(::alkahest::private::bits_size(#bits));
+                        const EXACT_SIZE: ::alkahest::private::bool = true;
+                        const HEAPLESS: ::alkahest::private::bool = true;
+
+                        ::alkahest::private::with_alloc! {
+                            fn descriptor() -> ::alkahest::private::Descriptor {
+                                ::alkahest::private::Descriptor::Packed(::alkahest::private::Box::new(::alkahest::private::Descriptor::Enum {
+                                    name: ::alkahest::private::Into::into(#name),
+                                    non_exhaustive: false,
+                                    discriminant: ::alkahest::private::Box::new(::alkahest::private::Descriptor::Bits(#bits)),
+                                    variants: ::alkahest::private::Vec::from([#(
+                                        ::alkahest::private::Variant::new(#variant_ids, #variant_names, ::alkahest::private::Vec::new()),
+                                    )*]),
+                                }))
+                            }
+                        }
+                    }
+
+                    impl ::alkahest::private::BareFormula for #ident {}
+
+                    impl ::alkahest::private::DeserializeBits<#ident> for ::alkahest::private::Validated {
+                        #[inline(always)]
+                        fn deserialize_bits(__bits: ::alkahest::private::u128) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
+                            // Index of packed variant never exceeds `u32`.
+                            #[allow(clippy::cast_possible_truncation)]
+                            let __idx = __bits as ::alkahest::private::u32;
+                            match __idx {
+                                #(#ident::#variant_name_ids => ::alkahest::private::Result::Ok(::alkahest::private::Validated),)*
+                                // Packed enums are never non-exhaustive.
+                                invalid => ::alkahest::private::Result::Err(::alkahest::private::DeserializeError::WrongVariant(invalid)),
+                            }
+                        }
+                    }
+                });
+                tokens.extend(validate_packed(ident));
+                return Ok(tokens);
+            }
+
+            tokens.extend(quote::quote! {
+                impl #validate_impl_generics ::alkahest::private::Deserialize<'__de, #ident #formula_type_generics> for ::alkahest::private::Validated #validate_where_clause {
+                    #[inline(always)]
+                    fn deserialize(mut de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
+                        let variant_idx = <#ident #formula_type_generics>::__alkahest_read_variant(&mut de)?;
+                        match variant_idx {
+                            #(
+                                <#ident #formula_type_generics>::#variant_name_ids => {
+                                    #validate_variants
+                                    de.finish()?;
+                                    ::alkahest::private::Result::Ok(::alkahest::private::Validated)
+                                }
+                            )*
+                            #validate_unknown
+                        }
+                    }
+
+                    #[inline(always)]
+                    fn deserialize_in_place(&mut self, de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<(), ::alkahest::private::DeserializeError> {
+                        <Self as ::alkahest::private::Deserialize<'__de, #ident #formula_type_generics>>::deserialize(de)?;
+                        ::alkahest::private::Result::Ok(())
+                    }
+                }
 
                 impl #formula_impl_generics ::alkahest::private::Formula for #ident #formula_type_generics #formula_where_clause {
                     const MAX_STACK_SIZE: ::alkahest::private::Option<::alkahest::private::usize> = {
@@ -311,13 +708,18 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                             max_size = ::alkahest::private::max_size(max_size, var_size);
                         )*
 
-                        // #expand_size
-                        ::alkahest::private::sum_size(::alkahest::private::VARIANT_SIZE_OPT, max_size)
+                        ::alkahest::private::assert!(
+                            #variant_range <= <#variant_formula as ::alkahest::private::VariantFormula>::MAX_VARIANTS,
+                            "Too many variants for variant formula",
+                        );
+
+                        #expand_size
+                        ::alkahest::private::sum_size(<#variant_formula as ::alkahest::private::Formula>::MAX_STACK_SIZE, max_size)
                     };
 
                     #[allow(unused_assignments)]
-                    const EXACT_SIZE: ::alkahest::private::bool = true && {
-                        let mut exact = true;
+                    const EXACT_SIZE: ::alkahest::private::bool = !#non_exhaustive && {
+                        let mut exact = <#variant_formula as ::alkahest::private::Formula>::EXACT_SIZE;
                         let mut common_size = None;
                         #(
                             #(exact &= <#last_field_types as ::alkahest::private::Formula>::EXACT_SIZE;)*
@@ -341,10 +743,46 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                     };
 
                     const HEAPLESS: ::alkahest::private::bool = true #(#(&& <#all_field_types as ::alkahest::private::Formula>::HEAPLESS)*)*;
+
+                    ::alkahest::private::with_alloc! {
+                        fn descriptor() -> ::alkahest::private::Descriptor {
+                            ::alkahest::private::Descriptor::Enum {
+                                name: ::alkahest::private::Into::into(#name),
+                                non_exhaustive: #non_exhaustive,
+                                discriminant: ::alkahest::private::Box::new(<#variant_formula as ::alkahest::private::Formula>::descriptor()),
+                                variants: ::alkahest::private::Vec::from([#(
+                                    ::alkahest::private::Variant::new(#variant_ids, #variant_names, ::alkahest::private::Vec::from([#(
+                                        ::alkahest::private::Field::new(#field_names, <#all_field_types as ::alkahest::private::Formula>::descriptor()),
+                                    )*])),
+                                )*]),
+                            }
+                        }
+                    }
                 }
 
                 impl #formula_impl_generics ::alkahest::private::BareFormula for #ident #formula_type_generics #formula_where_clause {}
-            })
+            });
+
+            Ok(tokens)
+        }
+    }
+}
+
+/// Validation of packed formula reads its bits and checks them.
+fn validate_packed(ident: &syn::Ident) -> TokenStream {
+    quote::quote! {
+        impl<'__de> ::alkahest::private::Deserialize<'__de, #ident> for ::alkahest::private::Validated {
+            #[inline(always)]
+            fn deserialize(mut de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
+                let __bits = ::alkahest::private::read_bits::<#ident>(&mut de)?;
+                <Self as ::alkahest::private::DeserializeBits<#ident>>::deserialize_bits(__bits)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<(), ::alkahest::private::DeserializeError> {
+                <Self as ::alkahest::private::Deserialize<'__de, #ident>>::deserialize(de)?;
+                ::alkahest::private::Result::Ok(())
+            }
         }
     }
 }
@@ -3,6 +3,7 @@ extern crate proc_macro;
 mod attrs;
 mod deserialize;
 mod formula;
+mod packed;
 mod serialize;
 
 use proc_macro::TokenStream;
@@ -0,0 +1,299 @@
+//! Derive implementations for `#[alkahest(packed)]` types.
+//!
+//! Values of packed formulas are serialized as a single bit-field,
+//! so both `Serialize` and `Deserialize` go through
+//! `SerializeBits` and `DeserializeBits` implementations.
+
+use proc_macro2::TokenStream;
+
+use crate::{
+    attrs::{Args, Formula},
+    struct_field_order_checks,
+};
+
+fn formula_path(
+    input: &syn::DeriveInput,
+    variant: Option<&syn::Ident>,
+    formula: Option<Formula>,
+) -> syn::Result<(syn::Path, bool)> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "Packed type cannot be generic",
+        ));
+    }
+
+    if let Some(variant) = variant {
+        return Err(syn::Error::new_spanned(
+            variant,
+            "Packed type cannot be serialized as enum variant",
+        ));
+    }
+
+    let ident = &input.ident;
+    match formula {
+        None => Ok((syn::parse_quote!(#ident), false)),
+        Some(formula) => Ok((formula.path, true)),
+    }
+}
+
+fn packed_check(input: &syn::DeriveInput, formula_path: &syn::Path) -> TokenStream {
+    let error = format!(
+        "`{}` must be packed if and only if formula is packed",
+        input.ident
+    );
+    quote::quote! {
+        const { ::alkahest::private::assert!(#formula_path::__ALKAHEST_PACKED, #error) };
+    }
+}
+
+fn bindings(fields: &syn::Fields) -> (Vec<syn::Ident>, TokenStream, TokenStream) {
+    let bound_names = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| match &field.ident {
+            Some(ident) => ident.clone(),
+            None => quote::format_ident!("_{}", idx),
+        })
+        .collect::<Vec<_>>();
+
+    let (bind_names, bind_ref_names) = match fields {
+        syn::Fields::Named(_) => (
+            quote::quote! { { #(#bound_names),* } },
+            quote::quote! { { #(ref #bound_names),* } },
+        ),
+        syn::Fields::Unnamed(_) => (
+            quote::quote! { ( #(#bound_names),* ) },
+            quote::quote! { ( #(ref #bound_names),* ) },
+        ),
+        syn::Fields::Unit => (quote::quote! {}, quote::quote! {}),
+    };
+
+    (bound_names, bind_names, bind_ref_names)
+}
+
+fn enum_variants(data: &syn::DataEnum) -> syn::Result<Vec<&syn::Ident>> {
+    data.variants
+        .iter()
+        .map(|variant| {
+            if variant.fields.is_empty() {
+                Ok(&variant.ident)
+            } else {
+                Err(syn::Error::new_spanned(
+                    &variant.fields,
+                    "Variants of packed enum cannot have fields",
+                ))
+            }
+        })
+        .collect()
+}
+
+pub fn derive_serialize(input: &syn::DeriveInput, args: Args) -> syn::Result<TokenStream> {
+    let formula = args.serialize.or(args.common);
+    let (formula_path, check_fields) = formula_path(input, args.variant.as_ref(), formula)?;
+    let ident = &input.ident;
+
+    let mut checks = if check_fields {
+        packed_check(input, &formula_path)
+    } else {
+        TokenStream::new()
+    };
+
+    let (pack_value, pack_ref) = match &input.data {
+        syn::Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "Serialize cannot be derived for unions",
+            ))
+        }
+        syn::Data::Struct(data) => {
+            if check_fields {
+                checks.extend(struct_field_order_checks(data, None, ident, &formula_path));
+            }
+
+            let (bound_names, bind_names, bind_ref_names) = bindings(&data.fields);
+
+            let pack = quote::quote! {
+                let mut __bits = 0u128;
+                let mut __offset = 0u32;
+                #(
+                    let with_formula = ::alkahest::private::with_formula(|s: &#formula_path| match *s {
+                        #formula_path #bind_ref_names => #bound_names,
+                        _ => unreachable!(),
+                    });
+                    with_formula.pack_bits(#bound_names, &mut __bits, &mut __offset);
+                )*
+                __bits
+            };
+
+            (
+                quote::quote! {
+                    let #ident #bind_names = self;
+                    #pack
+                },
+                quote::quote! {
+                    let #ident #bind_ref_names = *self;
+                    #pack
+                },
+            )
+        }
+        syn::Data::Enum(data) => {
+            let variants = enum_variants(data)?;
+            let variant_name_ids = variants
+                .iter()
+                .map(|v| quote::format_ident!("__ALKAHEST_FORMULA_VARIANT_{}_IDX", v))
+                .collect::<Vec<_>>();
+
+            let pack = quote::quote! {
+                #(
+                    #ident::#variants => ::alkahest::private::u128::from(#formula_path::#variant_name_ids),
+                )*
+            };
+
+            (
+                quote::quote! { match self { #pack } },
+                quote::quote! { match *self { #pack } },
+            )
+        }
+    };
+
+    Ok(quote::quote! {
+        impl ::alkahest::private::SerializeBits<#formula_path> for #ident {
+            #[inline(always)]
+            #[allow(unused_mut)]
+            fn serialize_bits(self) -> ::alkahest::private::u128 {
+                #checks
+                #pack_value
+            }
+        }
+
+        impl ::alkahest::private::SerializeBits<#formula_path> for &#ident {
+            #[inline(always)]
+            #[allow(unused_mut)]
+            fn serialize_bits(self) -> ::alkahest::private::u128 {
+                #checks
+                #pack_ref
+            }
+        }
+
+        impl ::alkahest::private::Serialize<#formula_path> for #ident {
+            #[inline(always)]
+            fn serialize<B>(self, __sizes: &mut ::alkahest::private::Sizes, __buffer: B) -> ::alkahest::private::Result<(), B::Error>
+            where
+                B: ::alkahest::private::Buffer,
+            {
+                let __bits = <Self as ::alkahest::private::SerializeBits<#formula_path>>::serialize_bits(self);
+                ::alkahest::private::write_bits::<#formula_path, B>(__bits, __sizes, __buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> ::alkahest::private::Option<::alkahest::private::Sizes> {
+                ::alkahest::private::Option::Some(::alkahest::private::Sizes::with_stack(
+                    ::alkahest::private::bits_size(<#formula_path as ::alkahest::private::BitFormula>::BITS),
+                ))
+            }
+        }
+
+        impl ::alkahest::private::Serialize<#formula_path> for &#ident {
+            #[inline(always)]
+            fn serialize<B>(self, __sizes: &mut ::alkahest::private::Sizes, __buffer: B) -> ::alkahest::private::Result<(), B::Error>
+            where
+                B: ::alkahest::private::Buffer,
+            {
+                let __bits = <Self as ::alkahest::private::SerializeBits<#formula_path>>::serialize_bits(self);
+                ::alkahest::private::write_bits::<#formula_path, B>(__bits, __sizes, __buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> ::alkahest::private::Option<::alkahest::private::Sizes> {
+                ::alkahest::private::Option::Some(::alkahest::private::Sizes::with_stack(
+                    ::alkahest::private::bits_size(<#formula_path as ::alkahest::private::BitFormula>::BITS),
+                ))
+            }
+        }
+    })
+}
+
+pub fn derive_deserialize(input: &syn::DeriveInput, args: Args) -> syn::Result<TokenStream> {
+    let formula = args.deserialize.or(args.common);
+    let (formula_path, check_fields) = formula_path(input, args.variant.as_ref(), formula)?;
+    let ident = &input.ident;
+
+    let mut checks = if check_fields {
+        packed_check(input, &formula_path)
+    } else {
+        TokenStream::new()
+    };
+
+    let unpack = match &input.data {
+        syn::Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "Deserialize cannot be derived for unions",
+            ))
+        }
+        syn::Data::Struct(data) => {
+            if check_fields {
+                checks.extend(struct_field_order_checks(data, None, ident, &formula_path));
+            }
+
+            let (bound_names, bind_names, bind_ref_names) = bindings(&data.fields);
+
+            quote::quote! {
+                let mut __offset = 0u32;
+                #(
+                    let with_formula = ::alkahest::private::with_formula(|s: &#formula_path| match *s {
+                        #formula_path #bind_ref_names => #bound_names,
+                        _ => unreachable!(),
+                    });
+                    let #bound_names = with_formula.unpack_bits(__bits, &mut __offset)?;
+                )*
+                ::alkahest::private::Result::Ok(#ident #bind_names)
+            }
+        }
+        syn::Data::Enum(data) => {
+            let variants = enum_variants(data)?;
+            let variant_name_ids = variants
+                .iter()
+                .map(|v| quote::format_ident!("__ALKAHEST_FORMULA_VARIANT_{}_IDX", v))
+                .collect::<Vec<_>>();
+
+            quote::quote! {
+                // Index of packed variant never exceeds `u32`.
+                #[allow(clippy::cast_possible_truncation)]
+                let __idx = __bits as ::alkahest::private::u32;
+                #(
+                    if __idx == #formula_path::#variant_name_ids {
+                        return ::alkahest::private::Result::Ok(#ident::#variants);
+                    }
+                )*
+                ::alkahest::private::Result::Err(::alkahest::private::DeserializeError::WrongVariant(__idx))
+            }
+        }
+    };
+
+    Ok(quote::quote! {
+        impl ::alkahest::private::DeserializeBits<#formula_path> for #ident {
+            #[inline(always)]
+            #[allow(unused_mut, unused_variables)]
+            fn deserialize_bits(__bits: ::alkahest::private::u128) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
+                #checks
+                #unpack
+            }
+        }
+
+        impl<'__de> ::alkahest::private::Deserialize<'__de, #formula_path> for #ident {
+            #[inline(always)]
+            fn deserialize(mut de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<Self, ::alkahest::private::DeserializeError> {
+                let __bits = ::alkahest::private::read_bits::<#formula_path>(&mut de)?;
+                <Self as ::alkahest::private::DeserializeBits<#formula_path>>::deserialize_bits(__bits)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: ::alkahest::private::Deserializer<'__de>) -> ::alkahest::private::Result<(), ::alkahest::private::DeserializeError> {
+                *self = <Self as ::alkahest::private::Deserialize<'__de, #formula_path>>::deserialize(de)?;
+                ::alkahest::private::Result::Ok(())
+            }
+        }
+    })
+}
@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use proc_macro2::TokenStream;
 
 use crate::{
-    attrs::{parse_attributes, path_make_expr_style, Args, Formula},
+    attrs::{find_unknown_variant, parse_attributes, path_make_expr_style, Args, Formula},
     enum_field_order_checks, filter_type_param, is_generic_ty, struct_field_order_checks,
 };
 
@@ -338,6 +338,10 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
     let input = syn::parse::<syn::DeriveInput>(input)?;
     let args = parse_attributes(&input.attrs)?;
 
+    if args.packed.is_some() {
+        return crate::packed::derive_serialize(&input, args);
+    }
+
     let ident = &input.ident;
     let generics = &input.generics;
     let (_impl_generics, type_generics, _where_clause) = generics.split_for_impl();
@@ -348,6 +352,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
             "Serialize cannot be derived for unions",
         )),
         syn::Data::Struct(data) => {
+            let extensible = args.extensible.is_some();
             let cfg = Config::for_struct(args, &data, ident, generics);
 
             let field_checks = if cfg.check_fields {
@@ -430,21 +435,37 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                 Some(v) => quote::quote! { :: #v },
             };
 
-            let start_stack_size = match &cfg.variant {
+            let variant_name_idx = cfg
+                .variant
+                .as_ref()
+                .map(|v| quote::format_ident!("__ALKAHEST_FORMULA_VARIANT_{}_IDX", v));
+            let start_stack_size = |formula_path: &syn::Path| match &variant_name_idx {
                 None => quote::quote! { 0usize },
-                Some(_) => quote::quote! { ::alkahest::private::VARIANT_SIZE },
+                Some(variant_name_idx) => {
+                    quote::quote! { #formula_path::__alkahest_variant_size(#formula_path::#variant_name_idx) }
+                }
+            };
+
+            // All fields of extensible structure are written with their sizes.
+            let (is_variant, check_fields) = (cfg.variant.is_some(), cfg.check_fields);
+            let is_extensible = |formula_path: &syn::Path| match (is_variant, check_fields) {
+                (true, _) => quote::quote! { false },
+                (false, true) => quote::quote! { #formula_path::__ALKAHEST_EXTENSIBLE },
+                (false, false) => quote::quote! { #extensible },
             };
 
             let mut tokens = TokenStream::new();
             {
                 let formula_path = &cfg.owned.path;
+                let extensible = is_extensible(formula_path);
+                let start_stack_size = start_stack_size(formula_path);
 
                 let write_variant = match &cfg.variant {
                     None => quote::quote! {},
                     Some(v) => {
                         let variant_name_idx =
                             quote::format_ident!("__ALKAHEST_FORMULA_VARIANT_{}_IDX", v);
-                        quote::quote! { ::alkahest::private::write_exact_size_field::<u32, u32, _>(#formula_path::#variant_name_idx, __sizes, __buffer.reborrow())?; }
+                        quote::quote! { #formula_path::__alkahest_write_variant(#formula_path::#variant_name_idx, __sizes, __buffer.reborrow())?; }
                     }
                 };
 
@@ -452,9 +473,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
 
                 generics.lt_token = generics.lt_token.or(cfg.owned.generics.lt_token);
                 generics.gt_token = generics.gt_token.or(cfg.owned.generics.gt_token);
-                generics
-                    .params
-                    .extend(cfg.owned.generics.params.into_iter());
+                generics.params.extend(cfg.owned.generics.params);
 
                 if let Some(where_clause) = cfg.owned.generics.where_clause {
                     generics
@@ -482,7 +501,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                     #formula_path #with_variant #bind_ref_names => #bound_names,
                                     _ => unreachable!(),
                                 });
-                                with_formula.write_field(#bound_names, __sizes, __buffer.reborrow(), #field_count == 1 + #field_ids)?;
+                                with_formula.write_field(#bound_names, __sizes, __buffer.reborrow(), !#extensible && #field_count == 1 + #field_ids)?;
                             )*
                             Ok(())
                         }
@@ -501,7 +520,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                     #formula_path #with_variant #bind_ref_names => #bound_names,
                                     _ => unreachable!(),
                                 });
-                                __total += with_formula.size_hint(#bound_names, #field_count == 1 + #field_ids)?;
+                                __total += with_formula.size_hint(#bound_names, !#extensible && #field_count == 1 + #field_ids)?;
                             )*
                             Some(__total)
                         }
@@ -511,6 +530,8 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
 
             if let Some(reference) = cfg.reference {
                 let formula_path = &reference.path;
+                let extensible = is_extensible(formula_path);
+                let start_stack_size = start_stack_size(formula_path);
                 let mut generics = input.generics.clone();
 
                 let write_variant = match &cfg.variant {
@@ -518,15 +539,13 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                     Some(v) => {
                         let variant_name_idx =
                             quote::format_ident!("__ALKAHEST_FORMULA_VARIANT_{}_IDX", v);
-                        quote::quote! { ::alkahest::private::write_exact_size_field::<u32, u32, _>(#formula_path::#variant_name_idx, __sizes, __buffer.reborrow())?; }
+                        quote::quote! { #formula_path::__alkahest_write_variant(#formula_path::#variant_name_idx, __sizes, __buffer.reborrow())?; }
                     }
                 };
 
                 generics.lt_token = generics.lt_token.or(reference.generics.lt_token);
                 generics.gt_token = generics.gt_token.or(reference.generics.gt_token);
-                generics
-                    .params
-                    .extend(reference.generics.params.into_iter());
+                generics.params.extend(reference.generics.params);
 
                 if let Some(where_clause) = reference.generics.where_clause {
                     generics
@@ -554,7 +573,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                     #formula_path #with_variant #bind_ref_names => #bound_names,
                                     _ => unreachable!(),
                                 });
-                                with_formula.write_field(#bound_names, __sizes, __buffer.reborrow(), #field_count == 1 + #field_ids)?;
+                                with_formula.write_field(#bound_names, __sizes, __buffer.reborrow(), !#extensible && #field_count == 1 + #field_ids)?;
                             )*
                             Ok(())
                         }
@@ -573,7 +592,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                                     #formula_path #with_variant #bind_ref_names => #bound_names,
                                     _ => unreachable!(),
                                 });
-                                __total += with_formula.size_hint(&#bound_names, #field_count == 1 + #field_ids)?;
+                                __total += with_formula.size_hint(&#bound_names, !#extensible && #field_count == 1 + #field_ids)?;
                             )*
                             Some(__total)
                         }
@@ -584,6 +603,13 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
             Ok(tokens)
         }
         syn::Data::Enum(data) => {
+            if let Some(unknown) = find_unknown_variant(&data)? {
+                return Err(syn::Error::new_spanned(
+                    unknown,
+                    "Unknown variant cannot be serialized",
+                ));
+            }
+
             let cfg = Config::for_enum(args, &data, ident, generics);
 
             let field_checks = if cfg.check_fields {
@@ -698,9 +724,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
 
                 generics.lt_token = generics.lt_token.or(cfg.owned.generics.lt_token);
                 generics.gt_token = generics.gt_token.or(cfg.owned.generics.gt_token);
-                generics
-                    .params
-                    .extend(cfg.owned.generics.params.into_iter());
+                generics.params.extend(cfg.owned.generics.params);
 
                 if let Some(where_clause) = cfg.owned.generics.where_clause {
                     generics
@@ -723,7 +747,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                             match self {
                                 #(
                                     #ident::#variant_names #bind_names => {
-                                        ::alkahest::private::write_exact_size_field::<u32, u32, _>(#formula_path::#variant_name_ids, __sizes, __buffer.reborrow())?;
+                                        #formula_path::__alkahest_write_variant(#formula_path::#variant_name_ids, __sizes, __buffer.reborrow())?;
                                         #(
                                             let with_formula = ::alkahest::private::with_formula(|s: &#formula_path| match *s {
                                                 #formula_path::#variant_names #bind_ref_names => #bound_names,
@@ -747,7 +771,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                             match *self {
                                 #(
                                     #ident::#variant_names #bind_ref_names => {
-                                        let mut __total = ::alkahest::private::Sizes::with_stack(::alkahest::private::VARIANT_SIZE);
+                                        let mut __total = ::alkahest::private::Sizes::with_stack(#formula_path::__alkahest_variant_size(#formula_path::#variant_name_ids));
                                         #(
                                             let with_formula = ::alkahest::private::with_formula(|s: &#formula_path| match *s {
                                                 #formula_path::#variant_names #bind_ref_names => #bound_names,
@@ -770,9 +794,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
 
                 generics.lt_token = generics.lt_token.or(reference.generics.lt_token);
                 generics.gt_token = generics.gt_token.or(reference.generics.gt_token);
-                generics
-                    .params
-                    .extend(reference.generics.params.into_iter());
+                generics.params.extend(reference.generics.params);
 
                 if let Some(where_clause) = reference.generics.where_clause {
                     generics
@@ -795,7 +817,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                             match *self {
                                 #(
                                     #ident::#variant_names #bind_ref_names => {
-                                        ::alkahest::private::write_exact_size_field::<u32, u32, _>(#formula_path::#variant_name_ids, __sizes, __buffer.reborrow())?;
+                                        #formula_path::__alkahest_write_variant(#formula_path::#variant_name_ids, __sizes, __buffer.reborrow())?;
                                         #(
                                             let with_formula = ::alkahest::private::with_formula(|s: &#formula_path| match *s {
                                                 #formula_path::#variant_names #bind_ref_names => #bound_names,
@@ -819,7 +841,7 @@ pub fn derive(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
                             match **self {
                                 #(
                                     #ident::#variant_names #bind_ref_names => {
-                                        let mut __total = ::alkahest::private::Sizes::with_stack(::alkahest::private::VARIANT_SIZE);
+                                        let mut __total = ::alkahest::private::Sizes::with_stack(#formula_path::__alkahest_variant_size(#formula_path::#variant_name_ids));
                                         #(
                                             let with_formula = ::alkahest::private::with_formula(|s: &#formula_path| match *s {
                                                 #formula_path::#variant_names #bind_ref_names => #bound_names,
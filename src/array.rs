@@ -4,8 +4,12 @@ use crate::{
     formula::{repeat_size, BareFormula, Formula},
     iter::{owned_iter_fast_sizes, ref_iter_fast_sizes},
     serialize::{write_array, write_slice, Serialize, Sizes},
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 impl<F, const N: usize> Formula for [F; N]
 where
     F: Formula,
@@ -13,6 +17,12 @@ where
     const MAX_STACK_SIZE: Option<usize> = repeat_size(F::MAX_STACK_SIZE, N);
     const EXACT_SIZE: bool = true; // All elements are padded.
     const HEAPLESS: bool = F::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Array(F::descriptor().into(), N)
+    }
 }
 
 impl<F, const N: usize> BareFormula for [F; N] where F: Formula {}
@@ -105,6 +115,7 @@ where
             *slot = Some(de.read_value::<F, T>(false)?);
             Ok(())
         })?;
+        de.finish()?;
         let value = opts.map(Option::unwrap);
         Ok(value)
     }
@@ -113,6 +124,27 @@ where
     fn deserialize_in_place(&mut self, mut de: Deserializer<'de>) -> Result<(), DeserializeError> {
         self.iter_mut()
             .try_for_each(|elem| de.read_in_place::<F, T>(elem, false))?;
+        de.finish()
+    }
+}
+
+impl<'de, F, const N: usize> Deserialize<'de, [F; N]> for Validated
+where
+    F: Formula,
+    Validated: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        for _ in 0..N {
+            de.read_value::<F, Validated>(false)?;
+        }
+        de.finish()?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, [F; N]>>::deserialize(de)?;
         Ok(())
     }
 }
@@ -133,11 +165,11 @@ where
 
 /// Returns the size of the serialized data if it can be determined fast.
 #[inline(always)]
-pub fn ref_array_fast_sizes<'a, F, I, T: 'a>(iter: I) -> Option<Sizes>
+pub fn ref_array_fast_sizes<'a, F, I, T>(iter: I) -> Option<Sizes>
 where
     F: Formula + ?Sized,
     I: Iterator<Item = &'a T>,
-    T: Serialize<F>,
+    T: Serialize<F> + 'a,
 {
     match (F::HEAPLESS, F::MAX_STACK_SIZE) {
         (true, Some(0)) => Some(Sizes::ZERO),
@@ -7,6 +7,9 @@ use crate::{
     serialize::{Serialize, Sizes},
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 /// Formula type that mirrors specified formula `F`.
 /// It can be used to turn unsized field type into sized one,
 /// keeping the same formula.
@@ -51,6 +54,12 @@ where
     const MAX_STACK_SIZE: Option<usize> = F::MAX_STACK_SIZE;
     const EXACT_SIZE: bool = F::EXACT_SIZE;
     const HEAPLESS: bool = F::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        F::descriptor()
+    }
 }
 
 impl<F, T> Serialize<As<F>> for T
@@ -1,12 +1,18 @@
-use std::{io::Cursor, marker::PhantomData, mem::size_of};
+use std::{
+    io::{self, Cursor},
+    marker::PhantomData,
+    mem::size_of,
+};
 
 use crate::{
     buffer::Buffer,
     bytes::Bytes,
+    descriptor::Descriptor,
     deserialize::{Deserialize, DeserializeError, Deserializer},
     formula::{reference_size, Formula},
     serialize::{write_reference, Serialize, Sizes},
     size::{FixedUsize, FixedUsizeType},
+    validate::Validated,
 };
 
 /// A formula that can be used to serialize and deserialize data
@@ -21,6 +27,11 @@ impl Formula for Bincode {
     const MAX_STACK_SIZE: Option<usize> = Some(reference_size::<Bytes>());
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = false;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("Bincode")
+    }
 }
 
 impl<T> Serialize<Bincode> for T
@@ -39,25 +50,48 @@ where
             Err(err) => panic!("Bincode serialization error: {}", err),
         };
 
-        let Ok(size) = FixedUsizeType::try_from(size) else {
-            panic!("Bincode serialization uses more that `FixedUsize::MAX` bytes");
+        let Ok(size) = usize::try_from(size) else {
+            panic!("Bincode serialization uses more that `usize::MAX` bytes");
         };
 
         let Ok(size) = FixedUsize::try_from(size) else {
-            panic!("Bincode serialization uses more that `usize::MAX` bytes");
+            panic!("Bincode serialization uses more that `FixedUsize::MAX` bytes");
         };
         let size: usize = size.into();
 
-        match buffer.reserve_heap(sizes.heap, sizes.stack, size) {
+        let reserved = match buffer.reserve_heap(sizes.heap, sizes.stack, size) {
             Err(err) => return Err(err),
-            Ok([]) => {} // Nothing to do.
+            Ok([]) => false,
             Ok(bytes) => {
                 let mut cursor = Cursor::new(&mut bytes[sizes.heap..]);
                 if let Err(err) = bincode::Options::serialize_into(options, &mut cursor, &self) {
                     panic!("Bincode serialization error: {}", err);
                 };
                 assert_eq!(cursor.position(), size as u64);
+                true
+            }
+        };
+
+        if !reserved {
+            // Buffer cannot provide heap space.
+            // Write through the stack and move to the heap.
+            {
+                let mut writer = StackWriter {
+                    buffer: buffer.reborrow(),
+                    heap: sizes.heap,
+                    stack: sizes.stack,
+                    remaining: size,
+                    error: None,
+                };
+                if let Err(err) = bincode::Options::serialize_into(options, &mut writer, &self) {
+                    match writer.error {
+                        Some(err) => return Err(err),
+                        None => panic!("Bincode serialization error: {}", err),
+                    }
+                }
+                assert_eq!(writer.remaining, 0);
             }
+            buffer.move_to_heap(sizes.heap, sizes.stack + size, size);
         }
 
         sizes.heap += size;
@@ -120,6 +154,11 @@ impl<T> Formula for Bincoded<T> {
     const MAX_STACK_SIZE: Option<usize> = Some(size_of::<[FixedUsizeType; 2]>());
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = false;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("Bincode")
+    }
 }
 
 impl<T> Serialize<Bincoded<T>> for T
@@ -157,3 +196,58 @@ where
         <T as Deserialize<'de, Bincode>>::deserialize_in_place(self, de)
     }
 }
+
+/// Only `Bincoded<T>` can be validated,
+/// since `Bincode` does not specify type of the value.
+impl<'de, T> Deserialize<'de, Bincoded<T>> for Validated
+where
+    T: serde::Deserialize<'de>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <T as Deserialize<'de, Bincode>>::deserialize(de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Bincoded<T>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Writer that fills region of the stack
+/// at the top of `stack` in the order of bytes.
+struct StackWriter<B: Buffer> {
+    buffer: B,
+    heap: usize,
+    stack: usize,
+
+    /// Size of the region left to write.
+    remaining: usize,
+    error: Option<B::Error>,
+}
+
+impl<B> io::Write for StackWriter<B>
+where
+    B: Buffer,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.remaining);
+        self.remaining -= len;
+        match self
+            .buffer
+            .write_stack(self.heap, self.stack + self.remaining, &buf[..len])
+        {
+            Ok(()) => Ok(len),
+            Err(err) => {
+                self.error = Some(err);
+                Err(io::ErrorKind::Other.into())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
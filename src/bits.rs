@@ -0,0 +1,809 @@
+//!
+//! This module provides formulas for bit-packed data.
+//!
+
+use core::{cmp::min, marker::PhantomData};
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{BareFormula, Formula},
+    iter::SerIter,
+    serialize::{write_bytes, write_exact_size_field, Serialize, Sizes},
+    size::{FixedUsize, SIZE_STACK},
+    validate::Validated,
+};
+
+#[cfg(feature = "alloc")]
+use {crate::descriptor::Descriptor, alloc::boxed::Box, alloc::vec::Vec};
+
+/// Formula for unsigned integers that fit into `N` bits.
+///
+/// Standalone `Bits<N>` occupies `N` bits rounded up to whole bytes.
+/// Within packed formulas and [`BitSlice`] it occupies exactly `N` bits.
+/// `N` must not exceed 128.
+///
+/// Serializing a value that does not fit into `N` bits
+/// is a bug that causes panic in debug builds
+/// and silently drops higher bits otherwise.
+/// Deserializing into a type that can't hold the value
+/// fails with [`DeserializeError::IntegerOverflow`].
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+///
+/// let mut buffer = [0u8; 16];
+/// let size = serialize::<Bits<12>, u16>(0xABC, &mut buffer).unwrap();
+/// assert_eq!(<Bits<12>>::MAX_STACK_SIZE, Some(2));
+///
+/// let value = deserialize::<Bits<12>, u16>(&buffer[..size]).unwrap().0;
+/// assert_eq!(value, 0xABC);
+///
+/// let err = deserialize::<Bits<12>, u8>(&buffer[..size]).unwrap_err();
+/// assert!(matches!(err, DeserializeError::IntegerOverflow));
+/// ```
+pub struct Bits<const N: u32>;
+
+/// Formula with fixed bit width.
+///
+/// Fields of packed formulas derived with `#[alkahest(packed)]`
+/// and elements of [`BitSlice`] must implement this trait.
+/// Implemented for `bool`, integers, [`Bits<N>`]
+/// and formulas derived with `#[alkahest(packed)]`.
+///
+/// Packed formulas are validated field by field,
+/// so formulas used as their fields must also implement
+/// [`DeserializeBits`] for [`Validated`](crate::Validated).
+pub trait BitFormula: Formula {
+    /// Number of bits the value occupies in packed data.
+    const BITS: u32;
+}
+
+/// Trait for types serializable into bits with [`BitFormula`].
+pub trait SerializeBits<F: BitFormula + ?Sized> {
+    /// Returns bits of the value.
+    /// Only the lowest `F::BITS` bits may be set.
+    fn serialize_bits(self) -> u128;
+}
+
+/// Trait for types deserializable from bits with [`BitFormula`].
+pub trait DeserializeBits<F: BitFormula + ?Sized>: Sized {
+    /// Restores value from bits.
+    /// Only the lowest `F::BITS` bits may be set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if bits do not represent a valid value.
+    fn deserialize_bits(bits: u128) -> Result<Self, DeserializeError>;
+}
+
+/// Returns number of bytes required to store `bits` bits.
+#[must_use]
+#[inline(always)]
+pub const fn bits_size(bits: u32) -> usize {
+    (bits as usize).div_ceil(8)
+}
+
+/// Returns mask of the lowest `bits` bits.
+#[must_use]
+#[inline(always)]
+pub const fn bits_mask(bits: u32) -> u128 {
+    if bits >= u128::BITS {
+        u128::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// Writes bits of packed formula `F` as little-endian bytes.
+#[inline(always)]
+pub fn write_bits<F, B>(bits: u128, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+where
+    F: BitFormula + ?Sized,
+    B: Buffer,
+{
+    write_bytes(&bits.to_le_bytes()[..bits_size(F::BITS)], sizes, buffer)
+}
+
+/// Reads bits of packed formula `F` from little-endian bytes.
+///
+/// # Errors
+///
+/// Returns `DeserializeError` if not enough bytes on stack
+/// and [`DeserializeError::NonCanonical`] if deserializer is strict
+/// and bits beyond `F::BITS` are set.
+#[inline(always)]
+pub fn read_bits<F>(de: &mut Deserializer) -> Result<u128, DeserializeError>
+where
+    F: BitFormula + ?Sized,
+{
+    let bytes = de.read_bytes(bits_size(F::BITS))?;
+    let mut array = [0; 16];
+    array[..bytes.len()].copy_from_slice(bytes);
+    let bits = u128::from_le_bytes(array);
+
+    // Unused bits are written as zeros.
+    if de.is_strict() && bits & !bits_mask(F::BITS) != 0 {
+        return Err(DeserializeError::NonCanonical);
+    }
+    Ok(bits & bits_mask(F::BITS))
+}
+
+impl BitFormula for bool {
+    const BITS: u32 = 1;
+}
+
+impl SerializeBits<bool> for bool {
+    #[inline(always)]
+    fn serialize_bits(self) -> u128 {
+        u128::from(self)
+    }
+}
+
+impl SerializeBits<bool> for &bool {
+    #[inline(always)]
+    fn serialize_bits(self) -> u128 {
+        u128::from(*self)
+    }
+}
+
+impl<T> DeserializeBits<bool> for T
+where
+    T: From<bool>,
+{
+    #[inline(always)]
+    fn deserialize_bits(bits: u128) -> Result<Self, DeserializeError> {
+        Ok(T::from(bits != 0))
+    }
+}
+
+impl DeserializeBits<bool> for Validated {
+    #[inline(always)]
+    fn deserialize_bits(_bits: u128) -> Result<Self, DeserializeError> {
+        Ok(Validated)
+    }
+}
+
+macro_rules! impl_bit_int {
+    ($($ty:ident as $unsigned:ident),* $(,)?) => {$(
+        impl BitFormula for $ty {
+            const BITS: u32 = $ty::BITS;
+        }
+
+        impl SerializeBits<$ty> for $ty {
+            #[inline(always)]
+            fn serialize_bits(self) -> u128 {
+                u128::from(self as $unsigned)
+            }
+        }
+
+        impl SerializeBits<$ty> for &$ty {
+            #[inline(always)]
+            fn serialize_bits(self) -> u128 {
+                u128::from(*self as $unsigned)
+            }
+        }
+
+        impl<T> DeserializeBits<$ty> for T
+        where
+            T: From<$ty>,
+        {
+            #[inline(always)]
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            fn deserialize_bits(bits: u128) -> Result<Self, DeserializeError> {
+                Ok(T::from(bits as $unsigned as $ty))
+            }
+        }
+
+        impl DeserializeBits<$ty> for Validated {
+            #[inline(always)]
+            fn deserialize_bits(_bits: u128) -> Result<Self, DeserializeError> {
+                Ok(Validated)
+            }
+        }
+    )*};
+}
+
+impl_bit_int! {
+    u8 as u8,
+    u16 as u16,
+    u32 as u32,
+    u64 as u64,
+    u128 as u128,
+    i8 as u8,
+    i16 as u16,
+    i32 as u32,
+    i64 as u64,
+    i128 as u128,
+}
+
+impl<const N: u32> Formula for Bits<N> {
+    const MAX_STACK_SIZE: Option<usize> = Some(bits_size(<Self as BitFormula>::BITS));
+    const EXACT_SIZE: bool = true;
+    const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Bits(N)
+    }
+}
+
+impl<const N: u32> BareFormula for Bits<N> {}
+
+impl<const N: u32> BitFormula for Bits<N> {
+    const BITS: u32 = {
+        assert!(N <= u128::BITS, "`Bits<N>` cannot exceed 128 bits");
+        N
+    };
+}
+
+macro_rules! impl_bits_value {
+    ($($ty:ident)*) => {$(
+        impl<const N: u32> SerializeBits<Bits<N>> for $ty {
+            #[inline(always)]
+            fn serialize_bits(self) -> u128 {
+                let bits = u128::from(self);
+                debug_assert!(
+                    bits <= bits_mask(<Bits<N> as BitFormula>::BITS),
+                    "Value does not fit into `Bits<N>`"
+                );
+                bits & bits_mask(<Bits<N> as BitFormula>::BITS)
+            }
+        }
+
+        impl<const N: u32> SerializeBits<Bits<N>> for &$ty {
+            #[inline(always)]
+            fn serialize_bits(self) -> u128 {
+                <$ty as SerializeBits<Bits<N>>>::serialize_bits(*self)
+            }
+        }
+
+        impl<const N: u32> Serialize<Bits<N>> for $ty {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                let bits = <$ty as SerializeBits<Bits<N>>>::serialize_bits(self);
+                write_bits::<Bits<N>, B>(bits, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes::with_stack(bits_size(<Bits<N> as BitFormula>::BITS)))
+            }
+        }
+
+        impl<const N: u32> Serialize<Bits<N>> for &$ty {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                <$ty as Serialize<Bits<N>>>::serialize(*self, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes::with_stack(bits_size(<Bits<N> as BitFormula>::BITS)))
+            }
+        }
+    )*};
+}
+
+impl_bits_value!(u8 u16 u32 u64 u128);
+
+impl<T, const N: u32> DeserializeBits<Bits<N>> for T
+where
+    T: TryFrom<u128>,
+{
+    #[inline(always)]
+    fn deserialize_bits(bits: u128) -> Result<Self, DeserializeError> {
+        T::try_from(bits).map_err(|_| DeserializeError::IntegerOverflow)
+    }
+}
+
+impl<'de, T, const N: u32> Deserialize<'de, Bits<N>> for T
+where
+    T: TryFrom<u128>,
+{
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let bits = read_bits::<Bits<N>>(&mut de)?;
+        <T as DeserializeBits<Bits<N>>>::deserialize_bits(bits)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <T as Deserialize<'de, Bits<N>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl<const N: u32> DeserializeBits<Bits<N>> for Validated {
+    #[inline(always)]
+    fn deserialize_bits(_bits: u128) -> Result<Self, DeserializeError> {
+        Ok(Validated)
+    }
+}
+
+impl<const N: u32> Deserialize<'_, Bits<N>> for Validated {
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+        read_bits::<Bits<N>>(&mut de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<Bits<N>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Formula for sequences of bit formula `F` elements
+/// packed without padding between elements.
+///
+/// Elements are packed into little-endian 64-bit words
+/// followed by the number of elements.
+/// Like `[F]` this formula is unsized.
+///
+/// Can be serialized from slices, arrays, `Vec` and [`SerIter`].
+/// Can be deserialized into `Vec` and [`LazyBitSlice`]
+/// that provides constant-time access to any element.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+///
+/// let flags = [true, false, false, true, true];
+///
+/// let mut buffer = [0u8; 64];
+/// let size = serialize::<BitSlice<bool>, _>(&flags, &mut buffer).unwrap();
+///
+/// let (lazy, _) = deserialize::<BitSlice<bool>, LazyBitSlice<bool>>(&buffer[..size]).unwrap();
+/// assert_eq!(lazy.len(), 5);
+/// assert_eq!(lazy.get::<bool>(3).unwrap(), Some(true));
+/// assert_eq!(lazy.get::<bool>(5).unwrap(), None);
+/// ```
+pub struct BitSlice<F> {
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+impl<F> Formula for BitSlice<F>
+where
+    F: BitFormula,
+{
+    const MAX_STACK_SIZE: Option<usize> = None;
+    const EXACT_SIZE: bool = true;
+    const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::BitSlice(Box::new(F::descriptor()))
+    }
+}
+
+impl<F> BareFormula for BitSlice<F> where F: BitFormula {}
+
+const WORD_SIZE: usize = 8;
+const WORD_BITS: u32 = u64::BITS;
+
+#[inline(always)]
+fn bit_slice_size<F>(len: usize) -> usize
+where
+    F: BitFormula,
+{
+    (len * F::BITS as usize).div_ceil(WORD_BITS as usize) * WORD_SIZE + SIZE_STACK
+}
+
+#[inline]
+fn write_bit_slice<F, I, B>(iter: I, sizes: &mut Sizes, mut buffer: B) -> Result<(), B::Error>
+where
+    F: BitFormula,
+    I: Iterator,
+    I::Item: SerializeBits<F>,
+    B: Buffer,
+{
+    let mut word = 0u64;
+    let mut filled = 0;
+    let mut len = 0usize;
+
+    for value in iter {
+        let mut bits = value.serialize_bits() & bits_mask(F::BITS);
+        let mut left = F::BITS;
+
+        while left > 0 {
+            let take = min(WORD_BITS - filled, left);
+
+            #[allow(clippy::cast_possible_truncation)]
+            let chunk = (bits & bits_mask(take)) as u64;
+            word |= chunk << filled;
+            bits = bits.checked_shr(take).unwrap_or(0);
+            filled += take;
+            left -= take;
+
+            if filled == WORD_BITS {
+                write_bytes(&word.to_le_bytes(), sizes, buffer.reborrow())?;
+                word = 0;
+                filled = 0;
+            }
+        }
+
+        len += 1;
+    }
+
+    if filled > 0 {
+        write_bytes(&word.to_le_bytes(), sizes, buffer.reborrow())?;
+    }
+
+    write_exact_size_field::<FixedUsize, usize, _>(len, sizes, buffer)
+}
+
+impl<'ser, F, T> Serialize<BitSlice<F>> for &'ser [T]
+where
+    F: BitFormula,
+    &'ser T: SerializeBits<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bit_slice::<F, _, B>(self.iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(bit_slice_size::<F>(self.len())))
+    }
+}
+
+impl<F, T, const N: usize> Serialize<BitSlice<F>> for [T; N]
+where
+    F: BitFormula,
+    T: SerializeBits<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bit_slice::<F, _, B>(self.into_iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(bit_slice_size::<F>(N)))
+    }
+}
+
+impl<'ser, F, T, const N: usize> Serialize<BitSlice<F>> for &'ser [T; N]
+where
+    F: BitFormula,
+    &'ser T: SerializeBits<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bit_slice::<F, _, B>(self.iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(bit_slice_size::<F>(N)))
+    }
+}
+
+impl<F, I> Serialize<BitSlice<F>> for SerIter<I>
+where
+    F: BitFormula,
+    I: Iterator,
+    I::Item: SerializeBits<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bit_slice::<F, _, B>(self.0, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        match self.0.size_hint() {
+            (lower, Some(upper)) if lower == upper => {
+                Some(Sizes::with_stack(bit_slice_size::<F>(lower)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F, T> Serialize<BitSlice<F>> for Vec<T>
+where
+    F: BitFormula,
+    T: SerializeBits<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bit_slice::<F, _, B>(self.into_iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(bit_slice_size::<F>(self.len())))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'ser, F, T> Serialize<BitSlice<F>> for &'ser Vec<T>
+where
+    F: BitFormula,
+    &'ser T: SerializeBits<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bit_slice::<F, _, B>(self.iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(bit_slice_size::<F>(self.len())))
+    }
+}
+
+/// Lazy view of a serialized [`BitSlice`].
+///
+/// Deserializes elements on access.
+/// Any element is accessed in constant time.
+pub struct LazyBitSlice<'de, F> {
+    words: &'de [u8],
+    len: usize,
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+impl<F> Clone for LazyBitSlice<'_, F> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F> Copy for LazyBitSlice<'_, F> {}
+
+impl<'de, F> LazyBitSlice<'de, F>
+where
+    F: BitFormula,
+{
+    /// Returns number of elements in the slice.
+    #[must_use]
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the slice has no elements.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Deserializes element at specified index.
+    /// Returns `Ok(None)` if index is out of bounds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    #[inline]
+    pub fn get<T>(&self, idx: usize) -> Result<Option<T>, DeserializeError>
+    where
+        T: DeserializeBits<F>,
+    {
+        if idx >= self.len {
+            return Ok(None);
+        }
+        T::deserialize_bits(self.bits_at(idx * F::BITS as usize)).map(Some)
+    }
+
+    /// Produce iterator over lazy deserialized elements.
+    #[inline(always)]
+    pub fn iter<T>(&self) -> BitIter<'de, F, T>
+    where
+        T: DeserializeBits<F>,
+    {
+        BitIter {
+            slice: *self,
+            next: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns word at specified index.
+    /// First written word is the last in the input.
+    #[inline(always)]
+    fn word(&self, idx: usize) -> u64 {
+        let end = self.words.len() - idx * WORD_SIZE;
+        let mut bytes = [0; WORD_SIZE];
+        bytes.copy_from_slice(&self.words[end - WORD_SIZE..end]);
+        u64::from_le_bytes(bytes)
+    }
+
+    #[inline]
+    fn bits_at(&self, mut pos: usize) -> u128 {
+        let mut value = 0;
+        let mut read = 0;
+
+        while read < F::BITS {
+            let word = self.word(pos / WORD_BITS as usize);
+
+            #[allow(clippy::cast_possible_truncation)]
+            let shift = (pos % WORD_BITS as usize) as u32;
+            let take = min(WORD_BITS - shift, F::BITS - read);
+
+            value |= (u128::from(word >> shift) & bits_mask(take)) << read;
+            read += take;
+            pos += take as usize;
+        }
+
+        value
+    }
+}
+
+impl<'de, F> Deserialize<'de, BitSlice<F>> for LazyBitSlice<'de, F>
+where
+    F: BitFormula,
+{
+    #[inline]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let strict = de.is_strict();
+        let bytes = de.read_all_bytes();
+        if bytes.len() < SIZE_STACK {
+            return Err(DeserializeError::WrongLength);
+        }
+
+        // Length is written after all words.
+        let (len, words) = bytes.split_at(SIZE_STACK);
+        let len =
+            Deserializer::new_unchecked(SIZE_STACK, len).read_value::<FixedUsize, usize>(true)?;
+
+        let expected = len
+            .checked_mul(F::BITS as usize)
+            .map(|bits| bits.div_ceil(WORD_BITS as usize) * WORD_SIZE);
+        if expected != Some(words.len()) {
+            return Err(DeserializeError::WrongLength);
+        }
+
+        let slice = LazyBitSlice {
+            words,
+            len,
+            marker: PhantomData,
+        };
+
+        // Bits after the last element are written as zeros.
+        #[allow(clippy::cast_possible_truncation)]
+        let used = (len * F::BITS as usize % WORD_BITS as usize) as u32;
+        if strict && used != 0 && slice.word(words.len() / WORD_SIZE - 1) >> used != 0 {
+            return Err(DeserializeError::NonCanonical);
+        }
+        Ok(slice)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'de, BitSlice<F>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, F, T> Deserialize<'de, BitSlice<F>> for Vec<T>
+where
+    F: BitFormula,
+    T: DeserializeBits<F>,
+{
+    #[inline]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let slice = <LazyBitSlice<'de, F> as Deserialize<'de, BitSlice<F>>>::deserialize(de)?;
+        slice.iter::<T>().collect()
+    }
+
+    #[inline]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        let slice = <LazyBitSlice<'de, F> as Deserialize<'de, BitSlice<F>>>::deserialize(de)?;
+        self.clear();
+        self.reserve(slice.len());
+        for value in slice.iter::<T>() {
+            self.push(value?);
+        }
+        Ok(())
+    }
+}
+
+impl<'de, F> Deserialize<'de, BitSlice<F>> for Validated
+where
+    F: BitFormula,
+    Validated: DeserializeBits<F>,
+{
+    #[inline]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let slice = <LazyBitSlice<'de, F> as Deserialize<'de, BitSlice<F>>>::deserialize(de)?;
+        for value in slice.iter::<Validated>() {
+            value?;
+        }
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, BitSlice<F>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Iterator over elements of [`LazyBitSlice`].
+pub struct BitIter<'de, F, T> {
+    slice: LazyBitSlice<'de, F>,
+    next: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<F, T> Clone for BitIter<'_, F, T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        BitIter {
+            slice: self.slice,
+            next: self.next,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<F, T> Iterator for BitIter<'_, F, T>
+where
+    F: BitFormula,
+    T: DeserializeBits<F>,
+{
+    type Item = Result<T, DeserializeError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.slice.get::<T>(self.next).transpose()?;
+        self.next += 1;
+        Some(value)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.slice.len - self.next;
+        (len, Some(len))
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.next = self.next.saturating_add(n).min(self.slice.len);
+        self.next()
+    }
+}
+
+impl<F, T> ExactSizeIterator for BitIter<'_, F, T>
+where
+    F: BitFormula,
+    T: DeserializeBits<F>,
+{
+}
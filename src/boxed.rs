@@ -0,0 +1,271 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{BareFormula, Formula},
+    iter::{owned_iter_fast_sizes, ref_iter_fast_sizes},
+    reference::Ref,
+    serialize::{write_bytes, write_ref, write_slice, Serialize, Sizes},
+    validate::Validated,
+};
+
+use crate::descriptor::Descriptor;
+
+// `Box` is a fundamental type, so `Box<F>` formula cannot have blanket impls
+// like `Rc<F>` and `Arc<F>` do without conflicting with forwarding impls.
+// Boxed values are serialized with `Box<F>` formula,
+// and boxed strings and slices with `str` and `[F]` formulas as well.
+
+impl<F> Formula for Box<F>
+where
+    F: BareFormula + ?Sized,
+{
+    const MAX_STACK_SIZE: Option<usize> = <Ref<F> as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <Ref<F> as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <Ref<F> as Formula>::HEAPLESS;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<F> as Formula>::descriptor()
+    }
+}
+
+impl<F, T> Serialize<Box<F>> for Box<T>
+where
+    F: BareFormula + ?Sized,
+    T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<F, T, _>(*self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <T as Serialize<Ref<F>>>::size_hint(self)
+    }
+}
+
+impl<'ser, F, T> Serialize<Box<F>> for &'ser Box<T>
+where
+    F: BareFormula + ?Sized,
+    T: ?Sized,
+    &'ser T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<F, &'ser T, _>(self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <&'ser T as Serialize<Ref<F>>>::size_hint(&&***self)
+    }
+}
+
+impl Serialize<Box<str>> for Box<str> {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<str, _, _>(self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <Self as Serialize<Ref<str>>>::size_hint(self)
+    }
+}
+
+impl<F, T> Serialize<Box<[F]>> for Box<[T]>
+where
+    F: Formula,
+    T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<[F], _, _>(self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <Self as Serialize<Ref<[F]>>>::size_hint(self)
+    }
+}
+
+impl<'de, F, T> Deserialize<'de, Box<F>> for Box<T>
+where
+    F: BareFormula + ?Sized,
+    T: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let de = de.deref::<F>()?;
+        Ok(Box::new(<T as Deserialize<F>>::deserialize(de)?))
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        let de = de.deref::<F>()?;
+        <T as Deserialize<F>>::deserialize_in_place(self, de)
+    }
+}
+
+impl<'de, F> Deserialize<'de, Box<F>> for Validated
+where
+    F: BareFormula + ?Sized,
+    Validated: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Validated as Deserialize<'de, Ref<F>>>::deserialize(de)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Validated as Deserialize<'de, Ref<F>>>::deserialize_in_place(self, de)
+    }
+}
+
+impl<'de> Deserialize<'de, Box<str>> for Box<str> {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let de = de.deref::<str>()?;
+        <Self as Deserialize<str>>::deserialize(de)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        let de = de.deref::<str>()?;
+        <Self as Deserialize<str>>::deserialize_in_place(self, de)
+    }
+}
+
+impl<'de, F, T> Deserialize<'de, Box<[F]>> for Box<[T]>
+where
+    F: Formula,
+    T: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let de = de.deref::<[F]>()?;
+        <Self as Deserialize<[F]>>::deserialize(de)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        let de = de.deref::<[F]>()?;
+        <Self as Deserialize<[F]>>::deserialize_in_place(self, de)
+    }
+}
+
+impl Serialize<str> for Box<str> {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(self.as_bytes(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(self.len()))
+    }
+}
+
+impl<F, T> Serialize<[F]> for Box<[T]>
+where
+    F: Formula,
+    T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_slice(self.into_vec().into_iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        ref_iter_fast_sizes::<F, _, _>(self.iter())
+    }
+}
+
+impl Serialize<str> for &Box<str> {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(self.as_bytes(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(self.len()))
+    }
+}
+
+impl<'ser, F, T> Serialize<[F]> for &'ser Box<[T]>
+where
+    F: Formula,
+    &'ser T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_slice(self.iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        owned_iter_fast_sizes::<F, _, _>(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de, str> for Box<str> {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let string = <&str as Deserialize<'de, str>>::deserialize(de)?;
+        Ok(Box::from(string))
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'de, str>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl<'de, F, T> Deserialize<'de, [F]> for Box<[T]>
+where
+    F: Formula,
+    T: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let vec = <Vec<T> as Deserialize<'de, [F]>>::deserialize(de)?;
+        Ok(vec.into_boxed_slice())
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'de, [F]>>::deserialize(de)?;
+        Ok(())
+    }
+}
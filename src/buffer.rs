@@ -1,4 +1,4 @@
-use core::{convert::Infallible, fmt};
+use core::{any::TypeId, convert::Infallible, fmt, hash::Hasher};
 
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
@@ -25,7 +25,8 @@ pub trait Buffer {
     /// If buffer cannot write bytes, it should return `Err`.
     fn write_stack(&mut self, heap: usize, stack: usize, bytes: &[u8]) -> Result<(), Self::Error>;
 
-    /// Add padding bytes to the stack.
+    /// Add zeroed padding bytes to the stack.
+    /// Padding must be zeroed for serialized data to depend only on the value.
     ///
     /// # Errors
     ///
@@ -53,6 +54,24 @@ pub trait Buffer {
         stack: usize,
         len: usize,
     ) -> Result<&mut [u8], Self::Error>;
+
+    /// Looks up shared value previously written to this buffer.
+    /// Returns stack size and address of the value.
+    ///
+    /// Buffers that do not deduplicate shared values always return `None`.
+    #[inline(always)]
+    fn find_shared(&mut self, key: (usize, TypeId)) -> Option<(usize, usize)> {
+        let _ = key;
+        None
+    }
+
+    /// Remembers stack size and address of the shared value
+    /// written to this buffer, so that following occurrences
+    /// can refer to it.
+    #[inline(always)]
+    fn add_shared(&mut self, key: (usize, TypeId), stack: usize, address: usize) {
+        let _ = (key, stack, address);
+    }
 }
 
 /// No-op buffer that does not write anything.
@@ -160,11 +179,8 @@ impl<'a> Buffer for CheckedFixedBuffer<'a> {
             return Err(BufferExhausted);
         }
 
-        #[cfg(test)]
-        {
-            let at = self.buf.len() - stack - len;
-            self.buf[at..][..len].fill(0);
-        }
+        let at = self.buf.len() - stack - len;
+        self.buf[at..][..len].fill(0);
         Ok(())
     }
 
@@ -215,11 +231,8 @@ impl<'a> Buffer for &'a mut [u8] {
         debug_assert!(heap + stack <= self.len());
         assert!(self.len() - heap - stack >= len);
 
-        #[cfg(test)]
-        {
-            let at = self.len() - stack - len;
-            self[at..][..len].fill(0);
-        }
+        let at = self.len() - stack - len;
+        self[at..][..len].fill(0);
         Ok(())
     }
 
@@ -298,6 +311,11 @@ impl<'a> Buffer for MaybeFixedBuffer<'a> {
                 *self.exhausted = true;
             }
         }
+
+        if !*self.exhausted {
+            let at = self.buf.len() - stack - len;
+            self.buf[at..][..len].fill(0);
+        }
         Ok(())
     }
 
@@ -335,28 +353,66 @@ impl<'a> Buffer for MaybeFixedBuffer<'a> {
     }
 }
 
+/// Byte storage that [`VecBuffer`] can write to.
+/// Implemented for `Vec<u8>` and, with "tokio-codec" feature, for `BytesMut`.
+#[cfg(feature = "alloc")]
+pub trait ByteVec: core::ops::DerefMut<Target = [u8]> + sealed::Sealed {
+    /// Resizes storage to `new_len` bytes, filling new bytes with zeros.
+    fn resize_zeroed(&mut self, new_len: usize);
+}
+
+#[cfg(feature = "alloc")]
+mod sealed {
+    pub trait Sealed {}
+}
+
+#[cfg(feature = "alloc")]
+impl sealed::Sealed for Vec<u8> {}
+
+#[cfg(feature = "alloc")]
+impl ByteVec for Vec<u8> {
+    #[inline(always)]
+    fn resize_zeroed(&mut self, new_len: usize) {
+        self.resize(new_len, 0);
+    }
+}
+
+#[cfg(feature = "tokio-codec")]
+impl sealed::Sealed for ::bytes::BytesMut {}
+
+#[cfg(feature = "tokio-codec")]
+impl ByteVec for ::bytes::BytesMut {
+    #[inline(always)]
+    fn resize_zeroed(&mut self, new_len: usize) {
+        self.resize(new_len, 0);
+    }
+}
+
 /// Extensible buffer that writes to a vector.
 /// If buffer is too small to fit serialized data it extends the vector.
 /// Never returns an error, cannot fail to serialize data except for OOM error.
 #[cfg(feature = "alloc")]
-pub struct VecBuffer<'a> {
-    buf: &'a mut Vec<u8>,
+pub struct VecBuffer<'a, V = Vec<u8>> {
+    buf: &'a mut V,
 }
 
 #[cfg(feature = "alloc")]
-impl<'a> VecBuffer<'a> {
+impl<'a, V> VecBuffer<'a, V> {
     /// Creates a new buffer that writes to the given vector.
-    pub fn new(buf: &'a mut Vec<u8>) -> Self {
+    pub fn new(buf: &'a mut V) -> Self {
         VecBuffer { buf }
     }
 }
 
 #[cfg(feature = "alloc")]
-impl VecBuffer<'_> {
+impl<V> VecBuffer<'_, V>
+where
+    V: ByteVec,
+{
     #[cold]
     fn do_reserve(&mut self, heap: usize, stack: usize, additional: usize) {
         let old_len = self.buf.len();
-        self.buf.resize(heap + stack + additional, 0);
+        self.buf.resize_zeroed(heap + stack + additional);
         let new_len = self.buf.len();
         self.buf
             .copy_within(old_len - stack..old_len, new_len - stack);
@@ -372,9 +428,12 @@ impl VecBuffer<'_> {
 }
 
 #[cfg(feature = "alloc")]
-impl<'a> Buffer for VecBuffer<'a> {
+impl<'a, V> Buffer for VecBuffer<'a, V>
+where
+    V: ByteVec,
+{
     type Error = Infallible;
-    type Reborrow<'b> = VecBuffer<'b> where 'a: 'b;
+    type Reborrow<'b> = VecBuffer<'b, V> where 'a: 'b;
 
     #[inline(always)]
     fn reborrow(&mut self) -> Self::Reborrow<'_> {
@@ -395,11 +454,8 @@ impl<'a> Buffer for VecBuffer<'a> {
         debug_assert!(heap + stack <= self.buf.len());
         self.reserve(heap, stack, len);
 
-        #[cfg(test)]
-        {
-            let at = self.buf.len() - stack - len;
-            self.buf[at..][..len].fill(0);
-        }
+        let at = self.buf.len() - stack - len;
+        self.buf[at..][..len].fill(0);
         Ok(())
     }
 
@@ -423,3 +479,309 @@ impl<'a> Buffer for VecBuffer<'a> {
         Ok(&mut self.buf[..heap + len])
     }
 }
+
+/// Buffer that streams serialized data into [`std::io::Write`].
+///
+/// Bytes are written in order, so heap is written to the writer
+/// as soon as values are moved there.
+/// Only the stack of values being serialized is kept in memory.
+///
+/// Root reference precedes the data, so the size of the value
+/// must be known before it is written.
+/// Use [`serialize_into_writer`](crate::serialize_into_writer)
+/// that takes care of it.
+#[cfg(feature = "std")]
+pub(crate) struct WriteBuffer<'a, W> {
+    writer: &'a mut W,
+
+    /// Stack bytes in reverse order.
+    /// Byte at index `i` is `i`-th byte from the end of the stack.
+    stack: &'a mut Vec<u8>,
+
+    /// Number of bytes written to the writer.
+    written: &'a mut usize,
+
+    /// Error that occurred when moving bytes to the heap.
+    error: &'a mut Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, W> WriteBuffer<'a, W>
+where
+    W: std::io::Write,
+{
+    /// Creates a new buffer that writes to the given writer.
+    /// `written` is the number of bytes already written.
+    pub(crate) fn new(
+        writer: &'a mut W,
+        stack: &'a mut Vec<u8>,
+        written: &'a mut usize,
+        error: &'a mut Option<std::io::Error>,
+    ) -> Self {
+        WriteBuffer {
+            writer,
+            stack,
+            written,
+            error,
+        }
+    }
+
+    fn check(&mut self) -> Result<(), std::io::Error> {
+        match self.error.take() {
+            None => Ok(()),
+            Some(err) => Err(err),
+        }
+    }
+
+    fn reserve(&mut self, stack: usize, len: usize) {
+        if self.stack.len() < stack + len {
+            self.stack.resize(stack + len, 0);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, W> Buffer for WriteBuffer<'a, W>
+where
+    W: std::io::Write,
+{
+    type Error = std::io::Error;
+    type Reborrow<'b> = WriteBuffer<'b, W> where 'a: 'b;
+
+    #[inline(always)]
+    fn reborrow(&mut self) -> Self::Reborrow<'_> {
+        WriteBuffer {
+            writer: self.writer,
+            stack: self.stack,
+            written: self.written,
+            error: self.error,
+        }
+    }
+
+    #[inline]
+    fn write_stack(
+        &mut self,
+        _heap: usize,
+        stack: usize,
+        bytes: &[u8],
+    ) -> Result<(), std::io::Error> {
+        self.check()?;
+        self.reserve(stack, bytes.len());
+        let reversed = &mut self.stack[stack..][..bytes.len()];
+        for (dst, src) in reversed.iter_mut().zip(bytes.iter().rev()) {
+            *dst = *src;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn pad_stack(&mut self, _heap: usize, stack: usize, len: usize) -> Result<(), std::io::Error> {
+        self.check()?;
+        self.reserve(stack, len);
+        self.stack[stack..][..len].fill(0);
+        Ok(())
+    }
+
+    #[inline]
+    fn move_to_heap(&mut self, heap: usize, stack: usize, len: usize) {
+        debug_assert!(stack >= len);
+        debug_assert_eq!(heap, *self.written, "Heap must be written in order");
+
+        if self.error.is_some() {
+            return;
+        }
+
+        // Moved bytes are on top of the stack and nothing above is used.
+        self.stack.truncate(stack);
+        let bytes = &mut self.stack[stack - len..];
+        bytes.reverse();
+
+        match self.writer.write_all(bytes) {
+            Ok(()) => *self.written += len,
+            Err(err) => *self.error = Some(err),
+        }
+        self.stack.truncate(stack - len);
+    }
+
+    #[inline]
+    fn reserve_heap(
+        &mut self,
+        _heap: usize,
+        _stack: usize,
+        _len: usize,
+    ) -> Result<&mut [u8], std::io::Error> {
+        self.check()?;
+
+        // Already written bytes are not accessible.
+        // Serializer falls back to `write_stack` and `move_to_heap`.
+        Ok(&mut [])
+    }
+}
+
+/// Buffer that feeds serialized data into [`Hasher`]
+/// instead of writing it anywhere.
+///
+/// Bytes are fed in order, so heap is fed to the hasher
+/// as soon as values are moved there.
+/// Only the stack of values being serialized is kept in the scratch slice.
+///
+/// Root reference precedes the data, so the size of the value
+/// must be known before it is fed.
+/// Use [`serialize_to_hasher`](crate::serialize_to_hasher)
+/// that takes care of it.
+pub(crate) struct HashBuffer<'a, H: ?Sized> {
+    hasher: &'a mut H,
+
+    /// Stack bytes in reverse order.
+    /// Byte at index `i` is `i`-th byte from the end of the stack.
+    stack: &'a mut [u8],
+
+    /// Number of bytes fed to the hasher.
+    written: &'a mut usize,
+}
+
+impl<'a, H> HashBuffer<'a, H>
+where
+    H: Hasher + ?Sized,
+{
+    /// Creates a new buffer that feeds the given hasher.
+    /// `written` is the number of bytes already fed.
+    pub(crate) fn new(hasher: &'a mut H, stack: &'a mut [u8], written: &'a mut usize) -> Self {
+        HashBuffer {
+            hasher,
+            stack,
+            written,
+        }
+    }
+
+    #[inline(always)]
+    fn reserve(&mut self, stack: usize, len: usize) -> Result<&mut [u8], BufferExhausted> {
+        match self.stack.get_mut(stack..stack + len) {
+            None => Err(BufferExhausted),
+            Some(reserved) => Ok(reserved),
+        }
+    }
+}
+
+impl<'a, H> Buffer for HashBuffer<'a, H>
+where
+    H: Hasher + ?Sized,
+{
+    type Error = BufferExhausted;
+    type Reborrow<'b> = HashBuffer<'b, H> where 'a: 'b;
+
+    #[inline(always)]
+    fn reborrow(&mut self) -> Self::Reborrow<'_> {
+        HashBuffer {
+            hasher: self.hasher,
+            stack: self.stack,
+            written: self.written,
+        }
+    }
+
+    #[inline]
+    fn write_stack(
+        &mut self,
+        _heap: usize,
+        stack: usize,
+        bytes: &[u8],
+    ) -> Result<(), BufferExhausted> {
+        let reversed = self.reserve(stack, bytes.len())?;
+        for (dst, src) in reversed.iter_mut().zip(bytes.iter().rev()) {
+            *dst = *src;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn pad_stack(&mut self, _heap: usize, stack: usize, len: usize) -> Result<(), BufferExhausted> {
+        self.reserve(stack, len)?.fill(0);
+        Ok(())
+    }
+
+    #[inline]
+    fn move_to_heap(&mut self, heap: usize, stack: usize, len: usize) {
+        debug_assert!(stack >= len);
+        debug_assert_eq!(heap, *self.written, "Heap must be fed in order");
+
+        // Moved bytes are on top of the stack and nothing above is used.
+        let bytes = &mut self.stack[stack - len..stack];
+        bytes.reverse();
+        self.hasher.write(bytes);
+        *self.written += len;
+    }
+
+    #[inline]
+    fn reserve_heap(
+        &mut self,
+        _heap: usize,
+        _stack: usize,
+        _len: usize,
+    ) -> Result<&mut [u8], BufferExhausted> {
+        // Bytes fed to the hasher can't be rewritten.
+        // Serializer falls back to `write_stack` and `move_to_heap`.
+        Ok(&mut [])
+    }
+}
+
+/// Buffer adapter that shifts heap of the wrapped buffer by fixed offset.
+/// Allows serializing value after some bytes already written to the buffer,
+/// with addresses in the value being relative to the offset.
+#[cfg(feature = "alloc")]
+pub(crate) struct OffsetBuffer<B> {
+    buffer: B,
+    offset: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<B> OffsetBuffer<B> {
+    #[inline(always)]
+    pub(crate) fn new(buffer: B, offset: usize) -> Self {
+        OffsetBuffer { buffer, offset }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<B> Buffer for OffsetBuffer<B>
+where
+    B: Buffer,
+{
+    type Error = B::Error;
+    type Reborrow<'a> = OffsetBuffer<B::Reborrow<'a>> where B: 'a;
+
+    #[inline(always)]
+    fn reborrow(&mut self) -> Self::Reborrow<'_> {
+        OffsetBuffer {
+            buffer: self.buffer.reborrow(),
+            offset: self.offset,
+        }
+    }
+
+    #[inline(always)]
+    fn write_stack(&mut self, heap: usize, stack: usize, bytes: &[u8]) -> Result<(), B::Error> {
+        self.buffer.write_stack(self.offset + heap, stack, bytes)
+    }
+
+    #[inline(always)]
+    fn pad_stack(&mut self, heap: usize, stack: usize, len: usize) -> Result<(), B::Error> {
+        self.buffer.pad_stack(self.offset + heap, stack, len)
+    }
+
+    #[inline(always)]
+    fn move_to_heap(&mut self, heap: usize, stack: usize, len: usize) {
+        self.buffer.move_to_heap(self.offset + heap, stack, len);
+    }
+
+    #[inline(always)]
+    fn reserve_heap(
+        &mut self,
+        heap: usize,
+        stack: usize,
+        len: usize,
+    ) -> Result<&mut [u8], B::Error> {
+        match self.buffer.reserve_heap(self.offset + heap, stack, len)? {
+            [] => Ok(&mut []),
+            reserved => Ok(&mut reserved[self.offset..]),
+        }
+    }
+}
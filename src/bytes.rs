@@ -3,8 +3,12 @@ use crate::{
     deserialize::{Deserialize, DeserializeError, Deserializer},
     formula::{BareFormula, Formula},
     serialize::{write_bytes, Serialize, Sizes},
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 /// A formula for a raw byte slices.
 /// Serializable from anything that implements `AsRef<[u8]>`.
 pub struct Bytes;
@@ -13,6 +17,12 @@ impl Formula for Bytes {
     const MAX_STACK_SIZE: Option<usize> = None;
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Bytes
+    }
 }
 
 impl BareFormula for Bytes {}
@@ -44,3 +54,15 @@ impl<'de, 'fe: 'de> Deserialize<'fe, Bytes> for &'de [u8] {
         Ok(())
     }
 }
+
+impl Deserialize<'_, Bytes> for Validated {
+    #[inline(always)]
+    fn deserialize(_de: Deserializer) -> Result<Self, DeserializeError> {
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, _de: Deserializer) -> Result<(), DeserializeError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+//!
+//! This module provides verification of canonical encoding.
+//!
+
+use core::cell::Cell;
+
+use crate::{
+    deserialize::{validate_strict, Deserialize, DeserializeError},
+    formula::Formula,
+    validate::Validated,
+};
+
+/// Checks that input is canonical encoding of value of formula `F`.
+///
+/// Canonical encoding is the only byte representation of a value
+/// that serialization functions produce.
+/// Two equal values serialized with the same formula
+/// yield identical bytes, so serialized data can be hashed,
+/// compared and addressed by content.
+///
+/// Encoding is canonical when it has following properties.
+///
+/// * Integers with fixed size formulas are little-endian
+///   unless formula says otherwise.
+///   Integers with [`Vlq`](crate::Vlq) formula use fewest bytes possible.
+/// * Padding added to values with non-exact size is zeroed.
+/// * Every nested value is written once to the heap,
+///   in order of serialization and without gaps.
+///   References never point to shared or overlapping bytes.
+/// * Entries of [`Map`](crate::Map) and elements of [`Set`](crate::Set)
+///   are sorted without duplicates.
+///   Slots of [`HashTable`](crate::HashTable) depend only on the set of keys.
+/// * Floating point numbers are written bit by bit,
+///   so `0.0` and `-0.0` or NaNs with different payloads differ.
+///
+/// [`serialize`](crate::serialize), `serialize_to_vec`,
+/// `serialize_into_writer` and other serialization functions
+/// produce canonical encoding with following exceptions.
+///
+/// * [`SerIter`](crate::SerIter) is serialized in iteration order.
+///   Pairs for `Map` and elements for `Set` must be yielded
+///   in ascending order without duplicates.
+/// * `serialize_shared` writes shared values once.
+/// * `Bincoded` values are as canonical as their bincode serialization.
+///
+/// Encoding depends on the size of [`FixedUsize`](crate::FixedUsize),
+/// peers must enable the same `fixed*` feature to produce same bytes.
+///
+/// Input is validated with strict deserializer,
+/// it walks the value once without allocations.
+/// Besides checks of [`validate`](crate::validate), it checks that
+/// every value uses all of its stack bytes except zeroed padding,
+/// [`Vlq`](crate::Vlq) integers, `bool` values and unused bits of packed formulas
+/// are encoded as serialization writes them
+/// and referenced values fill the input in serialization order.
+/// Unknown variants of non-exhaustive enums are rejected.
+///
+/// Order of [`Map`](crate::Map) entries and [`Set`](crate::Set) elements
+/// and placement of [`HashTable`](crate::HashTable) entries
+/// depend on keys and are not checked.
+/// [`deserialize_strict`](crate::deserialize_strict) performs this check
+/// and also checks order when deserializing into `BTreeMap` and `BTreeSet`.
+///
+/// # Errors
+///
+/// Returns `DeserializeError` if deserialization fails
+/// and [`DeserializeError::NonCanonical`] if input
+/// is not canonical encoding of a value.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// let mut buffer = [0u8; 64];
+/// let size = serialize::<Vlq, u32>(5, &mut buffer).unwrap();
+/// assert!(verify_canonical::<Vlq>(&buffer[..size]).is_ok());
+///
+/// // Value `5` encoded with one excessive byte,
+/// // preceded by the reference with address and size.
+/// const REF: usize = core::mem::size_of::<FixedUsize>();
+/// let mut padded = [0u8; 2 * REF + 2];
+/// padded[0] = padded.len() as u8;
+/// padded[REF] = 2;
+/// padded[2 * REF..].copy_from_slice(&[5, 0x10]);
+/// assert_eq!(deserialize::<Vlq, u32>(&padded).unwrap().0, 5);
+/// assert!(matches!(
+///     verify_canonical::<Vlq>(&padded),
+///     Err(DeserializeError::NonCanonical)
+/// ));
+/// ```
+#[inline]
+pub fn verify_canonical<F>(input: &[u8]) -> Result<(), DeserializeError>
+where
+    F: Formula + ?Sized,
+    for<'a> Validated: Deserialize<'a, F>,
+{
+    let heap = Cell::new(0);
+    validate_strict::<F>(input, &heap)
+}
@@ -0,0 +1,100 @@
+//!
+//! This module provides checksum of serialized data.
+//!
+
+use core::hash::Hasher;
+
+/// Incremental CRC-32 (IEEE) checksum.
+///
+/// Implements [`Hasher`] to be used with
+/// [`serialize_to_hasher`](crate::serialize_to_hasher),
+/// [`Hasher::finish`] returns the checksum.
+/// Checksum does not depend on how bytes are split between writes.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// let mut crc = Crc32::new();
+/// crc.update(b"123");
+/// crc.update(b"456789");
+/// assert_eq!(crc.checksum(), 0xCBF4_3926);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32 {
+    /// Inverted checksum of bytes so far.
+    state: u32,
+}
+
+impl Crc32 {
+    /// Returns checksum state of empty data.
+    #[must_use]
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Crc32 { state: !0 }
+    }
+
+    /// Updates checksum with the bytes.
+    #[inline]
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.state = bytes.iter().fold(self.state, |crc, &byte| {
+            CRC32_TABLE[usize::from((crc as u8) ^ byte)] ^ (crc >> 8)
+        });
+    }
+
+    /// Returns checksum of bytes so far.
+    #[must_use]
+    #[inline(always)]
+    pub const fn checksum(&self) -> u32 {
+        !self.state
+    }
+}
+
+impl Default for Crc32 {
+    #[inline(always)]
+    fn default() -> Self {
+        Crc32::new()
+    }
+}
+
+impl Hasher for Crc32 {
+    #[inline(always)]
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    #[inline(always)]
+    fn finish(&self) -> u64 {
+        u64::from(self.checksum())
+    }
+}
+
+/// Computes CRC-32 (IEEE) checksum of the bytes.
+#[inline]
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.checksum()
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xedb8_8320
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
@@ -0,0 +1,402 @@
+//!
+//! This module provides [`tokio_util::codec`] integration.
+//!
+//! Codecs write and read frames of the [`framing`](crate::framing) module.
+//! Values are serialized directly into the output [`BytesMut`]
+//! and deserialized directly from the input one, without intermediate copies.
+//!
+
+use core::{fmt, marker::PhantomData};
+use std::io;
+
+use ::bytes::{Buf, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{
+    deserialize::{deserialize, Deserialize, DeserializeError},
+    formula::{BareFormula, Formula},
+    framing::{take_frame, FrameEncoder, FrameHeader, DEFAULT_MAX_FRAME_SIZE},
+    lazy::Lazy,
+    serialize::Serialize,
+};
+
+/// Codec that encodes values with formula `F` into frames
+/// and decodes frames into [`OwnedFrame`] handles.
+///
+/// Decoded frames keep the bytes of the message and allow
+/// deserializing values borrowing from them.
+/// Use [`Codec`] to decode owned values directly.
+pub struct FrameCodec<F: ?Sized> {
+    encoder: FrameEncoder,
+    fingerprint: Option<u64>,
+    max_frame_size: usize,
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+impl<F> Clone for FrameCodec<F>
+where
+    F: ?Sized,
+{
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        FrameCodec {
+            encoder: self.encoder,
+            fingerprint: self.fingerprint,
+            max_frame_size: self.max_frame_size,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<F> fmt::Debug for FrameCodec<F>
+where
+    F: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameCodec")
+            .field("encoder", &self.encoder)
+            .field("fingerprint", &self.fingerprint)
+            .field("max_frame_size", &self.max_frame_size)
+            .finish()
+    }
+}
+
+impl<F> Default for FrameCodec<F>
+where
+    F: ?Sized,
+{
+    #[inline(always)]
+    fn default() -> Self {
+        FrameCodec::new()
+    }
+}
+
+impl<F> FrameCodec<F>
+where
+    F: ?Sized,
+{
+    /// Creates new codec that writes frames
+    /// without fingerprint and checksum.
+    #[must_use]
+    #[inline(always)]
+    pub const fn new() -> Self {
+        FrameCodec {
+            encoder: FrameEncoder::new(),
+            fingerprint: None,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            marker: PhantomData,
+        }
+    }
+
+    /// Writes fingerprint of the formula into each frame
+    /// and requires it in each received frame.
+    #[must_use]
+    #[inline]
+    pub fn with_fingerprint(mut self) -> Self
+    where
+        F: Formula,
+    {
+        let fingerprint = F::descriptor().fingerprint();
+        self.encoder = self.encoder.with_fingerprint(fingerprint);
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Writes checksum of the frame body into each frame.
+    /// Checksum of received frames is always verified if present.
+    #[must_use]
+    #[inline(always)]
+    pub const fn with_checksum(mut self) -> Self {
+        self.encoder = self.encoder.with_checksum();
+        self
+    }
+
+    /// Limits size of received frames, including header.
+    /// Defaults to [`DEFAULT_MAX_FRAME_SIZE`].
+    #[must_use]
+    #[inline(always)]
+    pub const fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    #[inline]
+    fn encode_frame<T>(&self, value: T, dst: &mut BytesMut)
+    where
+        F: Formula,
+        T: Serialize<F>,
+    {
+        self.encoder.encode_to_byte_vec::<F, T, _>(value, dst);
+    }
+
+    /// Decodes next frame from the source and passes its body to `f`.
+    /// Consumes frame bytes from the source afterwards.
+    fn decode_frame<R>(
+        &self,
+        src: &mut BytesMut,
+        f: impl FnOnce(&FrameHeader, &mut BytesMut) -> R,
+    ) -> io::Result<Option<R>> {
+        let (consumed, result) = take_frame(src, self.fingerprint, self.max_frame_size);
+
+        let header = match result {
+            Ok(Some(frame)) => *frame.header(),
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                src.advance(consumed);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+            }
+        };
+
+        let mut frame = src.split_to(consumed);
+        frame.advance(header.size());
+        Ok(Some(f(&header, &mut frame)))
+    }
+}
+
+impl<F, T> Encoder<T> for FrameCodec<F>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+{
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode_frame::<T>(item, dst);
+        Ok(())
+    }
+}
+
+impl<F> Decoder for FrameCodec<F>
+where
+    F: Formula + ?Sized,
+{
+    type Item = OwnedFrame<F>;
+    type Error = io::Error;
+
+    #[inline]
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<OwnedFrame<F>>> {
+        self.decode_frame(src, |header, body| OwnedFrame {
+            header: *header,
+            body: body.split().freeze(),
+            marker: PhantomData,
+        })
+    }
+}
+
+/// Codec that encodes values with formula `F` into frames
+/// and decodes frames into owned values of type `T`.
+///
+/// Values are deserialized directly from the input buffer.
+pub struct Codec<F: ?Sized, T> {
+    frame: FrameCodec<F>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<F, T> Clone for Codec<F, T>
+where
+    F: ?Sized,
+{
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Codec {
+            frame: self.frame.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<F, T> fmt::Debug for Codec<F, T>
+where
+    F: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Codec")
+            .field("encoder", &self.frame.encoder)
+            .field("fingerprint", &self.frame.fingerprint)
+            .field("max_frame_size", &self.frame.max_frame_size)
+            .finish()
+    }
+}
+
+impl<F, T> Default for Codec<F, T>
+where
+    F: ?Sized,
+{
+    #[inline(always)]
+    fn default() -> Self {
+        Codec::new()
+    }
+}
+
+impl<F, T> Codec<F, T>
+where
+    F: ?Sized,
+{
+    /// Creates new codec that writes frames
+    /// without fingerprint and checksum.
+    #[must_use]
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Codec {
+            frame: FrameCodec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Writes fingerprint of the formula into each frame
+    /// and requires it in each received frame.
+    #[must_use]
+    #[inline]
+    pub fn with_fingerprint(mut self) -> Self
+    where
+        F: Formula,
+    {
+        self.frame = self.frame.with_fingerprint();
+        self
+    }
+
+    /// Writes checksum of the frame body into each frame.
+    /// Checksum of received frames is always verified if present.
+    #[must_use]
+    #[inline(always)]
+    pub const fn with_checksum(mut self) -> Self {
+        self.frame = self.frame.with_checksum();
+        self
+    }
+
+    /// Limits size of received frames, including header.
+    /// Defaults to [`DEFAULT_MAX_FRAME_SIZE`].
+    #[must_use]
+    #[inline(always)]
+    pub const fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.frame = self.frame.with_max_frame_size(max_frame_size);
+        self
+    }
+}
+
+impl<F, T, U> Encoder<U> for Codec<F, T>
+where
+    F: Formula + ?Sized,
+    U: Serialize<F>,
+{
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: U, dst: &mut BytesMut) -> io::Result<()> {
+        self.frame.encode_frame::<U>(item, dst);
+        Ok(())
+    }
+}
+
+impl<F, T> Decoder for Codec<F, T>
+where
+    F: Formula + ?Sized,
+    T: for<'de> Deserialize<'de, F>,
+{
+    type Item = T;
+    type Error = io::Error;
+
+    #[inline]
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<T>> {
+        match self
+            .frame
+            .decode_frame(src, |_, body| deserialize::<F, T>(body))?
+        {
+            None => Ok(None),
+            Some(Ok((value, _))) => Ok(Some(value)),
+            Some(Err(err)) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+    }
+}
+
+/// Frame decoded by [`FrameCodec`].
+///
+/// Owns the bytes of the message.
+/// Values can be deserialized from it any number of times,
+/// borrowing from the frame.
+pub struct OwnedFrame<F: ?Sized> {
+    header: FrameHeader,
+    body: Bytes,
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+impl<F> Clone for OwnedFrame<F>
+where
+    F: ?Sized,
+{
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        OwnedFrame {
+            header: self.header,
+            body: self.body.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<F> fmt::Debug for OwnedFrame<F>
+where
+    F: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnedFrame")
+            .field("header", &self.header)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+impl<F> OwnedFrame<F>
+where
+    F: Formula + ?Sized,
+{
+    /// Returns header of the frame.
+    #[must_use]
+    #[inline(always)]
+    pub const fn header(&self) -> &FrameHeader {
+        &self.header
+    }
+
+    /// Returns body of the frame.
+    /// Body contains serialized message.
+    #[must_use]
+    #[inline(always)]
+    pub const fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Unwraps body of the frame.
+    #[must_use]
+    #[inline(always)]
+    pub fn into_body(self) -> Bytes {
+        self.body
+    }
+
+    /// Deserializes message from the frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    #[inline]
+    pub fn deserialize<'de, T>(&'de self) -> Result<T, DeserializeError>
+    where
+        T: Deserialize<'de, F>,
+    {
+        let (value, _) = deserialize::<F, T>(&self.body)?;
+        Ok(value)
+    }
+
+    /// Returns lazy message from the frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if frame body is not a valid reference.
+    #[inline]
+    pub fn lazy(&self) -> Result<Lazy<'_, F>, DeserializeError>
+    where
+        F: BareFormula,
+    {
+        self.deserialize::<Lazy<'_, F>>()
+    }
+}
@@ -0,0 +1,489 @@
+//!
+//! This module provides compatibility checks between formula descriptors.
+//!
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{descriptor::Descriptor, formula::Formula};
+
+/// Single violation of compatibility found by [`check_compatibility`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Incompatibility {
+    /// Path to the violating element.
+    ///
+    /// Path starts with the name of the root formula if it has one.
+    /// Fields are appended as `.name`, variants as `::Name`,
+    /// slice and array elements as `[]`, `Option` value as `?`
+    /// and referenced value as `*`.
+    pub path: String,
+
+    /// Kind of the violation.
+    pub kind: IncompatibilityKind,
+}
+
+/// Kind of compatibility violation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IncompatibilityKind {
+    /// Formulas have different layouts.
+    Mismatch {
+        /// Formula used to write the data.
+        writer: Descriptor,
+
+        /// Formula used to read the data.
+        reader: Descriptor,
+    },
+
+    /// Reader expects a field that writer does not write.
+    MissingField {
+        /// Name of the field in the reader formula.
+        name: String,
+    },
+
+    /// Writer writes a field that reader does not expect.
+    UnknownField {
+        /// Name of the field in the writer formula.
+        name: String,
+    },
+
+    /// Writer may write a variant that reader does not know.
+    UnknownVariant {
+        /// Name of the variant in the writer formula.
+        name: String,
+
+        /// Index of the variant.
+        index: u32,
+    },
+
+    /// Arrays have different lengths.
+    ArrayLength {
+        /// Length of the array in the writer formula.
+        writer: usize,
+
+        /// Length of the array in the reader formula.
+        reader: usize,
+    },
+
+    /// Formulas occupy different stack sizes where reader
+    /// relies on the size of its own formula.
+    StackSize {
+        /// Maximum stack size of the writer formula.
+        writer: Option<usize>,
+
+        /// Maximum stack size of the reader formula.
+        reader: Option<usize>,
+    },
+}
+
+impl fmt::Display for IncompatibilityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncompatibilityKind::Mismatch { writer, reader } => {
+                write!(f, "written as `{writer}` but read as `{reader}`")
+            }
+            IncompatibilityKind::MissingField { name } => {
+                write!(f, "field `{name}` is missing in written data")
+            }
+            IncompatibilityKind::UnknownField { name } => {
+                write!(f, "field `{name}` is unknown to reader")
+            }
+            IncompatibilityKind::UnknownVariant { name, index } => {
+                write!(
+                    f,
+                    "variant `{name}` with index {index} is unknown to reader"
+                )
+            }
+            IncompatibilityKind::ArrayLength { writer, reader } => {
+                write!(
+                    f,
+                    "array of length {writer} is read as array of length {reader}"
+                )
+            }
+            IncompatibilityKind::StackSize { writer, reader } => {
+                write!(
+                    f,
+                    "stack size {} is read as stack size {}",
+                    SizeDisplay(*writer),
+                    SizeDisplay(*reader)
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            fmt::Display::fmt(&self.kind, f)
+        } else {
+            write!(f, "{}: {}", self.path, self.kind)
+        }
+    }
+}
+
+struct SizeDisplay(Option<usize>);
+
+impl fmt::Display for SizeDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => f.write_str("unbounded"),
+            Some(size) => write!(f, "{size}"),
+        }
+    }
+}
+
+/// Checks that data serialized with `writer` formula
+/// can be deserialized with `reader` formula.
+///
+/// Names of fields and formulas do not affect the layout
+/// and are used only to build paths.
+/// Structures and tuples with compatible fields are compatible.
+/// Extensible structures are compatible with each other
+/// when their common fields are compatible.
+///
+/// # Errors
+///
+/// Returns every violation found with its path.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+///
+/// // `u16` is written where `u32` is expected.
+/// let errors = check_compatibility(
+///     &<(u8, u16)>::descriptor(),
+///     &<(u8, u32)>::descriptor(),
+/// )
+/// .unwrap_err();
+///
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].path, ".1");
+/// ```
+pub fn check_compatibility(
+    writer: &Descriptor,
+    reader: &Descriptor,
+) -> Result<(), Vec<Incompatibility>> {
+    let mut checker = Checker {
+        path: String::new(),
+        violations: Vec::new(),
+    };
+
+    if let (
+        Descriptor::Struct { name, .. } | Descriptor::Enum { name, .. },
+        Descriptor::Struct { .. } | Descriptor::Enum { .. },
+    ) = (writer, reader)
+    {
+        checker.path.push_str(name);
+    }
+
+    // Root value is accessed through a reference.
+    checker.check(writer, reader, Position::Reference);
+
+    if checker.violations.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.violations)
+    }
+}
+
+/// Checks that data serialized with `New` formula
+/// can be deserialized with `Old` formula.
+///
+/// # Errors
+///
+/// Returns every violation found with its path.
+pub fn check_forward_compatibility<Old, New>() -> Result<(), Vec<Incompatibility>>
+where
+    Old: Formula + ?Sized,
+    New: Formula + ?Sized,
+{
+    check_compatibility(&New::descriptor(), &Old::descriptor())
+}
+
+/// Checks that data serialized with `Old` formula
+/// can be deserialized with `New` formula.
+///
+/// # Errors
+///
+/// Returns every violation found with its path.
+pub fn check_backward_compatibility<Old, New>() -> Result<(), Vec<Incompatibility>>
+where
+    Old: Formula + ?Sized,
+    New: Formula + ?Sized,
+{
+    check_compatibility(&Old::descriptor(), &New::descriptor())
+}
+
+/// Describes how reader determines stack size of the value.
+#[derive(Clone, Copy)]
+enum Position {
+    /// Sized value is read with its own size,
+    /// unsized value is prefixed with its size.
+    Field,
+
+    /// Value gets the rest of the stack.
+    Last,
+
+    /// Value is accessed through a reference
+    /// that stores size unless the formula is sized and exact.
+    Reference,
+}
+
+struct Checker {
+    path: String,
+    violations: Vec<Incompatibility>,
+}
+
+impl Checker {
+    fn push(&mut self, kind: IncompatibilityKind) {
+        self.violations.push(Incompatibility {
+            path: self.path.clone(),
+            kind,
+        });
+    }
+
+    fn nested(
+        &mut self,
+        segment: &str,
+        writer: &Descriptor,
+        reader: &Descriptor,
+        position: Position,
+    ) {
+        let len = self.path.len();
+        self.path.push_str(segment);
+        self.check(writer, reader, position);
+        self.path.truncate(len);
+    }
+
+    fn check(&mut self, writer: &Descriptor, reader: &Descriptor, position: Position) {
+        if writer == reader && comparable(writer) {
+            return;
+        }
+
+        let violations = self.violations.len();
+        self.check_layout(writer, reader);
+
+        // Size violations are consequence of layout violations.
+        if self.violations.len() == violations {
+            self.check_size(writer, reader, position);
+        }
+    }
+
+    fn check_layout(&mut self, writer: &Descriptor, reader: &Descriptor) {
+        match (writer, reader) {
+            (Descriptor::Slice(writer), Descriptor::Slice(reader)) => {
+                self.nested("[]", writer, reader, Position::Field);
+            }
+            (Descriptor::Array(writer, writer_len), Descriptor::Array(reader, reader_len)) => {
+                if writer_len == reader_len {
+                    self.nested("[]", writer, reader, Position::Field);
+                } else {
+                    self.push(IncompatibilityKind::ArrayLength {
+                        writer: *writer_len,
+                        reader: *reader_len,
+                    });
+                }
+            }
+            (Descriptor::Option(writer), Descriptor::Option(reader)) => {
+                self.nested("?", writer, reader, Position::Last);
+            }
+            (Descriptor::Ref(writer), Descriptor::Ref(reader)) => {
+                self.nested("*", writer, reader, Position::Reference);
+            }
+            (
+                Descriptor::Enum {
+                    variants: writer,
+                    discriminant: writer_discriminant,
+                    ..
+                },
+                Descriptor::Enum {
+                    variants: reader,
+                    non_exhaustive,
+                    discriminant: reader_discriminant,
+                    ..
+                },
+            ) => {
+                if writer_discriminant != reader_discriminant {
+                    self.push(IncompatibilityKind::Mismatch {
+                        writer: (**writer_discriminant).clone(),
+                        reader: (**reader_discriminant).clone(),
+                    });
+                    return;
+                }
+
+                for writer in writer {
+                    match reader.iter().find(|reader| reader.id == writer.id) {
+                        // Non-exhaustive reader handles unknown variants.
+                        None if *non_exhaustive => {}
+                        None => {
+                            self.push(IncompatibilityKind::UnknownVariant {
+                                name: writer.name.clone(),
+                                index: writer.id,
+                            });
+                        }
+                        Some(reader) => {
+                            let len = self.path.len();
+                            self.path.push_str("::");
+                            self.path.push_str(&reader.name);
+                            self.check_fields(&fields(&writer.fields), &fields(&reader.fields));
+                            self.path.truncate(len);
+                        }
+                    }
+                }
+            }
+            (
+                Descriptor::Struct {
+                    fields: writer,
+                    extensible: true,
+                    ..
+                },
+                Descriptor::Struct {
+                    fields: reader,
+                    extensible: true,
+                    ..
+                },
+            ) => {
+                // Unknown fields are skipped by reader
+                // and missing fields are set to default.
+                for (writer, reader) in writer.iter().zip(reader) {
+                    let segment = alloc::format!(".{}", reader.name);
+                    self.nested(&segment, &writer.formula, &reader.formula, Position::Field);
+                }
+            }
+            // `str` is valid `Bytes`.
+            (Descriptor::Str, Descriptor::Bytes) => {}
+            // Unnamed opaque formulas can't be compared.
+            (Descriptor::Opaque { name: writer, .. }, Descriptor::Opaque { name: reader, .. })
+                if !writer.is_empty() && writer == reader => {}
+            _ => match (sequence(writer), sequence(reader)) {
+                (Some(writer), Some(reader)) => self.check_fields(&writer, &reader),
+                _ => self.push(IncompatibilityKind::Mismatch {
+                    writer: writer.clone(),
+                    reader: reader.clone(),
+                }),
+            },
+        }
+    }
+
+    fn check_fields(
+        &mut self,
+        writer: &[(Cow<'_, str>, &Descriptor)],
+        reader: &[(Cow<'_, str>, &Descriptor)],
+    ) {
+        for (idx, ((_, writer_field), (name, reader_field))) in
+            writer.iter().zip(reader.iter()).enumerate()
+        {
+            let position = if idx + 1 == writer.len() && idx + 1 == reader.len() {
+                Position::Last
+            } else {
+                Position::Field
+            };
+
+            let len = self.path.len();
+            self.path.push('.');
+            self.path.push_str(name);
+            self.check(writer_field, reader_field, position);
+            self.path.truncate(len);
+        }
+
+        for (name, _) in writer.iter().skip(reader.len()) {
+            self.push(IncompatibilityKind::UnknownField {
+                name: name.clone().into_owned(),
+            });
+        }
+
+        for (name, _) in reader.iter().skip(writer.len()) {
+            self.push(IncompatibilityKind::MissingField {
+                name: name.clone().into_owned(),
+            });
+        }
+    }
+
+    fn check_size(&mut self, writer: &Descriptor, reader: &Descriptor, position: Position) {
+        let writer_size = writer.max_stack_size();
+        let reader_size = reader.max_stack_size();
+
+        let compatible = match position {
+            Position::Field => writer_size == reader_size,
+            Position::Last => match (writer_size, reader_size) {
+                (_, None) => true,
+                (None, Some(_)) => false,
+                (Some(writer_size), Some(reader_size)) => {
+                    writer_size == reader_size
+                        || (!reader.exact_size() && writer_size <= reader_size)
+                }
+            },
+            Position::Reference => {
+                writer.reference_size() == reader.reference_size()
+                    && match (reader_size, reader.exact_size()) {
+                        (Some(_), true) => writer_size == reader_size,
+                        _ => true,
+                    }
+            }
+        };
+
+        if !compatible {
+            self.push(IncompatibilityKind::StackSize {
+                writer: writer_size,
+                reader: reader_size,
+            });
+        }
+    }
+}
+
+/// Returns `false` if descriptor contains unnamed opaque formulas,
+/// equal descriptors of such formulas may describe different layouts.
+fn comparable(descriptor: &Descriptor) -> bool {
+    match descriptor {
+        Descriptor::Slice(value)
+        | Descriptor::Array(value, _)
+        | Descriptor::Option(value)
+        | Descriptor::Ref(value)
+        | Descriptor::BigEndian(value)
+        | Descriptor::Packed(value)
+        | Descriptor::BitSlice(value) => comparable(value),
+        Descriptor::Tuple(elements) => elements.iter().all(comparable),
+        Descriptor::Struct { fields, .. } => fields.iter().all(|field| comparable(&field.formula)),
+        Descriptor::Enum {
+            variants,
+            discriminant,
+            ..
+        } => {
+            comparable(discriminant)
+                && variants
+                    .iter()
+                    .flat_map(|variant| &variant.fields)
+                    .all(|field| comparable(&field.formula))
+        }
+        Descriptor::Opaque { name, .. } => !name.is_empty(),
+        _ => true,
+    }
+}
+
+fn fields(fields: &[crate::descriptor::Field]) -> Vec<(Cow<'_, str>, &Descriptor)> {
+    fields
+        .iter()
+        .map(|field| (Cow::Borrowed(field.name.as_str()), &field.formula))
+        .collect()
+}
+
+/// Returns fields of formulas that are laid out as sequence of fields.
+fn sequence(descriptor: &Descriptor) -> Option<Vec<(Cow<'_, str>, &Descriptor)>> {
+    match descriptor {
+        Descriptor::Unit => Some(Vec::new()),
+        Descriptor::Tuple(elements) => Some(
+            elements
+                .iter()
+                .enumerate()
+                .map(|(idx, element)| (Cow::Owned(alloc::format!("{idx}")), element))
+                .collect(),
+        ),
+        Descriptor::Struct {
+            fields: f,
+            extensible: false,
+            ..
+        } => Some(fields(f)),
+        _ => None,
+    }
+}
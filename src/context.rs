@@ -0,0 +1,211 @@
+//!
+//! This module provides deserialization errors with context.
+//!
+
+#[cfg(feature = "error-context")]
+use core::fmt;
+
+#[cfg(feature = "error-context")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "error-context")]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "error-context")]
+use crate::{
+    deserialize::{deserialize, deserialize_with, Deserialize, DeserializeError},
+    formula::Formula,
+};
+
+/// Path and location of the failure collected while deserialization unwinds.
+#[cfg(feature = "error-context")]
+#[derive(Default)]
+struct Trace {
+    /// Path segments from innermost to outermost.
+    path: Vec<&'static str>,
+
+    /// Offset in the input where the failure happened.
+    offset: Option<usize>,
+}
+
+/// Handle to the trace shared by deserializers of nested values.
+/// Zero-sized unless "error-context" feature is enabled.
+#[derive(Clone)]
+pub(crate) struct Tracer {
+    #[cfg(feature = "error-context")]
+    trace: Option<Arc<Mutex<Trace>>>,
+}
+
+impl Tracer {
+    pub(crate) const NONE: Self = Tracer {
+        #[cfg(feature = "error-context")]
+        trace: None,
+    };
+
+    /// Records offset in the input where read failed.
+    /// Only the first, innermost, offset is kept.
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub(crate) fn fail(&self, offset: usize) {
+        #[cfg(feature = "error-context")]
+        if let Some(trace) = &self.trace {
+            if let Ok(mut trace) = trace.lock() {
+                trace.offset.get_or_insert(offset);
+            }
+        }
+    }
+
+    /// Records path segment of the value that failed to deserialize.
+    /// End of stack bytes of the value is recorded as offset of the failure
+    /// if deserializer did not record one.
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub(crate) fn record(&self, segment: &'static str, value_end: usize) {
+        #[cfg(feature = "error-context")]
+        if let Some(trace) = &self.trace {
+            if let Ok(mut trace) = trace.lock() {
+                trace.path.push(segment);
+                trace.offset.get_or_insert(value_end);
+            }
+        }
+    }
+}
+
+/// Deserialization error with context of the failure.
+///
+/// Contains path to the value that failed to deserialize,
+/// e.g. `GameMessage::Client.0.ClientData.nickname`,
+/// and offset in the input where deserialization failed.
+/// Path is composed of type, variant and field names
+/// of types with derived `Deserialize`.
+#[cfg(feature = "error-context")]
+#[derive(Clone, Debug)]
+pub struct ContextError {
+    error: DeserializeError,
+    path: Vec<&'static str>,
+    offset: Option<usize>,
+}
+
+#[cfg(feature = "error-context")]
+impl ContextError {
+    /// Returns underlying deserialization error.
+    #[must_use]
+    #[inline(always)]
+    pub fn error(&self) -> DeserializeError {
+        self.error
+    }
+
+    /// Returns path to the value that failed to deserialize.
+    /// Empty if failure is not inside a derived type.
+    #[must_use]
+    pub fn path(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Returns byte offset in the input where deserialization failed.
+    ///
+    /// Values are read from the end of their bytes,
+    /// so failing read touched bytes right before this offset.
+    /// If deserializer read bytes successfully but the value
+    /// rejected them, e.g. invalid UTF-8 of a string,
+    /// it is the offset where stack bytes of the innermost value in the path end.
+    /// `None` if failure is neither detected by deserializer
+    /// nor inside a derived type.
+    #[must_use]
+    #[inline(always)]
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+}
+
+#[cfg(feature = "error-context")]
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "`{}`", self.path())?;
+            f.write_str(": ")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, "at offset {offset}: ")?;
+        }
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+#[cfg(feature = "error-context")]
+impl std::error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Deserializes value from the input.
+/// Returns deserialized value and number of bytes consumed.
+///
+/// Unlike [`deserialize`] returns [`ContextError`]
+/// with path to the failed value and offset of the failure.
+/// Successful deserialization is as fast as with [`deserialize`],
+/// failed one is repeated to collect the context.
+/// Implementation of [`Deserialize`] for `T` must not have side effects
+/// besides returning the value, as they happen twice on failure.
+///
+/// # Errors
+///
+/// Returns `ContextError` if deserialization fails.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "derive")] {
+/// # use alkahest::*;
+/// #[derive(Debug, Formula, Serialize, Deserialize)]
+/// struct ClientData {
+///     id: u32,
+///     nickname: String,
+/// }
+///
+/// let mut buffer = [0u8; 64];
+/// let data = ClientData { id: 1, nickname: String::from("alice") };
+/// let size = serialize::<ClientData, _>(data, &mut buffer).unwrap();
+///
+/// // Corrupt the nickname.
+/// let pos = buffer.iter().position(|&b| b == b'e').unwrap();
+/// buffer[pos] = 0xFF;
+///
+/// let err = deserialize_with_context::<ClientData, ClientData>(&buffer[..size]).unwrap_err();
+/// assert!(matches!(err.error(), DeserializeError::NonUtf8(_)));
+/// assert_eq!(err.path(), "ClientData.nickname");
+/// # }
+/// ```
+#[cfg(feature = "error-context")]
+pub fn deserialize_with_context<'de, F, T>(input: &'de [u8]) -> Result<(T, usize), ContextError>
+where
+    F: Formula + ?Sized,
+    T: Deserialize<'de, F>,
+{
+    match deserialize::<F, T>(input) {
+        Ok(value) => Ok(value),
+        Err(_) => {
+            let trace = Arc::new(Mutex::new(Trace::default()));
+            let tracer = Tracer {
+                trace: Some(trace.clone()),
+            };
+
+            match deserialize_with::<F, T>(input, |de| de.with_tracer(tracer)) {
+                Ok(value) => Ok(value),
+                Err(error) => {
+                    let (mut path, offset) = match trace.lock() {
+                        Ok(mut trace) => (core::mem::take(&mut trace.path), trace.offset),
+                        Err(_) => (Vec::new(), None),
+                    };
+                    path.reverse();
+                    Err(ContextError {
+                        error,
+                        path,
+                        offset,
+                    })
+                }
+            }
+        }
+    }
+}
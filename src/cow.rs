@@ -0,0 +1,154 @@
+use alloc::{borrow::Cow, vec::Vec};
+
+use crate::{
+    buffer::Buffer,
+    bytes::Bytes,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::Formula,
+    iter::owned_iter_fast_sizes,
+    serialize::{write_bytes, write_slice, Serialize, Sizes},
+};
+
+impl Serialize<str> for Cow<'_, str> {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(self.as_bytes(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(self.len()))
+    }
+}
+
+impl Serialize<str> for &Cow<'_, str> {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(self.as_bytes(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(self.len()))
+    }
+}
+
+impl<'de, 'fe: 'de> Deserialize<'fe, str> for Cow<'de, str> {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'fe>) -> Result<Self, DeserializeError> {
+        let string = <&str as Deserialize<'fe, str>>::deserialize(de)?;
+        Ok(Cow::Borrowed(string))
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'fe>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'fe, str>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl Serialize<Bytes> for Cow<'_, [u8]> {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(&self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(self.len()))
+    }
+}
+
+impl Serialize<Bytes> for &Cow<'_, [u8]> {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(self.len()))
+    }
+}
+
+impl<'de, 'fe: 'de> Deserialize<'fe, Bytes> for Cow<'de, [u8]> {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'fe>) -> Result<Self, DeserializeError> {
+        Ok(Cow::Borrowed(de.read_all_bytes()))
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'fe>) -> Result<(), DeserializeError> {
+        *self = Cow::Borrowed(de.read_all_bytes());
+        Ok(())
+    }
+}
+
+impl<F, T> Serialize<[F]> for Cow<'_, [T]>
+where
+    F: Formula,
+    T: Clone,
+    for<'ser> &'ser T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_slice(self.iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        owned_iter_fast_sizes::<F, _, _>(self.iter())
+    }
+}
+
+impl<'ser, F, T> Serialize<[F]> for &'ser Cow<'_, [T]>
+where
+    F: Formula,
+    T: Clone,
+    &'ser T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_slice(self.iter(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        owned_iter_fast_sizes::<F, _, _>(self.iter())
+    }
+}
+
+impl<'de, F, T> Deserialize<'de, [F]> for Cow<'_, [T]>
+where
+    F: Formula,
+    T: Clone + Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let vec = <Vec<T> as Deserialize<'de, [F]>>::deserialize(de)?;
+        Ok(Cow::Owned(vec))
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Vec<T> as Deserialize<'de, [F]>>::deserialize_in_place(self.to_mut(), de)
+    }
+}
@@ -0,0 +1,1110 @@
+//!
+//! This module provides runtime descriptors of formulas.
+//!
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    bits::bits_size,
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{repeat_size, sum_size, BareFormula, Formula},
+    private::VARIANT_SIZE,
+    serialize::{
+        field_size_hint, serialize_to_vec, write_exact_size_field, write_field, Serialize, Sizes,
+    },
+    size::{FixedUsize, SIZE_STACK},
+    validate::Validated,
+};
+
+/// Runtime description of a formula.
+///
+/// Descriptor is a tree that mirrors the structure of the formula.
+/// It can be obtained with [`Formula::descriptor`]
+/// and is itself serializable with alkahest using `Descriptor` as formula.
+/// This allows shipping the schema alongside the data
+/// to interpret it without access to the Rust types.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+///
+/// let descriptor = <(u32, [u8])>::descriptor();
+/// assert_eq!(
+///     descriptor,
+///     Descriptor::Tuple(vec![Descriptor::U32, Descriptor::Slice(Box::new(Descriptor::U8))]),
+/// );
+///
+/// let mut buffer = [0u8; 1024];
+/// let size = serialize::<Descriptor, _>(&descriptor, &mut buffer).unwrap();
+/// let (de, _) = deserialize::<Descriptor, Descriptor>(&buffer[..size]).unwrap();
+/// assert_eq!(descriptor, de);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Descriptor {
+    /// Unit formula `()`. Occupies no space.
+    Unit,
+
+    /// `bool` formula.
+    Bool,
+
+    /// `u8` formula.
+    U8,
+
+    /// `u16` formula. Little-endian.
+    U16,
+
+    /// `u32` formula. Little-endian.
+    U32,
+
+    /// `u64` formula. Little-endian.
+    U64,
+
+    /// `u128` formula. Little-endian.
+    U128,
+
+    /// `i8` formula.
+    I8,
+
+    /// `i16` formula. Little-endian.
+    I16,
+
+    /// `i32` formula. Little-endian.
+    I32,
+
+    /// `i64` formula. Little-endian.
+    I64,
+
+    /// `i128` formula. Little-endian.
+    I128,
+
+    /// `f32` formula. Little-endian.
+    F32,
+
+    /// `f64` formula. Little-endian.
+    F64,
+
+    /// [`Bytes`](crate::Bytes) formula.
+    Bytes,
+
+    /// `str` formula.
+    Str,
+
+    /// [`Vlq`](crate::Vlq) formula.
+    Vlq,
+
+    /// [`ZigZag`](crate::ZigZag) formula.
+    ZigZag,
+
+    /// Slice formula `[F]`.
+    Slice(Box<Descriptor>),
+
+    /// Array formula `[F; N]`.
+    Array(Box<Descriptor>, usize),
+
+    /// `Option<F>` formula.
+    Option(Box<Descriptor>),
+
+    /// [`Ref<F>`](crate::Ref) formula.
+    Ref(Box<Descriptor>),
+
+    /// [`Be<F>`](crate::Be) formula.
+    /// Big-endian primitive formula, also used by [`Ne<F>`](crate::Ne)
+    /// on big-endian targets.
+    BigEndian(Box<Descriptor>),
+
+    /// [`Bits<N>`](crate::Bits) formula.
+    Bits(u32),
+
+    /// Packed structure or enum formula derived with `#[alkahest(packed)]`.
+    /// Wraps descriptor of the structure or enum
+    /// whose fields or variant index are packed into bits.
+    Packed(Box<Descriptor>),
+
+    /// [`BitSlice<F>`](crate::BitSlice) formula.
+    BitSlice(Box<Descriptor>),
+
+    /// Tuple formula.
+    Tuple(Vec<Descriptor>),
+
+    /// Structure formula.
+    /// Tuple structures use field indices as field names.
+    Struct {
+        /// Name of the structure.
+        name: String,
+
+        /// Fields of the structure in serialization order.
+        fields: Vec<Field>,
+
+        /// Whether new fields may be appended to the structure.
+        /// Extensible structure records its size and every field is
+        /// written as if it was not the last one.
+        extensible: bool,
+    },
+
+    /// Enum formula.
+    Enum {
+        /// Name of the enum.
+        name: String,
+
+        /// Variants of the enum in declaration order.
+        variants: Vec<Variant>,
+
+        /// Whether new variants may be added to the enum.
+        /// Stack size of non-exhaustive enum is unbounded.
+        non_exhaustive: bool,
+
+        /// Formula of the variant index.
+        discriminant: Box<Descriptor>,
+    },
+
+    /// Formula that does not describe its structure.
+    /// Only properties from [`Formula`] trait are known.
+    Opaque {
+        /// Name of the formula given by its [`Formula::descriptor`].
+        /// Empty if the formula does not override [`Formula::descriptor`].
+        name: String,
+
+        /// Value of [`Formula::MAX_STACK_SIZE`].
+        max_stack_size: Option<usize>,
+
+        /// Value of [`Formula::EXACT_SIZE`].
+        exact_size: bool,
+
+        /// Value of [`Formula::HEAPLESS`].
+        heapless: bool,
+    },
+}
+
+/// Field of a structure or enum variant formula.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Field {
+    /// Name of the field.
+    pub name: String,
+
+    /// Formula of the field.
+    pub formula: Descriptor,
+}
+
+/// Variant of an enum formula.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Variant {
+    /// Index of the variant written before its fields.
+    pub id: u32,
+
+    /// Name of the variant.
+    pub name: String,
+
+    /// Fields of the variant in serialization order.
+    pub fields: Vec<Field>,
+}
+
+impl Field {
+    /// Returns new field descriptor.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(name: impl Into<String>, formula: Descriptor) -> Self {
+        Field {
+            name: name.into(),
+            formula,
+        }
+    }
+}
+
+impl Variant {
+    /// Returns new variant descriptor.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(id: u32, name: impl Into<String>, fields: Vec<Field>) -> Self {
+        Variant {
+            id,
+            name: name.into(),
+            fields,
+        }
+    }
+}
+
+fn fields_size(fields: &[Field]) -> Option<usize> {
+    fields.iter().try_fold(0, |size, field| {
+        Some(size + field.formula.max_stack_size()?)
+    })
+}
+
+fn fields_exact(fields: &[Field]) -> bool {
+    fields
+        .last()
+        .map_or(true, |field| field.formula.exact_size())
+}
+
+fn fields_heapless(fields: &[Field]) -> bool {
+    fields.iter().all(|field| field.formula.heapless())
+}
+
+impl Descriptor {
+    /// Returns descriptor of opaque formula `F` with the given name.
+    ///
+    /// Name is part of the [`fingerprint`](Descriptor::fingerprint)
+    /// and opaque formulas are compatible only if their names match,
+    /// so it should be stable and unique among formulas.
+    /// Unnamed opaque descriptor is the default value of [`Formula::descriptor`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use alkahest::*;
+    /// struct Color;
+    ///
+    /// impl Formula for Color {
+    ///     const MAX_STACK_SIZE: Option<usize> = Some(3);
+    ///     const EXACT_SIZE: bool = true;
+    ///     const HEAPLESS: bool = true;
+    ///
+    ///     fn descriptor() -> Descriptor {
+    ///         Descriptor::opaque::<Self>("Color")
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(Color::descriptor().to_string(), "Color");
+    /// ```
+    #[must_use]
+    pub fn opaque<F>(name: impl Into<String>) -> Self
+    where
+        F: Formula + ?Sized,
+    {
+        Descriptor::Opaque {
+            name: name.into(),
+            max_stack_size: F::MAX_STACK_SIZE,
+            exact_size: F::EXACT_SIZE,
+            heapless: F::HEAPLESS,
+        }
+    }
+
+    /// Returns maximum size of stack the described formula occupies.
+    ///
+    /// Matches [`Formula::MAX_STACK_SIZE`] of the described formula.
+    #[must_use]
+    pub fn max_stack_size(&self) -> Option<usize> {
+        match self {
+            Descriptor::Unit => Some(0),
+            Descriptor::Bool | Descriptor::U8 | Descriptor::I8 => Some(1),
+            Descriptor::U16 | Descriptor::I16 => Some(2),
+            Descriptor::U32 | Descriptor::I32 | Descriptor::F32 => Some(4),
+            Descriptor::U64 | Descriptor::I64 | Descriptor::F64 => Some(8),
+            Descriptor::U128 | Descriptor::I128 => Some(16),
+            Descriptor::Bytes | Descriptor::Str | Descriptor::Vlq | Descriptor::ZigZag => None,
+            Descriptor::Slice(element) => match element.max_stack_size() {
+                Some(0) => Some(SIZE_STACK),
+                _ => None,
+            },
+            Descriptor::Array(element, len) => repeat_size(element.max_stack_size(), *len),
+            Descriptor::Option(value) => sum_size(Some(1), value.max_stack_size()),
+            Descriptor::Ref(value) => Some(value.reference_size()),
+            Descriptor::BigEndian(value) => value.max_stack_size(),
+            Descriptor::Bits(_) | Descriptor::Packed(_) => self.bit_width().map(bits_size),
+            Descriptor::BitSlice(_) => None,
+            Descriptor::Tuple(elements) => elements
+                .iter()
+                .try_fold(0, |size, element| Some(size + element.max_stack_size()?)),
+            Descriptor::Struct {
+                extensible: true, ..
+            } => None,
+            Descriptor::Struct { fields, .. } => fields_size(fields),
+            Descriptor::Enum {
+                non_exhaustive: true,
+                ..
+            } => None,
+            Descriptor::Enum {
+                variants,
+                discriminant,
+                ..
+            } => {
+                let size = variants.iter().try_fold(0, |size, variant| {
+                    Some(size.max(fields_size(&variant.fields)?))
+                });
+                sum_size(discriminant.max_stack_size(), size)
+            }
+            Descriptor::Opaque { max_stack_size, .. } => *max_stack_size,
+        }
+    }
+
+    /// Returns whether `max_stack_size` is accurate.
+    ///
+    /// Matches [`Formula::EXACT_SIZE`] of the described formula.
+    #[must_use]
+    pub fn exact_size(&self) -> bool {
+        match self {
+            Descriptor::Option(value) => matches!(value.max_stack_size(), Some(0)),
+            Descriptor::Tuple(elements) => elements.last().map_or(true, Descriptor::exact_size),
+            Descriptor::Struct {
+                extensible: true, ..
+            } => false,
+            Descriptor::Struct { fields, .. } => fields_exact(fields),
+            Descriptor::Enum {
+                non_exhaustive: true,
+                ..
+            } => false,
+            Descriptor::Enum {
+                variants,
+                discriminant,
+                ..
+            } => {
+                let mut exact = discriminant.exact_size();
+                let mut common_size = None;
+                for variant in variants {
+                    exact &= fields_exact(&variant.fields);
+
+                    let var_size = fields_size(&variant.fields);
+                    exact &= match (common_size, var_size) {
+                        (_, None) => false,
+                        (None, _) => true,
+                        (Some(common_size), Some(var_size)) => common_size == var_size,
+                    };
+                    common_size = var_size;
+                }
+                exact
+            }
+            Descriptor::Opaque { exact_size, .. } => *exact_size,
+            _ => true,
+        }
+    }
+
+    /// Returns whether heap is not used by the described formula.
+    ///
+    /// Matches [`Formula::HEAPLESS`] of the described formula.
+    #[must_use]
+    pub fn heapless(&self) -> bool {
+        match self {
+            Descriptor::Slice(element) | Descriptor::Array(element, _) => element.heapless(),
+            Descriptor::Option(value) => value.heapless(),
+            Descriptor::Ref(value) => matches!(value.max_stack_size(), Some(0)),
+            Descriptor::Tuple(elements) => elements.iter().all(Descriptor::heapless),
+            Descriptor::Struct { fields, .. } => fields_heapless(fields),
+            Descriptor::Enum { variants, .. } => variants
+                .iter()
+                .all(|variant| fields_heapless(&variant.fields)),
+            Descriptor::Opaque { heapless, .. } => *heapless,
+            _ => true,
+        }
+    }
+
+    /// Returns number of bits the described formula occupies in packed data.
+    /// Returns `None` if the formula is not a bit formula.
+    ///
+    /// Matches [`BitFormula::BITS`](crate::advanced::BitFormula::BITS)
+    /// of the described formula.
+    #[must_use]
+    pub fn bit_width(&self) -> Option<u32> {
+        match self {
+            Descriptor::Bool => Some(1),
+            Descriptor::U8 | Descriptor::I8 => Some(8),
+            Descriptor::U16 | Descriptor::I16 => Some(16),
+            Descriptor::U32 | Descriptor::I32 => Some(32),
+            Descriptor::U64 | Descriptor::I64 => Some(64),
+            Descriptor::U128 | Descriptor::I128 => Some(128),
+            Descriptor::Bits(bits) => Some(*bits),
+            Descriptor::Packed(value) => match &**value {
+                Descriptor::Struct { fields, .. } => fields
+                    .iter()
+                    .try_fold(0, |bits, field| Some(bits + field.formula.bit_width()?)),
+                Descriptor::Enum { variants, .. } => Some(enum_bits(
+                    variants
+                        .iter()
+                        .map(|v| v.id as usize + 1)
+                        .max()
+                        .unwrap_or(0),
+                )),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns size of the reference to the described formula.
+    ///
+    /// Matches [`reference_size`](crate::advanced::reference_size)
+    /// of the described formula.
+    #[must_use]
+    pub fn reference_size(&self) -> usize {
+        match (self.max_stack_size(), self.exact_size()) {
+            (Some(0), _) => 0,
+            (Some(_), true) => SIZE_STACK,
+            _ => SIZE_STACK * 2,
+        }
+    }
+
+    /// Returns 64-bit fingerprint of the described formula.
+    ///
+    /// Fingerprint is FNV-1a hash of the serialized descriptor.
+    /// Equal descriptors always produce equal fingerprints,
+    /// so peers can cheaply check that they agree on the formula.
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        let mut bytes = Vec::new();
+        let size = serialize_to_vec::<Descriptor, _>(self, &mut bytes);
+
+        bytes[..size]
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    fn tag(&self) -> u32 {
+        match self {
+            Descriptor::Unit => UNIT,
+            Descriptor::Bool => BOOL,
+            Descriptor::U8 => U8,
+            Descriptor::U16 => U16,
+            Descriptor::U32 => U32,
+            Descriptor::U64 => U64,
+            Descriptor::U128 => U128,
+            Descriptor::I8 => I8,
+            Descriptor::I16 => I16,
+            Descriptor::I32 => I32,
+            Descriptor::I64 => I64,
+            Descriptor::I128 => I128,
+            Descriptor::F32 => F32,
+            Descriptor::F64 => F64,
+            Descriptor::Bytes => BYTES,
+            Descriptor::Str => STR,
+            Descriptor::Vlq => VLQ,
+            Descriptor::ZigZag => ZIG_ZAG,
+            Descriptor::Slice(_) => SLICE,
+            Descriptor::Array(_, _) => ARRAY,
+            Descriptor::Option(_) => OPTION,
+            Descriptor::Ref(_) => REF,
+            Descriptor::BigEndian(_) => BIG_ENDIAN,
+            Descriptor::Bits(_) => BITS,
+            Descriptor::Packed(_) => PACKED,
+            Descriptor::BitSlice(_) => BIT_SLICE,
+            Descriptor::Tuple(_) => TUPLE,
+            Descriptor::Struct { .. } => STRUCT,
+            Descriptor::Enum { .. } => ENUM,
+            Descriptor::Opaque { .. } => OPAQUE,
+        }
+    }
+}
+
+// Variant indices of the `Descriptor` formula.
+const UNIT: u32 = 0;
+const BOOL: u32 = 1;
+const U8: u32 = 2;
+const U16: u32 = 3;
+const U32: u32 = 4;
+const U64: u32 = 5;
+const U128: u32 = 6;
+const I8: u32 = 7;
+const I16: u32 = 8;
+const I32: u32 = 9;
+const I64: u32 = 10;
+const I128: u32 = 11;
+const F32: u32 = 12;
+const F64: u32 = 13;
+const BYTES: u32 = 14;
+const STR: u32 = 15;
+const VLQ: u32 = 16;
+const SLICE: u32 = 17;
+const ARRAY: u32 = 18;
+const OPTION: u32 = 19;
+const REF: u32 = 20;
+const TUPLE: u32 = 21;
+const STRUCT: u32 = 22;
+const ENUM: u32 = 23;
+const OPAQUE: u32 = 24;
+const BIG_ENDIAN: u32 = 25;
+const BITS: u32 = 26;
+const PACKED: u32 = 27;
+const BIT_SLICE: u32 = 28;
+const ZIG_ZAG: u32 = 29;
+
+/// Returns number of bits required to store variant index
+/// of packed enum with `count` variants.
+fn enum_bits(count: usize) -> u32 {
+    usize::BITS - count.saturating_sub(1).leading_zeros()
+}
+
+impl fmt::Display for Descriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Descriptor::Unit => f.write_str("()"),
+            Descriptor::Bool => f.write_str("bool"),
+            Descriptor::U8 => f.write_str("u8"),
+            Descriptor::U16 => f.write_str("u16"),
+            Descriptor::U32 => f.write_str("u32"),
+            Descriptor::U64 => f.write_str("u64"),
+            Descriptor::U128 => f.write_str("u128"),
+            Descriptor::I8 => f.write_str("i8"),
+            Descriptor::I16 => f.write_str("i16"),
+            Descriptor::I32 => f.write_str("i32"),
+            Descriptor::I64 => f.write_str("i64"),
+            Descriptor::I128 => f.write_str("i128"),
+            Descriptor::F32 => f.write_str("f32"),
+            Descriptor::F64 => f.write_str("f64"),
+            Descriptor::Bytes => f.write_str("Bytes"),
+            Descriptor::Str => f.write_str("str"),
+            Descriptor::Vlq => f.write_str("Vlq"),
+            Descriptor::ZigZag => f.write_str("ZigZag"),
+            Descriptor::Slice(element) => write!(f, "[{element}]"),
+            Descriptor::Array(element, len) => write!(f, "[{element}; {len}]"),
+            Descriptor::Option(value) => write!(f, "Option<{value}>"),
+            Descriptor::Ref(value) => write!(f, "Ref<{value}>"),
+            Descriptor::BigEndian(value) => write!(f, "Be<{value}>"),
+            Descriptor::Bits(bits) => write!(f, "Bits<{bits}>"),
+            Descriptor::Packed(value) => write!(f, "packed {value}"),
+            Descriptor::BitSlice(element) => write!(f, "BitSlice<{element}>"),
+            Descriptor::Tuple(elements) => {
+                f.write_str("(")?;
+                for element in elements {
+                    write!(f, "{element},")?;
+                }
+                f.write_str(")")
+            }
+            Descriptor::Opaque { name, .. } if name.is_empty() => f.write_str("opaque"),
+            Descriptor::Struct { name, .. }
+            | Descriptor::Enum { name, .. }
+            | Descriptor::Opaque { name, .. } => f.write_str(name),
+        }
+    }
+}
+
+/// Layout of the `Descriptor` formula matches layout of
+/// an enum formula with variants mirroring `Descriptor` variants.
+impl Formula for Descriptor {
+    const MAX_STACK_SIZE: Option<usize> = None;
+    const EXACT_SIZE: bool = false;
+    const HEAPLESS: bool = false;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("Descriptor")
+    }
+}
+
+impl BareFormula for Descriptor {}
+
+#[inline(always)]
+fn write_variant<F, T, B>(
+    tag: u32,
+    payload: T,
+    sizes: &mut Sizes,
+    mut buffer: B,
+) -> Result<(), B::Error>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+    B: Buffer,
+{
+    write_exact_size_field::<u32, u32, _>(tag, sizes, buffer.reborrow())?;
+    write_field::<F, T, _>(payload, sizes, buffer, true)
+}
+
+#[inline(always)]
+fn variant_size_hint<F, T>(payload: T) -> Option<Sizes>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+{
+    let mut sizes = field_size_hint::<F>(&payload, true)?;
+    sizes.add_stack(VARIANT_SIZE);
+    Some(sizes)
+}
+
+impl Serialize<Descriptor> for &Descriptor {
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        let tag = self.tag();
+        match self {
+            Descriptor::Slice(value)
+            | Descriptor::Option(value)
+            | Descriptor::Ref(value)
+            | Descriptor::BigEndian(value)
+            | Descriptor::Packed(value)
+            | Descriptor::BitSlice(value) => {
+                write_variant::<Descriptor, _, _>(tag, &**value, sizes, buffer)
+            }
+            Descriptor::Bits(bits) => write_variant::<u32, _, _>(tag, bits, sizes, buffer),
+            Descriptor::Array(element, len) => write_variant::<(Descriptor, FixedUsize), _, _>(
+                tag,
+                (&**element, *len),
+                sizes,
+                buffer,
+            ),
+            Descriptor::Tuple(elements) => {
+                write_variant::<Vec<Descriptor>, _, _>(tag, elements, sizes, buffer)
+            }
+            Descriptor::Struct {
+                name,
+                fields,
+                extensible,
+            } => write_variant::<(String, Vec<Field>, bool), _, _>(
+                tag,
+                (name, fields, extensible),
+                sizes,
+                buffer,
+            ),
+            Descriptor::Enum {
+                name,
+                variants,
+                non_exhaustive,
+                discriminant,
+            } => write_variant::<(String, Vec<Variant>, bool, Descriptor), _, _>(
+                tag,
+                (name, variants, non_exhaustive, &**discriminant),
+                sizes,
+                buffer,
+            ),
+            Descriptor::Opaque {
+                name,
+                max_stack_size,
+                exact_size,
+                heapless,
+            } => write_variant::<(String, Option<FixedUsize>, bool, bool), _, _>(
+                tag,
+                (name, max_stack_size, exact_size, heapless),
+                sizes,
+                buffer,
+            ),
+            _ => write_variant::<(), _, _>(tag, (), sizes, buffer),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> Option<Sizes> {
+        match self {
+            Descriptor::Slice(value)
+            | Descriptor::Option(value)
+            | Descriptor::Ref(value)
+            | Descriptor::BigEndian(value)
+            | Descriptor::Packed(value)
+            | Descriptor::BitSlice(value) => variant_size_hint::<Descriptor, _>(&**value),
+            Descriptor::Bits(bits) => variant_size_hint::<u32, _>(bits),
+            Descriptor::Array(element, len) => {
+                variant_size_hint::<(Descriptor, FixedUsize), _>((&**element, *len))
+            }
+            Descriptor::Tuple(elements) => variant_size_hint::<Vec<Descriptor>, _>(elements),
+            Descriptor::Struct {
+                name,
+                fields,
+                extensible,
+            } => variant_size_hint::<(String, Vec<Field>, bool), _>((name, fields, *extensible)),
+            Descriptor::Enum {
+                name,
+                variants,
+                non_exhaustive,
+                discriminant,
+            } => variant_size_hint::<(String, Vec<Variant>, bool, Descriptor), _>((
+                name,
+                variants,
+                *non_exhaustive,
+                &**discriminant,
+            )),
+            Descriptor::Opaque {
+                name,
+                max_stack_size,
+                exact_size,
+                heapless,
+            } => variant_size_hint::<(String, Option<FixedUsize>, bool, bool), _>((
+                name,
+                max_stack_size,
+                exact_size,
+                heapless,
+            )),
+            _ => variant_size_hint::<(), _>(()),
+        }
+    }
+}
+
+impl Serialize<Descriptor> for Descriptor {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        <&Descriptor as Serialize<Descriptor>>::serialize(&self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <&Descriptor as Serialize<Descriptor>>::size_hint(&self)
+    }
+}
+
+impl<'de> Deserialize<'de, Descriptor> for Descriptor {
+    #[inline]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        deserialize_descriptor(de, MAX_DEPTH)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'de, Descriptor>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Maximum nesting depth of deserialized descriptors.
+const MAX_DEPTH: usize = 128;
+
+/// Deserializer of a value nested in a descriptor.
+/// Nested descriptors are deserialized explicitly to keep track of depth.
+struct Nested<'de>(Deserializer<'de>);
+
+impl<'de, F> Deserialize<'de, F> for Nested<'de>
+where
+    F: BareFormula + ?Sized,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        Ok(Nested(de))
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        self.0 = de;
+        Ok(())
+    }
+}
+
+/// Deserializes descriptor that may contain
+/// descriptors nested up to `depth` levels deep.
+///
+/// Payloads of variants are read by separate functions
+/// to keep stack frame of this recursive function small.
+fn deserialize_descriptor(
+    mut de: Deserializer<'_>,
+    depth: usize,
+) -> Result<Descriptor, DeserializeError> {
+    let Some(depth) = depth.checked_sub(1) else {
+        return Err(DeserializeError::TooDeep);
+    };
+
+    let descriptor = match de.read_value::<u32, u32>(false)? {
+        UNIT => Descriptor::Unit,
+        BOOL => Descriptor::Bool,
+        U8 => Descriptor::U8,
+        U16 => Descriptor::U16,
+        U32 => Descriptor::U32,
+        U64 => Descriptor::U64,
+        U128 => Descriptor::U128,
+        I8 => Descriptor::I8,
+        I16 => Descriptor::I16,
+        I32 => Descriptor::I32,
+        I64 => Descriptor::I64,
+        I128 => Descriptor::I128,
+        F32 => Descriptor::F32,
+        F64 => Descriptor::F64,
+        BYTES => Descriptor::Bytes,
+        STR => Descriptor::Str,
+        VLQ => Descriptor::Vlq,
+        ZIG_ZAG => Descriptor::ZigZag,
+        SLICE => Descriptor::Slice(read_descriptor(&mut de, true, depth)?),
+        ARRAY => read_array(&mut de, depth)?,
+        OPTION => Descriptor::Option(read_descriptor(&mut de, true, depth)?),
+        REF => Descriptor::Ref(read_descriptor(&mut de, true, depth)?),
+        TUPLE => Descriptor::Tuple(read_vec::<Descriptor, _>(&mut de, true, |de| {
+            deserialize_descriptor(de, depth)
+        })?),
+        STRUCT => read_struct(&mut de, depth)?,
+        ENUM => read_enum(&mut de, depth)?,
+        BIG_ENDIAN => Descriptor::BigEndian(read_descriptor(&mut de, true, depth)?),
+        BITS => Descriptor::Bits(de.read_value::<u32, _>(true)?),
+        PACKED => Descriptor::Packed(read_descriptor(&mut de, true, depth)?),
+        BIT_SLICE => Descriptor::BitSlice(read_descriptor(&mut de, true, depth)?),
+        OPAQUE => read_opaque(&mut de)?,
+        invalid => return Err(DeserializeError::WrongVariant(invalid)),
+    };
+    de.finish()?;
+    Ok(descriptor)
+}
+
+#[inline(never)]
+fn read_array(de: &mut Deserializer<'_>, depth: usize) -> Result<Descriptor, DeserializeError> {
+    let Nested(mut payload) = de.read_value::<(Descriptor, FixedUsize), Nested>(true)?;
+    let element = read_descriptor(&mut payload, false, depth)?;
+    let len = payload.read_value::<FixedUsize, usize>(true)?;
+    payload.finish()?;
+    Ok(Descriptor::Array(element, len))
+}
+
+#[inline(never)]
+fn read_struct(de: &mut Deserializer<'_>, depth: usize) -> Result<Descriptor, DeserializeError> {
+    let Nested(mut payload) = de.read_value::<(String, Vec<Field>, bool), Nested>(true)?;
+    let name = payload.read_value::<String, String>(false)?;
+    let fields = read_fields(&mut payload, false, depth)?;
+    let extensible = payload.read_value::<bool, bool>(true)?;
+    payload.finish()?;
+    Ok(Descriptor::Struct {
+        name,
+        fields,
+        extensible,
+    })
+}
+
+#[inline(never)]
+fn read_enum(de: &mut Deserializer<'_>, depth: usize) -> Result<Descriptor, DeserializeError> {
+    let Nested(mut payload) =
+        de.read_value::<(String, Vec<Variant>, bool, Descriptor), Nested>(true)?;
+    let name = payload.read_value::<String, String>(false)?;
+    let variants = read_vec::<Variant, _>(&mut payload, false, |mut de| {
+        let id = de.read_value::<u32, u32>(false)?;
+        let name = de.read_value::<String, String>(false)?;
+        let fields = read_fields(&mut de, true, depth)?;
+        de.finish()?;
+        Ok(Variant { id, name, fields })
+    })?;
+    let non_exhaustive = payload.read_value::<bool, bool>(false)?;
+    let discriminant = read_descriptor(&mut payload, true, depth)?;
+    payload.finish()?;
+    Ok(Descriptor::Enum {
+        name,
+        variants,
+        non_exhaustive,
+        discriminant,
+    })
+}
+
+#[inline(never)]
+fn read_opaque(de: &mut Deserializer<'_>) -> Result<Descriptor, DeserializeError> {
+    let (name, max_stack_size, exact_size, heapless) = de.read_value::<(
+        String,
+        Option<FixedUsize>,
+        bool,
+        bool,
+    ), (String, Option<usize>, bool, bool)>(
+        true
+    )?;
+    Ok(Descriptor::Opaque {
+        name,
+        max_stack_size,
+        exact_size,
+        heapless,
+    })
+}
+
+/// Reads nested descriptor field.
+#[inline(never)]
+fn read_descriptor(
+    de: &mut Deserializer<'_>,
+    last: bool,
+    depth: usize,
+) -> Result<Box<Descriptor>, DeserializeError> {
+    let Nested(de) = de.read_value::<Descriptor, Nested>(last)?;
+    deserialize_descriptor(de, depth).map(Box::new)
+}
+
+/// Reads `Vec<Field>` field with nested descriptors.
+#[inline(never)]
+fn read_fields(
+    de: &mut Deserializer<'_>,
+    last: bool,
+    depth: usize,
+) -> Result<Vec<Field>, DeserializeError> {
+    read_vec::<Field, _>(de, last, |mut de| {
+        let name = de.read_value::<String, String>(false)?;
+        let formula = *read_descriptor(&mut de, true, depth)?;
+        de.finish()?;
+        Ok(Field { name, formula })
+    })
+}
+
+/// Reads `Vec<F>` field, deserializing each element with `read`.
+#[inline]
+fn read_vec<'de, F, T>(
+    de: &mut Deserializer<'de>,
+    last: bool,
+    mut read: impl FnMut(Deserializer<'de>) -> Result<T, DeserializeError>,
+) -> Result<Vec<T>, DeserializeError>
+where
+    F: BareFormula,
+{
+    let Nested(de) = de.read_value::<Vec<F>, Nested>(last)?;
+    let mut iter = de.into_unsized_iter::<F, Nested>();
+    let mut elements = Vec::new();
+    for element in iter.by_ref() {
+        elements.push(read(element?.0)?);
+    }
+    iter.finish()?;
+    Ok(elements)
+}
+
+impl<'de> Deserialize<'de, Descriptor> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Descriptor as Deserialize<'de, Descriptor>>::deserialize(de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Descriptor>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Layout of the `Field` formula matches `(String, Descriptor)`.
+impl Formula for Field {
+    const MAX_STACK_SIZE: Option<usize> = <(String, Descriptor) as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <(String, Descriptor) as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <(String, Descriptor) as Formula>::HEAPLESS;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("Field")
+    }
+}
+
+impl BareFormula for Field {}
+
+impl Serialize<Field> for &Field {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        <(&String, &Descriptor) as Serialize<(String, Descriptor)>>::serialize(
+            (&self.name, &self.formula),
+            sizes,
+            buffer,
+        )
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <(&String, &Descriptor) as Serialize<(String, Descriptor)>>::size_hint(&(
+            &self.name,
+            &self.formula,
+        ))
+    }
+}
+
+impl Serialize<Field> for Field {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        <&Field as Serialize<Field>>::serialize(&self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <&Field as Serialize<Field>>::size_hint(&self)
+    }
+}
+
+impl<'de> Deserialize<'de, Field> for Field {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let (name, formula) =
+            <(String, Descriptor) as Deserialize<'de, (String, Descriptor)>>::deserialize(de)?;
+        Ok(Field { name, formula })
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'de, Field>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de, Field> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Field as Deserialize<'de, Field>>::deserialize(de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Field>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Layout of the `Variant` formula matches `(u32, String, Vec<Field>)`.
+impl Formula for Variant {
+    const MAX_STACK_SIZE: Option<usize> = <(u32, String, Vec<Field>) as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <(u32, String, Vec<Field>) as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <(u32, String, Vec<Field>) as Formula>::HEAPLESS;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("Variant")
+    }
+}
+
+impl BareFormula for Variant {}
+
+impl Serialize<Variant> for &Variant {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        <(u32, &String, &Vec<Field>) as Serialize<(u32, String, Vec<Field>)>>::serialize(
+            (self.id, &self.name, &self.fields),
+            sizes,
+            buffer,
+        )
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <(u32, &String, &Vec<Field>) as Serialize<(u32, String, Vec<Field>)>>::size_hint(&(
+            self.id,
+            &self.name,
+            &self.fields,
+        ))
+    }
+}
+
+impl Serialize<Variant> for Variant {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        <&Variant as Serialize<Variant>>::serialize(&self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <&Variant as Serialize<Variant>>::size_hint(&self)
+    }
+}
+
+impl<'de> Deserialize<'de, Variant> for Variant {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let (id, name, fields) = <(u32, String, Vec<Field>) as Deserialize<
+            'de,
+            (u32, String, Vec<Field>),
+        >>::deserialize(de)?;
+        Ok(Variant { id, name, fields })
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'de, Variant>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de, Variant> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Variant as Deserialize<'de, Variant>>::deserialize(de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Variant>>::deserialize(de)?;
+        Ok(())
+    }
+}
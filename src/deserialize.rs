@@ -1,8 +1,20 @@
-use core::{iter::FusedIterator, marker::PhantomData, str::Utf8Error};
+use core::{cell::Cell, fmt, iter::FusedIterator, marker::PhantomData, str::Utf8Error};
+
+#[cfg(feature = "alloc")]
+use core::any::TypeId;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+
+#[cfg(feature = "alloc")]
+use crate::shared::SharedValues;
 
 use crate::{
+    canonical::verify_canonical,
+    context::Tracer,
     formula::{reference_size, unwrap_size, Formula},
     size::{FixedIsizeType, FixedUsize, FixedUsizeType, SIZE_STACK},
+    validate::Validated,
 };
 
 #[inline(always)]
@@ -45,6 +57,72 @@ pub enum DeserializeError {
 
     /// Data is incompatible with the type to be deserialized.
     Incompatible,
+
+    /// Value is not a valid unicode scalar value where `char` is expected.
+    InvalidChar(u32),
+
+    /// Zero value where non-zero integer is expected.
+    ZeroValue,
+
+    /// Nanoseconds value of a time is not less than one second.
+    InvalidNanos(u32),
+
+    /// Data is not the canonical encoding of the value.
+    ///
+    /// Returned by strict deserializer, see [`verify_canonical`],
+    /// when input has unused bytes or encodes the value
+    /// differently than serialization would.
+    NonCanonical,
+
+    /// Recursive value is nested deeper than deserializer allows.
+    TooDeep,
+
+    /// Looked up key is too large to be hashed without allocation.
+    KeyTooLarge,
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeserializeError::OutOfBounds => f.write_str("input buffer is too small"),
+            DeserializeError::WrongAddress => f.write_str("relative address is invalid"),
+            DeserializeError::WrongLength => f.write_str("value length is invalid"),
+            DeserializeError::InvalidUsize(value) => {
+                write!(f, "size value {value} does not fit `usize`")
+            }
+            DeserializeError::InvalidIsize(value) => {
+                write!(f, "size value {value} does not fit `isize`")
+            }
+            DeserializeError::WrongVariant(variant) => {
+                write!(f, "enum variant {variant} is invalid")
+            }
+            DeserializeError::NonUtf8(err) => write!(f, "string is not UTF8: {err}"),
+            DeserializeError::IntegerOverflow => f.write_str("integer value overflows target type"),
+            DeserializeError::Incompatible => f.write_str("data is incompatible with the type"),
+            DeserializeError::InvalidChar(value) => {
+                write!(f, "value {value:#x} is not a valid `char`")
+            }
+            DeserializeError::ZeroValue => f.write_str("zero value for non-zero integer"),
+            DeserializeError::InvalidNanos(nanos) => {
+                write!(f, "nanoseconds value {nanos} exceeds one second")
+            }
+            DeserializeError::NonCanonical => f.write_str("data is not in canonical encoding"),
+            DeserializeError::TooDeep => f.write_str("value is nested too deeply"),
+            DeserializeError::KeyTooLarge => {
+                f.write_str("looked up key is too large to be hashed without allocation")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DeserializeError::NonUtf8(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 /// Trait for types that can be deserialized
@@ -87,6 +165,108 @@ pub struct Deserializer<'de> {
     /// Input buffer sub-slice usable for deserialization.
     input: &'de [u8],
     stack: usize,
+    tracer: Tracer,
+    strict: Option<Strict<'de>>,
+
+    /// Pointers deserialized from `Shared` formulas.
+    /// Shared by all deserializers of the input.
+    /// `None` if sharing of pointers is not restored.
+    #[cfg(feature = "alloc")]
+    shared: Option<SharedValues>,
+}
+
+/// State of strict deserializer.
+#[derive(Clone, Copy)]
+struct Strict<'de> {
+    /// Offset in the input where next referenced value must begin.
+    /// Shared by all deserializers of the input.
+    /// `None` if layout of referenced values is not checked.
+    heap: Option<&'de Cell<usize>>,
+
+    /// Unused stack bytes are padding and must be zeroed.
+    padded: bool,
+
+    /// End of the referenced value whose stack is read by this deserializer.
+    /// Values it references must precede the stack.
+    end: Option<usize>,
+}
+
+impl<'de> Strict<'de> {
+    /// Checks stack bytes that value left unused.
+    /// Closes referenced value if stack belongs to one.
+    #[inline(always)]
+    fn finish(self, unused: &[u8], start: usize) -> Result<(), DeserializeError> {
+        // Only padding may be left unused and it must be zeroed.
+        let zeroed_padding = self.padded && unused.iter().all(|&b| b == 0);
+        if !unused.is_empty() && !zeroed_padding {
+            return cold_err(DeserializeError::NonCanonical);
+        }
+        match self.end {
+            None => Ok(()),
+            Some(end) => self.close(start, end),
+        }
+    }
+
+    /// Checks that referenced value with stack starting at `start`
+    /// immediately follows previously read referenced values.
+    /// Next referenced value must begin at `end`.
+    #[inline(always)]
+    fn close(self, start: usize, end: usize) -> Result<(), DeserializeError> {
+        if let Some(heap) = self.heap {
+            if heap.get() != start {
+                return cold_err(DeserializeError::NonCanonical);
+            }
+            heap.set(end);
+        }
+        Ok(())
+    }
+
+    /// Checks reference to value with formula `F`.
+    /// `start` is where stack of the deserializer that read the reference begins.
+    /// Returns state for deserializer of the referenced value.
+    #[inline(always)]
+    fn deref<F>(
+        self,
+        start: usize,
+        address: usize,
+        size: usize,
+    ) -> Result<Strict<'de>, DeserializeError>
+    where
+        F: Formula + ?Sized,
+    {
+        let mut strict = Strict {
+            heap: self.heap,
+            padded: false,
+            end: None,
+        };
+
+        if self.heap.is_none() {
+            return Ok(strict);
+        }
+
+        // Reference to zero-sized value has no address.
+        if F::MAX_STACK_SIZE == Some(0) {
+            if let Some(end) = self.end {
+                self.close(start, end)?;
+            }
+            return Ok(strict);
+        }
+
+        // When the reference is the only content of referenced value,
+        // value it points to is written right before it.
+        let end = match self.end {
+            None => address,
+            Some(end) if address == start => end,
+            Some(_) => return cold_err(DeserializeError::NonCanonical),
+        };
+
+        if F::HEAPLESS {
+            strict.close(address - size, end)?;
+        } else {
+            strict.end = Some(end);
+        }
+        Ok(strict)
+    }
 }
 
 impl<'de> Deserializer<'de> {
@@ -108,17 +288,159 @@ impl<'de> Deserializer<'de> {
     #[inline(always)]
     pub const fn new_unchecked(stack: usize, input: &'de [u8]) -> Self {
         debug_assert!(stack <= input.len());
-        Deserializer { input, stack }
+        Deserializer {
+            input,
+            stack,
+            tracer: Tracer::NONE,
+            strict: None,
+            #[cfg(feature = "alloc")]
+            shared: None,
+        }
+    }
+
+    /// Creates deserializer for nested value
+    /// that shares tracer, strict mode and shared values with this one.
+    /// Unused stack bytes of `padded` value are padding.
+    #[inline(always)]
+    fn nested(&self, stack: usize, input: &'de [u8], padded: bool) -> Self {
+        debug_assert!(stack <= input.len());
+        Deserializer {
+            input,
+            stack,
+            tracer: self.tracer.clone(),
+            strict: self.strict.map(|strict| Strict {
+                heap: strict.heap,
+                padded,
+                end: None,
+            }),
+            #[cfg(feature = "alloc")]
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Attaches tracer that records context of errors.
+    #[cfg(feature = "error-context")]
+    #[inline(always)]
+    pub(crate) fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
+    /// Attaches table of shared values
+    /// that restores sharing of pointers with `Shared` formulas.
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    pub(crate) fn with_shared(mut self, shared: SharedValues) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    /// Looks up pointer previously deserialized
+    /// from the same location in the input.
+    ///
+    /// Deserializers without table of shared values always return `None`.
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    pub(crate) fn find_shared<P>(&self) -> Option<P>
+    where
+        P: Clone + 'static,
+    {
+        let shared = self.shared.as_ref()?.borrow();
+        let key = (self.input.len(), self.stack, TypeId::of::<P>());
+        shared.get(&key)?.downcast_ref::<P>().cloned()
+    }
+
+    /// Remembers pointer deserialized from this location in the input.
+    ///
+    /// Deserializers without table of shared values ignore this call.
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    pub(crate) fn add_shared<P>(&self, ptr: &P)
+    where
+        P: Clone + 'static,
+    {
+        if let Some(shared) = &self.shared {
+            let key = (self.input.len(), self.stack, TypeId::of::<P>());
+            shared.borrow_mut().insert(key, Box::new(ptr.clone()));
+        }
+    }
+
+    /// Makes deserializer strict.
+    /// Referenced values must be laid out starting from offset in `heap`,
+    /// their layout is not checked if `heap` is `None`.
+    #[inline(always)]
+    fn strict(mut self, heap: Option<&'de Cell<usize>>) -> Self {
+        self.strict = Some(Strict {
+            heap,
+            padded: false,
+            end: None,
+        });
+        self
+    }
+
+    /// Returns `true` if deserializer is strict.
+    ///
+    /// Strict deserializer accepts only canonical encoding of values,
+    /// see [`verify_canonical`].
+    /// Implementations of [`Deserialize`] that accept multiple encodings
+    /// of the same value should reject all but canonical one
+    /// with [`DeserializeError::NonCanonical`] when this returns `true`.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_strict(&self) -> bool {
+        self.strict.is_some()
+    }
+
+    /// Returns `true` if unused stack bytes are padding.
+    #[inline(always)]
+    fn is_padded(&self) -> bool {
+        matches!(self.strict, Some(Strict { padded: true, .. }))
+    }
+
+    /// Returns offset in the input where bytes of the next value end.
+    /// Values are read from the end of the stack.
+    #[inline(always)]
+    pub(crate) fn stack_end(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Records path segment of the value that failed to deserialize
+    /// and end of its stack bytes when deserializing with context.
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn trace_error(
+        &self,
+        error: DeserializeError,
+        segment: &'static str,
+        value_end: usize,
+    ) -> DeserializeError {
+        self.tracer.record(segment, value_end);
+        error
+    }
+
+    /// Returns error of the read that failed at `offset` in the input.
+    /// Records the offset when deserializing with context.
+    #[cold]
+    #[inline(never)]
+    fn fail<T>(&self, error: DeserializeError, offset: usize) -> Result<T, DeserializeError> {
+        self.tracer.fail(offset);
+        Err(error)
     }
 
     #[inline(always)]
     #[track_caller]
     pub(crate) fn sub(&mut self, stack: usize) -> Result<Self, DeserializeError> {
+        self.sub_padded(stack, false)
+    }
+
+    #[inline(always)]
+    #[track_caller]
+    fn sub_padded(&mut self, stack: usize, padded: bool) -> Result<Self, DeserializeError> {
         if self.stack < stack {
-            return cold_err(DeserializeError::WrongLength);
+            return self.fail(DeserializeError::WrongLength, self.input.len());
         }
 
-        let sub = Deserializer::new_unchecked(stack, self.input);
+        let sub = self.nested(stack, self.input, padded);
 
         self.stack -= stack;
         let end = self.input.len() - stack;
@@ -126,6 +448,13 @@ impl<'de> Deserializer<'de> {
         Ok(sub)
     }
 
+    /// Returns `true` if there are no bytes left on stack.
+    #[must_use]
+    #[inline(always)]
+    pub const fn is_empty(&self) -> bool {
+        self.stack == 0
+    }
+
     /// Reads specified number of bytes from the input buffer.
     /// Returns slice of bytes.
     /// Advances the input buffer.
@@ -136,7 +465,7 @@ impl<'de> Deserializer<'de> {
     #[inline(always)]
     pub fn read_bytes(&mut self, len: usize) -> Result<&'de [u8], DeserializeError> {
         if len > self.stack {
-            return cold_err(DeserializeError::WrongLength);
+            return self.fail(DeserializeError::WrongLength, self.input.len());
         }
         let at = self.input.len() - len;
         let (head, tail) = self.input.split_at(at);
@@ -155,7 +484,7 @@ impl<'de> Deserializer<'de> {
     #[inline(always)]
     pub fn read_byte(&mut self) -> Result<u8, DeserializeError> {
         if self.stack == 0 {
-            return cold_err(DeserializeError::WrongLength);
+            return self.fail(DeserializeError::WrongLength, self.input.len());
         }
 
         let [head @ .., last] = self.input else {
@@ -176,7 +505,7 @@ impl<'de> Deserializer<'de> {
     #[inline(always)]
     pub fn read_byte_array<const N: usize>(&mut self) -> Result<[u8; N], DeserializeError> {
         if N > self.stack {
-            return cold_err(DeserializeError::WrongLength);
+            return self.fail(DeserializeError::WrongLength, self.input.len());
         }
         let at = self.input.len() - N;
 
@@ -209,14 +538,15 @@ impl<'de> Deserializer<'de> {
         F: Formula + ?Sized,
         T: Deserialize<'de, F>,
     {
-        let stack = match (F::MAX_STACK_SIZE, F::EXACT_SIZE, last) {
-            (None, _, false) => self.read_value::<FixedUsize, usize>(false)?,
-            (None, _, true) => self.stack,
-            (Some(max_stack), false, true) => max_stack.min(self.stack),
-            (Some(max_stack), _, _) => max_stack,
+        // Non-last values without exact size are padded to maximum size.
+        let (stack, padded) = match (F::MAX_STACK_SIZE, F::EXACT_SIZE, last) {
+            (None, _, false) => (self.read_value::<FixedUsize, usize>(false)?, false),
+            (None, _, true) => (self.stack, self.is_padded()),
+            (Some(max_stack), false, true) => (max_stack.min(self.stack), self.is_padded()),
+            (Some(max_stack), exact, _) => (max_stack, !exact),
         };
 
-        <T as Deserialize<'de, F>>::deserialize(self.sub(stack)?)
+        <T as Deserialize<'de, F>>::deserialize(self.sub_padded(stack, padded)?)
     }
 
     /// Reads and deserializes field from the back of input buffer.
@@ -235,13 +565,13 @@ impl<'de> Deserializer<'de> {
 
         if self.stack < stack {
             self.stack = 0;
-            return cold_err(DeserializeError::WrongLength);
+            return self.fail(DeserializeError::WrongLength, self.input.len());
         }
 
         let input_back = &self.input[..self.input.len() - self.stack + stack];
         self.stack -= stack;
 
-        let sub = Deserializer::new_unchecked(stack, input_back);
+        let sub = self.nested(stack, input_back, !F::EXACT_SIZE);
         <T as Deserialize<'de, F>>::deserialize(sub)
     }
 
@@ -256,13 +586,13 @@ impl<'de> Deserializer<'de> {
         F: Formula + ?Sized,
         T: Deserialize<'de, F> + ?Sized,
     {
-        let stack = match (last, F::MAX_STACK_SIZE) {
-            (true, _) => self.stack,
-            (false, Some(max_stack)) => max_stack,
-            (false, None) => self.read_value::<FixedUsize, usize>(false)?,
+        let (stack, padded) = match (last, F::MAX_STACK_SIZE) {
+            (true, _) => (self.stack, self.is_padded()),
+            (false, Some(max_stack)) => (max_stack, !F::EXACT_SIZE),
+            (false, None) => (self.read_value::<FixedUsize, usize>(false)?, false),
         };
 
-        <T as Deserialize<'de, F>>::deserialize_in_place(place, self.sub(stack)?)
+        <T as Deserialize<'de, F>>::deserialize_in_place(place, self.sub_padded(stack, padded)?)
     }
 
     /// Reads and deserializes reference from the input buffer.
@@ -278,19 +608,35 @@ impl<'de> Deserializer<'de> {
     {
         let reference_size = reference_size::<F>();
         if self.stack < reference_size {
-            return Err(DeserializeError::OutOfBounds);
+            return self.fail(DeserializeError::OutOfBounds, self.input.len());
         }
 
         let (head, tail) = self.input.split_at(self.input.len() - reference_size);
         let (address, size) = read_reference::<F>(tail, head.len());
 
         if address > head.len() {
-            return Err(DeserializeError::WrongAddress);
+            return self.fail(DeserializeError::WrongAddress, self.input.len());
         }
 
         let input = &head[..address];
 
-        Deserializer::new(size, input)
+        if size > input.len() {
+            return self.fail(DeserializeError::OutOfBounds, address);
+        }
+
+        let mut de = self.nested(size, input, false);
+        if let Some(strict) = self.strict {
+            // Rest of the stack is unused.
+            let start = self.input.len() - self.stack;
+            let unused = &head[start..];
+            Strict {
+                end: None,
+                ..strict
+            }
+            .finish(unused, start)?;
+            de.strict = Some(strict.deref::<F>(start, address, size)?);
+        }
+        Ok(de)
     }
 
     /// Converts deserializer into iterator over deserialized values with
@@ -320,6 +666,23 @@ impl<'de> Deserializer<'de> {
         }
     }
 
+    /// Returns number of values with specified formula in this deserializer.
+    /// The formula must be sized.
+    #[inline(always)]
+    pub(crate) fn sized_len<F>(&self) -> usize
+    where
+        F: Formula + ?Sized,
+    {
+        match F::MAX_STACK_SIZE {
+            None => panic!("Formula must be sized"),
+            Some(0) => self
+                .clone()
+                .read_value::<FixedUsize, usize>(true)
+                .unwrap_or(0),
+            Some(max_stack) => self.stack / max_stack,
+        }
+    }
+
     /// Converts deserializer into iterator over deserialized values with
     /// specified formula.
     #[inline(always)]
@@ -383,15 +746,26 @@ impl<'de> Deserializer<'de> {
         }
     }
 
-    // /// Finishing check for deserializer.
-    // #[inline(always)]
-    // pub fn finish(self) -> Result<(), DeserializeError> {
-    //     if self.stack == 0 {
-    //         Ok(())
-    //     } else {
-    //         Err(DeserializeError::WrongLength)
-    //     }
-    // }
+    /// Finishes deserialization of the value.
+    ///
+    /// Implementations of [`Deserialize`] call it after reading the value.
+    /// Does nothing unless deserializer is strict.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DeserializeError::NonCanonical`] if deserializer is strict
+    /// and stack has bytes that are not used by the value,
+    /// other than zeroed padding.
+    #[inline(always)]
+    pub fn finish(self) -> Result<(), DeserializeError> {
+        match self.strict {
+            None => Ok(()),
+            Some(strict) => {
+                let start = self.input.len() - self.stack;
+                strict.finish(&self.input[start..], start)
+            }
+        }
+    }
 
     /// Skips specified number of values with specified formula.
     #[inline(always)]
@@ -444,6 +818,18 @@ where
         self.upper == 0 || self.stack_empty()
     }
 
+    /// Finishes deserialization of the elements.
+    /// See [`Deserializer::finish`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DeserializeError::NonCanonical`] if deserializer is strict
+    /// and stack has bytes that are not used by the elements.
+    #[inline(always)]
+    pub fn finish(self) -> Result<(), DeserializeError> {
+        self.de.finish()
+    }
+
     /// Returns true if no items remains in the iterator.
     #[inline(always)]
     fn stack_empty(&self) -> bool {
@@ -535,23 +921,29 @@ where
                 }
                 let sub = Deserializer::new_unchecked(SIZE_STACK, self.de.input);
                 self.de.input = &self.de.input[..self.de.input.len() - SIZE_STACK];
+                self.de.stack -= SIZE_STACK;
 
                 let stack = match <usize as Deserialize<'de, FixedUsize>>::deserialize(sub) {
-                    Ok(stack) => stack,
+                    Ok(stack) if stack <= self.de.stack => stack,
+                    Ok(_) => {
+                        self.de.stack = 0;
+                        let offset = self.de.input.len();
+                        return f(init, self.de.fail(DeserializeError::WrongLength, offset));
+                    }
                     Err(err) => {
                         self.de.stack = 0;
                         return f(init, cold_err(err));
                     }
                 };
-                let sub = Deserializer::new_unchecked(stack, self.de.input);
+                let sub = self.de.nested(stack, self.de.input, false);
                 self.de.input = &self.de.input[..self.de.input.len() - stack];
-                self.de.stack -= SIZE_STACK * stack;
+                self.de.stack -= stack;
 
                 let result = <T as Deserialize<'de, F>>::deserialize(sub);
                 init = f(init, result);
             },
             Some(0) => {
-                let sub = Deserializer::new_unchecked(0, self.de.input);
+                let sub = self.de.nested(0, self.de.input, false);
                 for _ in 0..self.upper {
                     let result = <T as Deserialize<'de, F>>::deserialize(sub.clone());
                     init = f(init, result);
@@ -560,7 +952,7 @@ where
             Some(stack) => {
                 assert_eq!(self.de.stack / stack, self.upper);
                 for _ in 0..self.upper {
-                    let sub = Deserializer::new_unchecked(stack, self.de.input);
+                    let sub = self.de.nested(stack, self.de.input, !F::EXACT_SIZE);
                     self.de.input = &self.de.input[..self.de.input.len() - stack];
 
                     let result = <T as Deserialize<'de, F>>::deserialize(sub);
@@ -615,7 +1007,7 @@ where
     {
         match Self::ELEMENT_SIZE {
             0 => {
-                let sub = Deserializer::new_unchecked(0, self.de.input);
+                let sub = self.de.nested(0, self.de.input, false);
                 for _ in 0..self.upper {
                     let result = <T as Deserialize<'de, F>>::deserialize(sub.clone());
                     init = f(init, result);
@@ -626,7 +1018,7 @@ where
                 let mut end = self.de.input.len() - stack * self.upper;
                 for _ in 0..self.upper {
                     end += stack;
-                    let sub = Deserializer::new_unchecked(stack, &self.de.input[..end]);
+                    let sub = self.de.nested(stack, &self.de.input[..end], !F::EXACT_SIZE);
 
                     let result = <T as Deserialize<'de, F>>::deserialize(sub);
                     init = f(init, result);
@@ -691,6 +1083,20 @@ where
 /// Returns `DeserializeError` if deserialization fails.
 #[inline(always)]
 pub fn deserialize<'de, F, T>(input: &'de [u8]) -> Result<(T, usize), DeserializeError>
+where
+    F: Formula + ?Sized,
+    T: Deserialize<'de, F>,
+{
+    deserialize_with::<F, T>(input, |de| de)
+}
+
+/// Deserializes value from the input
+/// with deserializer configured by `configure`.
+#[inline(always)]
+pub(crate) fn deserialize_with<'de, F, T>(
+    input: &'de [u8],
+    configure: impl FnOnce(Deserializer<'de>) -> Deserializer<'de>,
+) -> Result<(T, usize), DeserializeError>
 where
     F: Formula + ?Sized,
     T: Deserialize<'de, F>,
@@ -711,12 +1117,112 @@ where
         return Err(DeserializeError::OutOfBounds);
     }
 
-    let de = Deserializer::new_unchecked(size, &input[..address]);
+    let de = configure(Deserializer::new_unchecked(size, &input[..address]));
     let value = <T as Deserialize<'de, F>>::deserialize(de)?;
 
     Ok((value, address))
 }
 
+/// Deserializes value from the input, accepting only
+/// canonical encoding of a value.
+/// Returns deserialized value.
+///
+/// Regular deserialization ignores bytes that the value does not use,
+/// like unused stack bytes, unreferenced heap regions and padding,
+/// so different inputs may produce the same value.
+/// This function checks input with [`verify_canonical`] first
+/// and then deserializes the value with strict deserializer.
+/// Deserializing `BTreeMap` and `BTreeSet` with strict deserializer
+/// also rejects entries that are not sorted or have duplicate keys.
+/// Use it where input must not be malleable, e.g. to verify signatures.
+///
+/// Input must contain exactly one value, without trailing bytes.
+///
+/// # Errors
+///
+/// Returns `DeserializeError` if deserialization fails
+/// and [`DeserializeError::NonCanonical`] if input
+/// is not canonical encoding.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// type MyFormula = (Option<u32>, u8);
+///
+/// let mut buffer = [0u8; 64];
+/// let size = serialize::<MyFormula, _>((None::<u32>, 7u8), &mut buffer).unwrap();
+///
+/// let value = deserialize_strict::<MyFormula, (Option<u32>, u8)>(&buffer[..size]).unwrap();
+/// assert_eq!(value, (None, 7));
+///
+/// // `None` is padded to the size of `Some`.
+/// // Regular deserialization ignores non-zero padding.
+/// buffer[core::mem::size_of::<FixedUsize>() + 1] = 1;
+/// let (value, _) = deserialize::<MyFormula, (Option<u32>, u8)>(&buffer[..size]).unwrap();
+/// assert_eq!(value, (None, 7));
+/// assert!(matches!(
+///     deserialize_strict::<MyFormula, (Option<u32>, u8)>(&buffer[..size]),
+///     Err(DeserializeError::NonCanonical)
+/// ));
+/// ```
+#[inline]
+pub fn deserialize_strict<'de, F, T>(input: &'de [u8]) -> Result<T, DeserializeError>
+where
+    F: Formula + ?Sized,
+    T: Deserialize<'de, F>,
+    for<'a> Validated: Deserialize<'a, F>,
+{
+    verify_canonical::<F>(input)?;
+
+    // Layout of referenced values is checked already.
+    let (address, size) = read_reference::<F>(input, input.len() - reference_size::<F>());
+    let de = Deserializer::new_unchecked(size, &input[..address]).strict(None);
+    <T as Deserialize<'de, F>>::deserialize(de)
+}
+
+/// Validates value in the input with strict deserializer.
+/// Referenced values are laid out starting from offset in `heap`.
+#[inline]
+pub(crate) fn validate_strict<'de, F>(
+    input: &'de [u8],
+    heap: &'de Cell<usize>,
+) -> Result<(), DeserializeError>
+where
+    F: Formula + ?Sized,
+    Validated: Deserialize<'de, F>,
+{
+    let reference_size = reference_size::<F>();
+
+    if input.len() < reference_size {
+        return Err(DeserializeError::OutOfBounds);
+    }
+
+    let (address, size) = read_reference::<F>(input, input.len() - reference_size);
+
+    if size > address {
+        return Err(DeserializeError::WrongAddress);
+    }
+
+    if address > input.len() {
+        return Err(DeserializeError::OutOfBounds);
+    }
+
+    if address < input.len() {
+        return Err(DeserializeError::NonCanonical);
+    }
+
+    heap.set(reference_size);
+    let de = Deserializer::new_unchecked(size, input).strict(Some(heap));
+    <Validated as Deserialize<'de, F>>::deserialize(de)?;
+
+    // Referenced values fill the input up to the stack of the value.
+    if heap.get() != address - size {
+        return Err(DeserializeError::NonCanonical);
+    }
+    Ok(())
+}
+
 /// Deserializes value from the input into specified place.
 /// Returns number of bytes consumed.
 ///
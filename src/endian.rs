@@ -0,0 +1,39 @@
+//!
+//! This module provides formulas for primitives with explicit byte order.
+//!
+
+use core::marker::PhantomData;
+
+/// Big-endian formula for primitive `F`.
+///
+/// Primitive formulas are little-endian.
+/// `Be<F>` has the same size as `F` and serializes
+/// the same values, but with the most significant byte first.
+/// Use it to describe formats with network byte order.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+///
+/// let mut buffer = [0u8; 16];
+/// let size = serialize::<Be<u32>, u32>(0x0102_0304, &mut buffer).unwrap();
+/// assert_eq!(buffer[size - 4..size], [1, 2, 3, 4]);
+///
+/// let value = deserialize::<Be<u32>, u32>(&buffer[..size]).unwrap().0;
+/// assert_eq!(value, 0x0102_0304);
+/// ```
+pub struct Be<F> {
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+/// Native-endian formula for primitive `F`.
+///
+/// Byte order of `Ne<F>` is the byte order of the target platform.
+/// It matches `F` on little-endian targets and [`Be<F>`] on big-endian ones,
+/// and its descriptor reflects that.
+/// Use it for data that never leaves the machine,
+/// like memory-mapped files and shared memory.
+pub struct Ne<F> {
+    marker: PhantomData<fn(&F) -> &F>,
+}
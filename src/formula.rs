@@ -1,5 +1,8 @@
 use crate::size::SIZE_STACK;
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 /// Trait for data formulas.
 /// Types that implement this trait are used as markers
 /// to guide serialization and deserialization process.
@@ -107,6 +110,20 @@ pub trait Formula {
 
     /// Signals that heap is not used for serialzation.
     const HEAPLESS: bool;
+
+    /// Returns runtime descriptor of this formula.
+    ///
+    /// Default implementation returns unnamed [`Descriptor::Opaque`]
+    /// built from the associated constants.
+    /// Unnamed opaque formula is not compatible with any formula,
+    /// manual implementations should return [`Descriptor::opaque`]
+    /// with a stable name or describe the structure.
+    /// `derive(Formula)` generates descriptor with names of fields and variants.
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("")
+    }
 }
 
 /// Ad-hoc negative trait.
@@ -183,3 +200,130 @@ where
         _ => SIZE_STACK * 2,
     }
 }
+
+/// Implements formula for a value type that is serialized
+/// with layout of another formula.
+///
+/// Value is converted into representation with `to`
+/// and deserialized representation is converted back with `from`
+/// that validates it.
+/// Descriptor of representation formula is used unless specified.
+macro_rules! impl_repr_formula {
+    (
+        $formula:ty as $repr:ty {
+            value: $value:ty,
+            repr: $repr_value:ty,
+            to: $to:expr,
+            from: $from:expr,
+            $(descriptor: $descriptor:expr,)?
+        }
+    ) => {
+        impl $crate::formula::Formula for $formula {
+            const MAX_STACK_SIZE: Option<usize> =
+                <$repr as $crate::formula::Formula>::MAX_STACK_SIZE;
+            const EXACT_SIZE: bool = <$repr as $crate::formula::Formula>::EXACT_SIZE;
+            const HEAPLESS: bool = <$repr as $crate::formula::Formula>::HEAPLESS;
+
+            #[cfg(feature = "alloc")]
+            #[inline(always)]
+            fn descriptor() -> $crate::descriptor::Descriptor {
+                $crate::formula::impl_repr_formula!(@descriptor $repr $(, $descriptor)?)
+            }
+        }
+
+        impl $crate::formula::BareFormula for $formula {}
+
+        impl $crate::serialize::Serialize<$formula> for $value {
+            #[inline(always)]
+            fn serialize<B>(
+                self,
+                sizes: &mut $crate::serialize::Sizes,
+                buffer: B,
+            ) -> Result<(), B::Error>
+            where
+                B: $crate::buffer::Buffer,
+            {
+                <&$value as $crate::serialize::Serialize<$formula>>::serialize(&self, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<$crate::serialize::Sizes> {
+                <&$value as $crate::serialize::Serialize<$formula>>::size_hint(&self)
+            }
+        }
+
+        impl $crate::serialize::Serialize<$formula> for &$value {
+            #[inline(always)]
+            fn serialize<B>(
+                self,
+                sizes: &mut $crate::serialize::Sizes,
+                buffer: B,
+            ) -> Result<(), B::Error>
+            where
+                B: $crate::buffer::Buffer,
+            {
+                let to: fn(&$value) -> $repr_value = $to;
+                <$repr_value as $crate::serialize::Serialize<$repr>>::serialize(
+                    to(self),
+                    sizes,
+                    buffer,
+                )
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<$crate::serialize::Sizes> {
+                let to: fn(&$value) -> $repr_value = $to;
+                <$repr_value as $crate::serialize::Serialize<$repr>>::size_hint(&to(self))
+            }
+        }
+
+        impl $crate::deserialize::Deserialize<'_, $formula> for $value {
+            #[inline(always)]
+            fn deserialize(
+                de: $crate::deserialize::Deserializer,
+            ) -> Result<Self, $crate::deserialize::DeserializeError> {
+                let from: fn($repr_value) -> Result<$value, $crate::deserialize::DeserializeError> =
+                    $from;
+                from(<$repr_value as $crate::deserialize::Deserialize<$repr>>::deserialize(de)?)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: $crate::deserialize::Deserializer,
+            ) -> Result<(), $crate::deserialize::DeserializeError> {
+                *self = <$value as $crate::deserialize::Deserialize<$formula>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+
+        impl $crate::deserialize::Deserialize<'_, $formula> for $crate::validate::Validated {
+            #[inline(always)]
+            fn deserialize(
+                de: $crate::deserialize::Deserializer,
+            ) -> Result<Self, $crate::deserialize::DeserializeError> {
+                <$value as $crate::deserialize::Deserialize<$formula>>::deserialize(de)?;
+                Ok($crate::validate::Validated)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: $crate::deserialize::Deserializer,
+            ) -> Result<(), $crate::deserialize::DeserializeError> {
+                <$value as $crate::deserialize::Deserialize<$formula>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+    };
+
+    (@descriptor $repr:ty) => {
+        <$repr as $crate::formula::Formula>::descriptor()
+    };
+
+    (@descriptor $repr:ty, $descriptor:expr) => {
+        $descriptor
+    };
+}
+
+pub(crate) use impl_repr_formula;
@@ -0,0 +1,696 @@
+//!
+//! This module provides length-delimited framing of serialized messages.
+//!
+//! Each frame consists of a [`FrameHeader`] followed by a message
+//! serialized with alkahest.
+//! Frames can be written back to back into a single byte stream
+//! and split again on the other side, even when stream delivers
+//! data in arbitrary chunks.
+//!
+
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    buffer::BufferExhausted,
+    checksum::crc32,
+    deserialize::{deserialize, Deserialize, DeserializeError},
+    formula::{BareFormula, Formula},
+    lazy::Lazy,
+    serialize::{serialize, Serialize},
+};
+
+#[cfg(feature = "alloc")]
+use crate::{
+    buffer::{Buffer, ByteVec, OffsetBuffer, VecBuffer},
+    checksum::Crc32,
+    formula::reference_size,
+    serialize::{hash_streaming, serialize_into, serialize_streaming, serialized_sizes, Sizes},
+};
+
+const FLAG_FINGERPRINT: u8 = 1;
+const FLAG_CHECKSUM: u8 = 2;
+
+const LENGTH_SIZE: usize = 8;
+const FINGERPRINT_SIZE: usize = 8;
+const CHECKSUM_SIZE: usize = 4;
+
+#[cfg(feature = "alloc")]
+const MAX_HEADER_SIZE: usize = header_size(true, true);
+
+#[cfg(feature = "alloc")]
+const INITIAL_SCRATCH_SIZE: usize = 256;
+
+/// Default limit of frame size for [`FrameDecoder`]
+/// and codecs of the `codec` module, including header.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Error that can occur during frame decoding.
+#[derive(Clone, Copy, Debug)]
+pub enum FrameError {
+    /// Frame header contains unknown flags.
+    /// Stream is likely corrupted or not framed.
+    InvalidHeader,
+
+    /// Frame length exceeds configured limit
+    /// or the maximum `usize` for current platform.
+    TooLarge,
+
+    /// Frame fingerprint does not match expected one.
+    /// Frame was produced for a different formula.
+    FingerprintMismatch {
+        /// Expected fingerprint.
+        expected: u64,
+
+        /// Fingerprint in the frame header.
+        /// `None` if frame has no fingerprint.
+        found: Option<u64>,
+    },
+
+    /// Checksum of the frame body does not match checksum in the header.
+    ChecksumMismatch {
+        /// Checksum in the frame header.
+        expected: u32,
+
+        /// Checksum of the received frame body.
+        found: u32,
+    },
+
+    /// Failed to deserialize frame body.
+    Deserialize(DeserializeError),
+}
+
+impl From<DeserializeError> for FrameError {
+    #[inline(always)]
+    fn from(err: DeserializeError) -> Self {
+        FrameError::Deserialize(err)
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::InvalidHeader => f.write_str("frame header is invalid"),
+            FrameError::TooLarge => f.write_str("frame is too large"),
+            FrameError::FingerprintMismatch {
+                expected,
+                found: Some(found),
+            } => write!(
+                f,
+                "frame fingerprint {found:#018x} does not match expected {expected:#018x}"
+            ),
+            FrameError::FingerprintMismatch {
+                expected,
+                found: None,
+            } => write!(f, "frame has no fingerprint, expected {expected:#018x}"),
+            FrameError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "frame checksum {found:#010x} does not match expected {expected:#010x}"
+            ),
+            FrameError::Deserialize(err) => write!(f, "failed to deserialize frame: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FrameError::Deserialize(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Header of a frame.
+///
+/// Encoded as a flags byte, followed by length of the frame body
+/// and then optional fingerprint and checksum.
+/// All values are little-endian, length is 64 bits wide
+/// regardless of the size of [`FixedUsize`](crate::FixedUsize).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Length of the frame body in bytes.
+    pub length: usize,
+
+    /// Fingerprint of the formula of the frame body.
+    /// See [`Descriptor::fingerprint`](crate::Descriptor::fingerprint).
+    pub fingerprint: Option<u64>,
+
+    /// CRC-32 checksum of the frame body.
+    pub checksum: Option<u32>,
+}
+
+impl FrameHeader {
+    /// Returns size of the encoded header.
+    #[must_use]
+    #[inline(always)]
+    pub const fn size(&self) -> usize {
+        header_size(self.fingerprint.is_some(), self.checksum.is_some())
+    }
+
+    /// Writes header into the beginning of the output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if output is shorter than [`FrameHeader::size`].
+    pub fn write(&self, output: &mut [u8]) {
+        let mut flags = 0;
+        if self.fingerprint.is_some() {
+            flags |= FLAG_FINGERPRINT;
+        }
+        if self.checksum.is_some() {
+            flags |= FLAG_CHECKSUM;
+        }
+        output[0] = flags;
+
+        let length = self.length as u64;
+        output[1..][..LENGTH_SIZE].copy_from_slice(&length.to_le_bytes());
+
+        let mut at = 1 + LENGTH_SIZE;
+        if let Some(fingerprint) = self.fingerprint {
+            output[at..][..FINGERPRINT_SIZE].copy_from_slice(&fingerprint.to_le_bytes());
+            at += FINGERPRINT_SIZE;
+        }
+        if let Some(checksum) = self.checksum {
+            output[at..][..CHECKSUM_SIZE].copy_from_slice(&checksum.to_le_bytes());
+        }
+    }
+
+    /// Reads header from the beginning of the input.
+    /// Returns `Ok(None)` if input is too short to contain the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameError::InvalidHeader`] if header has unknown flags.
+    /// Returns [`FrameError::TooLarge`] if length does not fit `usize`.
+    pub fn read(input: &[u8]) -> Result<Option<Self>, FrameError> {
+        let Some(&flags) = input.first() else {
+            return Ok(None);
+        };
+
+        if flags & !(FLAG_FINGERPRINT | FLAG_CHECKSUM) != 0 {
+            return Err(FrameError::InvalidHeader);
+        }
+
+        let has_fingerprint = flags & FLAG_FINGERPRINT != 0;
+        let has_checksum = flags & FLAG_CHECKSUM != 0;
+        if input.len() < header_size(has_fingerprint, has_checksum) {
+            return Ok(None);
+        }
+
+        let mut length = [0; LENGTH_SIZE];
+        length.copy_from_slice(&input[1..][..LENGTH_SIZE]);
+        let Ok(length) = usize::try_from(u64::from_le_bytes(length)) else {
+            return Err(FrameError::TooLarge);
+        };
+
+        let mut at = 1 + LENGTH_SIZE;
+        let mut fingerprint = None;
+        if has_fingerprint {
+            let mut bytes = [0; FINGERPRINT_SIZE];
+            bytes.copy_from_slice(&input[at..][..FINGERPRINT_SIZE]);
+            fingerprint = Some(u64::from_le_bytes(bytes));
+            at += FINGERPRINT_SIZE;
+        }
+
+        let mut checksum = None;
+        if has_checksum {
+            let mut bytes = [0; CHECKSUM_SIZE];
+            bytes.copy_from_slice(&input[at..][..CHECKSUM_SIZE]);
+            checksum = Some(u32::from_le_bytes(bytes));
+        }
+
+        Ok(Some(FrameHeader {
+            length,
+            fingerprint,
+            checksum,
+        }))
+    }
+}
+
+#[inline(always)]
+const fn header_size(fingerprint: bool, checksum: bool) -> usize {
+    let mut size = 1 + LENGTH_SIZE;
+    if fingerprint {
+        size += FINGERPRINT_SIZE;
+    }
+    if checksum {
+        size += CHECKSUM_SIZE;
+    }
+    size
+}
+
+/// Complete frame borrowed from the input.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame<'de> {
+    header: FrameHeader,
+    body: &'de [u8],
+}
+
+impl<'de> Frame<'de> {
+    /// Returns header of the frame.
+    #[must_use]
+    #[inline(always)]
+    pub const fn header(&self) -> &FrameHeader {
+        &self.header
+    }
+
+    /// Returns body of the frame.
+    /// Body contains serialized message.
+    #[must_use]
+    #[inline(always)]
+    pub const fn body(&self) -> &'de [u8] {
+        self.body
+    }
+
+    /// Deserializes message from the frame body.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    #[inline]
+    pub fn deserialize<F, T>(&self) -> Result<T, DeserializeError>
+    where
+        F: Formula + ?Sized,
+        T: Deserialize<'de, F>,
+    {
+        let (value, _) = deserialize::<F, T>(self.body)?;
+        Ok(value)
+    }
+
+    /// Returns lazy message from the frame body.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if frame body is not a valid reference.
+    #[inline]
+    pub fn lazy<F>(&self) -> Result<Lazy<'de, F>, DeserializeError>
+    where
+        F: BareFormula + ?Sized,
+    {
+        self.deserialize::<F, Lazy<'de, F>>()
+    }
+}
+
+/// Splits first complete frame from the input.
+/// Returns the frame and total number of bytes it occupies in the input.
+/// Returns `Ok(None)` if input does not contain a complete frame yet.
+///
+/// Checksum of the frame body is verified if present.
+///
+/// # Errors
+///
+/// Returns error if frame header is invalid,
+/// frame is larger than `max_frame_size` bytes
+/// or checksum does not match.
+pub fn split_frame(
+    input: &[u8],
+    max_frame_size: usize,
+) -> Result<Option<(Frame<'_>, usize)>, FrameError> {
+    let Some(header) = FrameHeader::read(input)? else {
+        return Ok(None);
+    };
+
+    let header_size = header.size();
+    if header.length > max_frame_size.saturating_sub(header_size) {
+        return Err(FrameError::TooLarge);
+    }
+
+    let frame_size = header_size + header.length;
+    if input.len() < frame_size {
+        return Ok(None);
+    }
+
+    let body = &input[header_size..frame_size];
+    if let Some(expected) = header.checksum {
+        let found = crc32(body);
+        if found != expected {
+            return Err(FrameError::ChecksumMismatch { expected, found });
+        }
+    }
+
+    Ok(Some((Frame { header, body }, frame_size)))
+}
+
+/// Takes next complete frame from the input and checks its fingerprint.
+/// Returns number of bytes to consume from the input alongside the result.
+/// Frames with mismatching checksum or fingerprint are consumed
+/// to allow skipping them.
+#[cfg(feature = "alloc")]
+pub(crate) fn take_frame(
+    input: &[u8],
+    fingerprint: Option<u64>,
+    max_frame_size: usize,
+) -> (usize, Result<Option<Frame<'_>>, FrameError>) {
+    let (frame, size) = match split_frame(input, max_frame_size) {
+        Ok(Some(split)) => split,
+        Ok(None) => return (0, Ok(None)),
+        Err(err @ FrameError::ChecksumMismatch { .. }) => {
+            let size = match FrameHeader::read(input) {
+                Ok(Some(header)) => header.size() + header.length,
+                _ => 0,
+            };
+            return (size, Err(err));
+        }
+        Err(err) => return (0, Err(err)),
+    };
+
+    if let Some(expected) = fingerprint {
+        if frame.header.fingerprint != Some(expected) {
+            return (
+                size,
+                Err(FrameError::FingerprintMismatch {
+                    expected,
+                    found: frame.header.fingerprint,
+                }),
+            );
+        }
+    }
+
+    (size, Ok(Some(frame)))
+}
+
+/// Encoder that writes frames into buffers.
+#[derive(Clone, Copy, Debug, Default)]
+#[must_use]
+pub struct FrameEncoder {
+    fingerprint: Option<u64>,
+    checksum: bool,
+}
+
+impl FrameEncoder {
+    /// Creates new encoder that writes frames
+    /// without fingerprint and checksum.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        FrameEncoder {
+            fingerprint: None,
+            checksum: false,
+        }
+    }
+
+    /// Writes specified fingerprint into each frame header.
+    #[inline(always)]
+    pub const fn with_fingerprint(mut self, fingerprint: u64) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Writes checksum of the frame body into each frame header.
+    #[inline(always)]
+    pub const fn with_checksum(mut self) -> Self {
+        self.checksum = true;
+        self
+    }
+
+    /// Returns size of frame headers written by this encoder.
+    #[must_use]
+    #[inline(always)]
+    pub const fn header_size(&self) -> usize {
+        header_size(self.fingerprint.is_some(), self.checksum)
+    }
+
+    /// Writes frame with serialized value into the output.
+    /// Frame is written at the beginning of the output.
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BufferExhausted`] if the output is too small.
+    #[inline]
+    pub fn encode<F, T>(&self, value: T, output: &mut [u8]) -> Result<usize, BufferExhausted>
+    where
+        F: Formula + ?Sized,
+        T: Serialize<F>,
+    {
+        let header_size = self.header_size();
+        let Some(body) = output.get_mut(header_size..) else {
+            return Err(BufferExhausted);
+        };
+
+        let length = serialize::<F, T>(value, body)?;
+        self.write_header(length, output);
+        Ok(header_size + length)
+    }
+
+    /// Appends frame with serialized value to the vector.
+    /// Returns the number of bytes appended.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn encode_to_vec<F, T>(&self, value: T, output: &mut Vec<u8>) -> usize
+    where
+        F: Formula + ?Sized,
+        T: Serialize<F>,
+    {
+        self.encode_to_byte_vec::<F, T, _>(value, output)
+    }
+
+    /// Appends frame with serialized value to the byte vector.
+    /// Returns the number of bytes appended.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn encode_to_byte_vec<F, T, V>(&self, value: T, output: &mut V) -> usize
+    where
+        F: Formula + ?Sized,
+        T: Serialize<F>,
+        V: ByteVec,
+    {
+        let offset = output.len();
+        let header_size = self.header_size();
+        output.resize_zeroed(offset + header_size);
+
+        let length = match serialize_into::<F, T, _>(
+            value,
+            OffsetBuffer::new(VecBuffer::new(output), offset + header_size),
+        ) {
+            Ok(length) => length,
+            Err(never) => match never {},
+        };
+
+        output.resize_zeroed(offset + header_size + length);
+        self.write_header(length, &mut output[offset..]);
+        header_size + length
+    }
+
+    /// Writes frame with serialized value into the buffer at specified offset.
+    /// Returns the number of bytes written.
+    ///
+    /// Header is written before the body, so any buffer works,
+    /// including buffers that can only append bytes.
+    /// Length of the body is taken from the size hint of the value,
+    /// value without size hint is serialized once more to measure it.
+    /// With checksum enabled the value is serialized once more
+    /// to compute checksum of the body before writing the header.
+    /// [`FrameEncoder::encode`] and [`FrameEncoder::encode_to_vec`]
+    /// serialize the value once.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if buffer write fails.
+    #[cfg(feature = "alloc")]
+    pub fn encode_at<F, T, B>(
+        &self,
+        value: T,
+        offset: usize,
+        mut buffer: B,
+    ) -> Result<usize, B::Error>
+    where
+        F: Formula + ?Sized,
+        T: Serialize<F> + Clone,
+        B: Buffer,
+    {
+        let promised = match <T as Serialize<F>>::size_hint(&value) {
+            Some(promised) => promised,
+            None => serialized_sizes::<F, T>(value.clone()),
+        };
+        let length = reference_size::<F>() + promised.heap + promised.stack;
+
+        let header = FrameHeader {
+            length,
+            fingerprint: self.fingerprint,
+            checksum: self
+                .checksum
+                .then(|| body_checksum::<F, T>(value.clone(), promised, length)),
+        };
+
+        let header_size = self.header_size();
+        let mut head = [0; MAX_HEADER_SIZE];
+        let head = &mut head[..header_size];
+        header.write(head);
+        buffer.write_stack(offset, 0, head)?;
+        buffer.move_to_heap(offset, header_size, header_size);
+
+        serialize_streaming::<F, T, _>(
+            value,
+            promised,
+            OffsetBuffer::new(buffer.reborrow(), offset + header_size),
+        )?;
+
+        Ok(header_size + length)
+    }
+
+    /// Writes header of the frame at the beginning of the `frame`
+    /// that contains body of `length` bytes after the header.
+    fn write_header(&self, length: usize, frame: &mut [u8]) {
+        let (head, body) = frame.split_at_mut(self.header_size());
+        let header = FrameHeader {
+            length,
+            fingerprint: self.fingerprint,
+            checksum: self.checksum.then(|| crc32(&body[..length])),
+        };
+        header.write(head);
+    }
+}
+
+/// Computes checksum of the serialized value without writing it.
+/// Scratch for the stack of the value grows as needed,
+/// it never exceeds the length of the body.
+#[cfg(feature = "alloc")]
+fn body_checksum<F, T>(value: T, promised: Sizes, length: usize) -> u32
+where
+    F: Formula + ?Sized,
+    T: Serialize<F> + Clone,
+{
+    let mut scratch = alloc::vec![0; length.min(INITIAL_SCRATCH_SIZE)];
+    loop {
+        let mut crc = Crc32::new();
+        match hash_streaming::<F, T, _>(value.clone(), promised, &mut crc, &mut scratch) {
+            Err(BufferExhausted) if scratch.len() < length => {
+                scratch.resize(length.min(scratch.len() * 2), 0);
+            }
+            _ => return crc.checksum(),
+        }
+    }
+}
+
+/// Decoder that splits incoming bytes into frames.
+///
+/// Bytes are appended with [`FrameDecoder::extend`] as they arrive,
+/// complete frames are taken with [`FrameDecoder::next_frame`]
+/// or [`FrameDecoder::next_lazy`].
+/// Incomplete trailing frame is kept until the rest of it arrives.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::{*, framing::*};
+/// let encoder = FrameEncoder::new().with_checksum();
+///
+/// let mut stream = Vec::new();
+/// encoder.encode_to_vec::<str, _>("Hello", &mut stream);
+/// encoder.encode_to_vec::<str, _>("World", &mut stream);
+///
+/// let mut decoder = FrameDecoder::new();
+/// decoder.extend(&stream[..10]);
+/// assert!(decoder.next_lazy::<str>().unwrap().is_none());
+///
+/// decoder.extend(&stream[10..]);
+/// assert_eq!(decoder.next_lazy::<str>().unwrap().unwrap().get::<&str>().unwrap(), "Hello");
+/// assert_eq!(decoder.next_lazy::<str>().unwrap().unwrap().get::<&str>().unwrap(), "World");
+/// assert!(decoder.next_lazy::<str>().unwrap().is_none());
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    consumed: usize,
+    fingerprint: Option<u64>,
+    max_frame_size: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for FrameDecoder {
+    #[inline(always)]
+    fn default() -> Self {
+        FrameDecoder::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FrameDecoder {
+    /// Creates new decoder.
+    /// Frames are limited to [`DEFAULT_MAX_FRAME_SIZE`] bytes.
+    #[must_use]
+    #[inline(always)]
+    pub const fn new() -> Self {
+        FrameDecoder {
+            buffer: Vec::new(),
+            consumed: 0,
+            fingerprint: None,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Requires every frame to carry specified fingerprint.
+    #[must_use]
+    #[inline(always)]
+    pub const fn with_fingerprint(mut self, fingerprint: u64) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Limits size of frames, including header.
+    /// Decoder buffers whole frame before returning it,
+    /// so the limit bounds memory used for a single frame.
+    /// Defaults to [`DEFAULT_MAX_FRAME_SIZE`].
+    #[must_use]
+    #[inline(always)]
+    pub const fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Appends received bytes to the decoder.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        if self.consumed > 0 {
+            self.buffer.drain(..self.consumed);
+            self.consumed = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Returns bytes received but not yet taken as frames.
+    #[must_use]
+    #[inline(always)]
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer[self.consumed..]
+    }
+
+    /// Takes next complete frame.
+    /// Returns `Ok(None)` if no complete frame is available yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if frame is invalid.
+    /// Frames with mismatching checksum or fingerprint are skipped,
+    /// so decoding may continue with the next frame.
+    /// Other errors mean the stream is corrupted.
+    pub fn next_frame(&mut self) -> Result<Option<Frame<'_>>, FrameError> {
+        let (consumed, result) = take_frame(
+            &self.buffer[self.consumed..],
+            self.fingerprint,
+            self.max_frame_size,
+        );
+        self.consumed += consumed;
+        result
+    }
+
+    /// Takes next complete frame and returns lazy message from it.
+    /// Returns `Ok(None)` if no complete frame is available yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns same errors as [`FrameDecoder::next_frame`].
+    /// Returns [`FrameError::Deserialize`] if frame body is not a valid reference.
+    pub fn next_lazy<F>(&mut self) -> Result<Option<Lazy<'_, F>>, FrameError>
+    where
+        F: BareFormula + ?Sized,
+    {
+        match self.next_frame()? {
+            None => Ok(None),
+            Some(frame) => Ok(Some(frame.lazy::<F>()?)),
+        }
+    }
+}
@@ -35,11 +35,11 @@ where
 
 /// Returns the size of the serialized data if it can be determined fast.
 #[inline(always)]
-pub fn ref_iter_fast_sizes<'a, F, I, T: 'a>(iter: I) -> Option<Sizes>
+pub fn ref_iter_fast_sizes<'a, F, I, T>(iter: I) -> Option<Sizes>
 where
     F: Formula + ?Sized,
     I: Iterator<Item = &'a T>,
-    T: Serialize<F>,
+    T: Serialize<F> + 'a,
 {
     match (F::HEAPLESS, F::MAX_STACK_SIZE) {
         (true, Some(0)) => Some(Sizes::with_stack(SIZE_STACK)),
@@ -56,7 +56,7 @@ where
         }
         _ => {
             let (_lower, upper) = iter.size_hint();
-            if upper.map_or(false, |upper| upper <= ITER_UPPER) {
+            if upper.is_some_and(|upper| upper <= ITER_UPPER) {
                 let mut sizes = Sizes::ZERO;
                 for elem in iter {
                     sizes += field_size_hint::<F>(elem, false)?;
@@ -91,7 +91,7 @@ where
         }
         _ => {
             let (_lower, upper) = iter.size_hint();
-            if upper.map_or(false, |upper| upper <= ITER_UPPER) {
+            if upper.is_some_and(|upper| upper <= ITER_UPPER) {
                 let mut sizes = Sizes::ZERO;
                 for elem in iter {
                     sizes += field_size_hint::<F>(&elem, false)?;
@@ -17,26 +17,50 @@ extern crate alloc;
 
 mod array;
 mod r#as;
+mod bits;
 mod buffer;
 mod bytes;
+mod canonical;
+mod checksum;
+mod context;
 mod deserialize;
+mod endian;
 mod formula;
 mod iter;
 mod lazy;
+mod map;
+mod num;
 mod option;
+mod phantom;
 mod primitive;
 mod reference;
+mod result;
 mod serialize;
 mod size;
 mod skip;
 mod slice;
 mod str;
+mod table;
+mod time;
 mod tuple;
+mod validate;
+mod variant;
 mod vlq;
 
+pub mod framing;
+
+#[cfg(feature = "tokio-codec")]
+pub mod codec;
+
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "alloc")]
+mod compat;
+
+#[cfg(feature = "alloc")]
+mod descriptor;
+
 #[cfg(feature = "alloc")]
 mod vec;
 
@@ -46,31 +70,78 @@ mod vec_deque;
 #[cfg(feature = "alloc")]
 mod string;
 
+#[cfg(feature = "alloc")]
+mod boxed;
+
+#[cfg(feature = "alloc")]
+mod rc;
+
+#[cfg(feature = "alloc")]
+mod cow;
+
+#[cfg(feature = "alloc")]
+mod shared;
+
+#[cfg(feature = "std")]
+mod net;
+
+#[cfg(feature = "std")]
+mod path;
+
+#[cfg(feature = "std")]
+mod reader;
+
 #[cfg(feature = "bincoded")]
 mod bincoded;
 
 pub use crate::{
+    bits::{BitIter, BitSlice, Bits, LazyBitSlice},
     buffer::BufferExhausted,
     bytes::Bytes,
+    canonical::verify_canonical,
+    checksum::Crc32,
     deserialize::{
-        deserialize, deserialize_in_place, value_size, DeIter, Deserialize, DeserializeError,
+        deserialize, deserialize_in_place, deserialize_strict, value_size, DeIter, Deserialize,
+        DeserializeError,
     },
+    endian::{Be, Ne},
     formula::Formula,
     iter::SerIter,
     lazy::Lazy,
+    map::{LazyMap, LazySet, Map, Set},
     r#as::As,
     reference::Ref,
     serialize::{
-        serialize, serialize_or_size, serialize_unchecked, serialized_size, BufferSizeRequired,
-        Serialize,
+        serialize, serialize_or_size, serialize_to_hasher, serialize_to_hasher_cloned,
+        serialize_unchecked, serialized_size, BufferSizeRequired, Serialize,
     },
     size::{FixedIsize, FixedUsize},
     skip::Skip,
-    vlq::Vlq,
+    table::{HashTable, LazyHashTable},
+    time::VlqDuration,
+    validate::{validate, Validated},
+    vlq::{Vlq, ZigZag},
 };
 
 #[cfg(feature = "alloc")]
-pub use crate::serialize::serialize_to_vec;
+pub use crate::{
+    compat::{
+        check_backward_compatibility, check_compatibility, check_forward_compatibility,
+        Incompatibility, IncompatibilityKind,
+    },
+    descriptor::{Descriptor, Field, Variant},
+    serialize::serialize_to_vec,
+    shared::{deserialize_shared, serialize_shared, serialize_shared_to_vec, Shared},
+};
+
+#[cfg(feature = "std")]
+pub use crate::{
+    reader::{deserialize_from_buf_read, Reader},
+    serialize::{serialize_into_writer, serialize_into_writer_cloned},
+};
+
+#[cfg(feature = "error-context")]
+pub use crate::context::{deserialize_with_context, ContextError};
 
 #[cfg(feature = "derive")]
 pub use alkahest_proc::{Deserialize, Formula, Serialize};
@@ -82,6 +153,7 @@ pub use bincoded::{Bincode, Bincoded};
 /// `Serialize` and `Deserialize` traits.
 pub mod advanced {
     pub use crate::{
+        bits::{bits_size, read_bits, write_bits, BitFormula, DeserializeBits, SerializeBits},
         buffer::{Buffer, CheckedFixedBuffer, MaybeFixedBuffer},
         deserialize::Deserializer,
         formula::{reference_size, BareFormula},
@@ -92,10 +164,11 @@ pub mod advanced {
             SliceWriter,
         },
         size::{FixedIsize, FixedIsizeType},
+        variant::VariantFormula,
     };
 
     #[cfg(feature = "alloc")]
-    pub use crate::buffer::VecBuffer;
+    pub use crate::buffer::{ByteVec, VecBuffer};
 }
 
 /// Private module for macros to use.
@@ -104,21 +177,71 @@ pub mod advanced {
 pub mod private {
     pub use {
         bool,
-        core::{convert::Into, debug_assert_eq, option::Option, result::Result},
-        u32, u8, usize,
+        core::{
+            assert, convert::Into, debug_assert, debug_assert_eq, default::Default, option::Option,
+            result::Result,
+        },
+        u128, u32, u8, usize,
     };
 
     pub use crate::{
+        bits::{
+            bits_mask, bits_size, read_bits, write_bits, BitFormula, DeserializeBits, SerializeBits,
+        },
         buffer::Buffer,
+        bytes::Bytes,
         deserialize::{Deserialize, DeserializeError, Deserializer},
         formula::{max_size, sum_size, BareFormula, Formula},
         serialize::{formula_fast_sizes, write_exact_size_field, write_field, Serialize, Sizes},
+        validate::Validated,
+        variant::VariantFormula,
     };
 
+    #[cfg(feature = "alloc")]
+    pub use {
+        crate::descriptor::{Descriptor, Field, Variant},
+        alloc::{boxed::Box, vec::Vec},
+    };
+
+    pub use crate::__alkahest_with_alloc as with_alloc;
+
+    /// Expands to the input tokens only when "alloc" feature is enabled.
+    #[cfg(feature = "alloc")]
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __alkahest_with_alloc {
+        ($($tt:tt)*) => { $($tt)* };
+    }
+
+    /// Expands to the input tokens only when "alloc" feature is enabled.
+    #[cfg(not(feature = "alloc"))]
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __alkahest_with_alloc {
+        ($($tt:tt)*) => {};
+    }
+
     use core::marker::PhantomData;
 
+    /// Returns offset in the input where bytes of the next value end.
+    #[inline(always)]
+    pub fn stack_end(de: &Deserializer) -> usize {
+        de.stack_end()
+    }
+
+    /// Records path segment of the value that failed to deserialize
+    /// when deserializing with context.
+    #[inline(always)]
+    pub fn trace_error(
+        de: &Deserializer,
+        error: DeserializeError,
+        segment: &'static str,
+        value_end: usize,
+    ) -> DeserializeError {
+        de.trace_error(error, segment, value_end)
+    }
+
     pub const VARIANT_SIZE: usize = core::mem::size_of::<u32>();
-    pub const VARIANT_SIZE_OPT: Option<usize> = Some(VARIANT_SIZE);
 
     pub struct WithFormula<F: Formula + ?Sized> {
         marker: PhantomData<fn(&F) -> &F>,
@@ -143,17 +266,42 @@ pub mod private {
             crate::serialize::write_field(value, sizes, buffer, last)
         }
 
+        #[inline(always)]
+        pub fn pack_bits<T>(self, value: T, bits: &mut u128, offset: &mut u32)
+        where
+            F: BitFormula,
+            T: SerializeBits<F>,
+        {
+            let value = value.serialize_bits() & bits_mask(F::BITS);
+            *bits |= value.checked_shl(*offset).unwrap_or(0);
+            *offset += F::BITS;
+        }
+
+        #[inline(always)]
+        pub fn unpack_bits<T>(self, bits: u128, offset: &mut u32) -> Result<T, DeserializeError>
+        where
+            F: BitFormula,
+            T: DeserializeBits<F>,
+        {
+            let value = bits.checked_shr(*offset).unwrap_or(0) & bits_mask(F::BITS);
+            *offset += F::BITS;
+            T::deserialize_bits(value)
+        }
+
         #[inline(always)]
         pub fn read_field<'de, T>(
             self,
             de: &mut Deserializer<'de>,
             last: bool,
+            segment: &'static str,
         ) -> Result<T, DeserializeError>
         where
             F: Formula,
             T: Deserialize<'de, F>,
         {
+            let value_end = de.stack_end();
             de.read_value::<F, T>(last)
+                .map_err(|err| de.trace_error(err, segment, value_end))
         }
 
         #[inline(always)]
@@ -162,12 +310,15 @@ pub mod private {
             place: &mut T,
             de: &mut Deserializer<'de>,
             last: bool,
+            segment: &'static str,
         ) -> Result<(), DeserializeError>
         where
             F: Formula,
             T: Deserialize<'de, F>,
         {
+            let value_end = de.stack_end();
             de.read_in_place::<F, T>(place, last)
+                .map_err(|err| de.trace_error(err, segment, value_end))
         }
 
         #[inline(always)]
@@ -0,0 +1,809 @@
+//!
+//! This module provides formulas for maps and sets.
+//!
+
+use core::{borrow::Borrow, cmp::Ordering, marker::PhantomData};
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer, SizedDeIter},
+    formula::{unwrap_size, BareFormula, Formula},
+    iter::SerIter,
+    reference::Ref,
+    serialize::{write_ref, Serialize, Sizes},
+    validate::Validated,
+};
+
+#[cfg(feature = "alloc")]
+use alloc::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "alloc")]
+use crate::{formula::reference_size, iter::owned_iter_fast_sizes, size::SIZE_STACK};
+
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, Hash},
+};
+
+#[cfg(feature = "std")]
+use crate::iter::deserialize_extend_iter;
+
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
+/// Formula for maps with keys of formula `K` and values of formula `V`.
+///
+/// Map is serialized as a reference to a slice of key-value pairs
+/// sorted by key, same as `Vec<(K, V)>`.
+/// Can be serialized from `BTreeMap`, `HashMap` and from [`SerIter`]
+/// over key-value pairs.
+/// Can be deserialized into `BTreeMap`, `HashMap` and [`LazyMap`]
+/// which allows looking up values without deserializing whole map.
+///
+/// `HashMap` entries are sorted by key before serialization.
+/// Pairs from `SerIter` are serialized in iteration order,
+/// keys must be yielded in ascending order for [`LazyMap::get`] to work.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// let entries = [(1u32, 10u64), (2, 20), (3, 30)];
+///
+/// let mut buffer = [0u8; 1024];
+/// serialize::<Map<u32, u64>, _>(SerIter(entries.into_iter()), &mut buffer).unwrap();
+///
+/// let (lazy, _) = deserialize::<Map<u32, u64>, LazyMap<u32, u64>>(&buffer).unwrap();
+/// assert_eq!(lazy.get::<u32, u64, _>(&2).unwrap(), Some(20));
+/// assert_eq!(lazy.get::<u32, u64, _>(&4).unwrap(), None);
+/// ```
+pub struct Map<K, V> {
+    marker: PhantomData<Ref<[(K, V)]>>,
+}
+
+impl<K, V> Formula for Map<K, V>
+where
+    K: Formula,
+    V: Formula,
+{
+    const MAX_STACK_SIZE: Option<usize> = <Ref<[(K, V)]> as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <Ref<[(K, V)]> as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <Ref<[(K, V)]> as Formula>::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<[(K, V)]> as Formula>::descriptor()
+    }
+}
+
+impl<K, V> BareFormula for Map<K, V>
+where
+    K: Formula,
+    V: Formula,
+{
+}
+
+/// Formula for sets with elements of formula `F`.
+///
+/// Set is serialized as a reference to a slice of sorted elements,
+/// same as `Vec<F>`.
+/// Can be serialized from `BTreeSet`, `HashSet` and from [`SerIter`].
+/// Can be deserialized into `BTreeSet`, `HashSet` and [`LazySet`]
+/// which allows checking presence of elements without deserializing whole set.
+///
+/// `HashSet` elements are sorted before serialization.
+/// Elements from `SerIter` are serialized in iteration order,
+/// they must be yielded in ascending order for [`LazySet::contains`] to work.
+pub struct Set<F> {
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+impl<F> Formula for Set<F>
+where
+    F: Formula,
+{
+    const MAX_STACK_SIZE: Option<usize> = <Ref<[F]> as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <Ref<[F]> as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <Ref<[F]> as Formula>::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<[F]> as Formula>::descriptor()
+    }
+}
+
+impl<F> BareFormula for Set<F> where F: Formula {}
+
+/// Returns sizes of the reference to the slice
+/// if it can be determined from the number of elements alone.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+fn len_fast_sizes<F>(len: usize) -> Option<Sizes>
+where
+    F: Formula,
+{
+    let mut sizes = match (F::HEAPLESS, F::MAX_STACK_SIZE) {
+        (true, Some(0)) => Sizes::with_stack(SIZE_STACK),
+        (true, Some(max_stack)) => Sizes::with_stack(len * max_stack),
+        _ => return None,
+    };
+    sizes.to_heap(0);
+    sizes.add_stack(reference_size::<[F]>());
+    Some(sizes)
+}
+
+/// Returns sizes of the reference to the slice
+/// if it can be determined fast.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+fn iter_fast_sizes<F, I, T>(iter: I) -> Option<Sizes>
+where
+    F: Formula,
+    I: Iterator<Item = T>,
+    T: Serialize<F>,
+{
+    let mut sizes = owned_iter_fast_sizes::<F, I, T>(iter)?;
+    sizes.to_heap(0);
+    sizes.add_stack(reference_size::<[F]>());
+    Some(sizes)
+}
+
+impl<FK, FV, I> Serialize<Map<FK, FV>> for SerIter<I>
+where
+    FK: Formula,
+    FV: Formula,
+    SerIter<I>: Serialize<[(FK, FV)]>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<[(FK, FV)], _, _>(self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <Self as Serialize<Ref<[(FK, FV)]>>>::size_hint(self)
+    }
+}
+
+impl<F, I> Serialize<Set<F>> for SerIter<I>
+where
+    F: Formula,
+    SerIter<I>: Serialize<[F]>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<[F], _, _>(self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        <Self as Serialize<Ref<[F]>>>::size_hint(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<FK, FV, K, V> Serialize<Map<FK, FV>> for BTreeMap<K, V>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Serialize<FK>,
+    V: Serialize<FV>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<[(FK, FV)], _, _>(SerIter(self.into_iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        len_fast_sizes::<(FK, FV)>(self.len())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'ser, FK, FV, K, V> Serialize<Map<FK, FV>> for &'ser BTreeMap<K, V>
+where
+    FK: Formula,
+    FV: Formula,
+    &'ser K: Serialize<FK>,
+    &'ser V: Serialize<FV>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<[(FK, FV)], _, _>(SerIter(self.iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        iter_fast_sizes::<(FK, FV), _, _>(self.iter())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F, T> Serialize<Set<F>> for BTreeSet<T>
+where
+    F: Formula,
+    T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<[F], _, _>(SerIter(self.into_iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        len_fast_sizes::<F>(self.len())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'ser, F, T> Serialize<Set<F>> for &'ser BTreeSet<T>
+where
+    F: Formula,
+    &'ser T: Serialize<F>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<[F], _, _>(SerIter(self.iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        iter_fast_sizes::<F, _, _>(self.iter())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<FK, FV, K, V, S> Serialize<Map<FK, FV>> for HashMap<K, V, S>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Serialize<FK> + Ord,
+    V: Serialize<FV>,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        let mut entries = self.into_iter().collect::<Vec<_>>();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        write_ref::<[(FK, FV)], _, _>(SerIter(entries.into_iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        len_fast_sizes::<(FK, FV)>(self.len())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'ser, FK, FV, K, V, S> Serialize<Map<FK, FV>> for &'ser HashMap<K, V, S>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Ord,
+    &'ser K: Serialize<FK>,
+    &'ser V: Serialize<FV>,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        write_ref::<[(FK, FV)], _, _>(SerIter(entries.into_iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        iter_fast_sizes::<(FK, FV), _, _>(self.iter())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F, T, S> Serialize<Set<F>> for HashSet<T, S>
+where
+    F: Formula,
+    T: Serialize<F> + Ord,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        let mut elements = self.into_iter().collect::<Vec<_>>();
+        elements.sort_unstable();
+        write_ref::<[F], _, _>(SerIter(elements.into_iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        len_fast_sizes::<F>(self.len())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'ser, F, T, S> Serialize<Set<F>> for &'ser HashSet<T, S>
+where
+    F: Formula,
+    T: Ord,
+    &'ser T: Serialize<F>,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        let mut elements = self.iter().collect::<Vec<_>>();
+        elements.sort_unstable();
+        write_ref::<[F], _, _>(SerIter(elements.into_iter()), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        iter_fast_sizes::<F, _, _>(self.iter())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, FK, FV, K, V> Deserialize<'de, Map<FK, FV>> for BTreeMap<K, V>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Deserialize<'de, FK> + Ord,
+    V: Deserialize<'de, FV>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let mut map = BTreeMap::new();
+        extend_btree_map::<FK, FV, K, V>(&mut map, de)?;
+        Ok(map)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        self.clear();
+        extend_btree_map::<FK, FV, K, V>(self, de)
+    }
+}
+
+/// Deserializes entries of the map into `map`.
+/// Strict deserializer requires keys to be in ascending order.
+#[cfg(feature = "alloc")]
+#[inline]
+fn extend_btree_map<'de, FK, FV, K, V>(
+    map: &mut BTreeMap<K, V>,
+    de: Deserializer<'de>,
+) -> Result<(), DeserializeError>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Deserialize<'de, FK> + Ord,
+    V: Deserialize<'de, FV>,
+{
+    let strict = de.is_strict();
+    let mut iter = de
+        .deref::<[(FK, FV)]>()?
+        .into_unsized_iter::<(FK, FV), (K, V)>();
+    for entry in iter.by_ref() {
+        let (key, value) = entry?;
+        if strict {
+            if let Some((last, _)) = map.last_key_value() {
+                if *last >= key {
+                    return Err(DeserializeError::NonCanonical);
+                }
+            }
+        }
+        map.insert(key, value);
+    }
+    iter.finish()
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, F, T> Deserialize<'de, Set<F>> for BTreeSet<T>
+where
+    F: Formula,
+    T: Deserialize<'de, F> + Ord,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let mut set = BTreeSet::new();
+        extend_btree_set::<F, T>(&mut set, de)?;
+        Ok(set)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        self.clear();
+        extend_btree_set::<F, T>(self, de)
+    }
+}
+
+/// Deserializes elements of the set into `set`.
+/// Strict deserializer requires elements to be in ascending order.
+#[cfg(feature = "alloc")]
+#[inline]
+fn extend_btree_set<'de, F, T>(
+    set: &mut BTreeSet<T>,
+    de: Deserializer<'de>,
+) -> Result<(), DeserializeError>
+where
+    F: Formula,
+    T: Deserialize<'de, F> + Ord,
+{
+    let strict = de.is_strict();
+    let mut iter = de.deref::<[F]>()?.into_unsized_iter::<F, T>();
+    for element in iter.by_ref() {
+        let element = element?;
+        if strict {
+            if let Some(last) = set.last() {
+                if *last >= element {
+                    return Err(DeserializeError::NonCanonical);
+                }
+            }
+        }
+        set.insert(element);
+    }
+    iter.finish()
+}
+
+#[cfg(feature = "std")]
+impl<'de, FK, FV, K, V, S> Deserialize<'de, Map<FK, FV>> for HashMap<K, V, S>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Deserialize<'de, FK> + Eq + Hash,
+    V: Deserialize<'de, FV>,
+    S: BuildHasher + Default,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let iter = de.deref::<[(FK, FV)]>()?.into_unsized_iter::<(FK, FV), _>();
+        let (lower, _) = Iterator::size_hint(&iter);
+        let mut map = HashMap::with_capacity_and_hasher(lower, S::default());
+        deserialize_extend_iter(&mut map, iter)?;
+        Ok(map)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        self.clear();
+        deserialize_extend_iter(
+            self,
+            de.deref::<[(FK, FV)]>()?.into_unsized_iter::<(FK, FV), _>(),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, F, T, S> Deserialize<'de, Set<F>> for HashSet<T, S>
+where
+    F: Formula,
+    T: Deserialize<'de, F> + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let iter = de.deref::<[F]>()?.into_unsized_iter::<F, _>();
+        let (lower, _) = Iterator::size_hint(&iter);
+        let mut set = HashSet::with_capacity_and_hasher(lower, S::default());
+        deserialize_extend_iter(&mut set, iter)?;
+        Ok(set)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        self.clear();
+        deserialize_extend_iter(self, de.deref::<[F]>()?.into_unsized_iter::<F, _>())
+    }
+}
+
+/// Lazy view of a serialized map.
+///
+/// Looks up values by key using binary search over serialized entries,
+/// deserializing only keys it visits and the found value.
+/// Requires key and value formulas to be sized
+/// to access entries in constant time.
+#[derive(Clone)]
+pub struct LazyMap<'de, K, V> {
+    de: Deserializer<'de>,
+    marker: PhantomData<Ref<[(K, V)]>>,
+}
+
+impl<'de, FK, FV> LazyMap<'de, FK, FV>
+where
+    FK: Formula,
+    FV: Formula,
+{
+    // Fail compilation for unsized entries.
+    const ENTRY_SIZE: usize = unwrap_size(<(FK, FV)>::MAX_STACK_SIZE);
+
+    /// Returns number of entries in the map.
+    #[must_use]
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        let _ = Self::ENTRY_SIZE;
+        self.de.sized_len::<(FK, FV)>()
+    }
+
+    /// Returns true if the map has no entries.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Produce iterator over lazy deserialized entries.
+    #[inline(always)]
+    pub fn iter<K, V>(&self) -> SizedDeIter<'de, (FK, FV), (K, V)>
+    where
+        K: Deserialize<'de, FK>,
+        V: Deserialize<'de, FV>,
+    {
+        let _ = Self::ENTRY_SIZE;
+        self.de.clone().into_sized_iter()
+    }
+
+    /// Deserializes value associated with the key.
+    /// Returns `Ok(None)` if key is not in the map.
+    ///
+    /// Key is compared with keys in the map deserialized as `K`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    pub fn get<K, V, Q>(&self, key: &Q) -> Result<Option<V>, DeserializeError>
+    where
+        K: Deserialize<'de, FK> + Borrow<Q>,
+        V: Deserialize<'de, FV>,
+        Q: Ord + ?Sized,
+    {
+        match self.find::<K, Q>(key)? {
+            None => Ok(None),
+            Some(idx) => match self.iter::<K, V>().nth(idx) {
+                Some(Ok((_, value))) => Ok(Some(value)),
+                Some(Err(err)) => Err(err),
+                None => unreachable!(),
+            },
+        }
+    }
+
+    /// Returns true if the key is in the map.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    #[inline]
+    pub fn contains_key<K, Q>(&self, key: &Q) -> Result<bool, DeserializeError>
+    where
+        K: Deserialize<'de, FK> + Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Ok(self.find::<K, Q>(key)?.is_some())
+    }
+
+    #[inline]
+    fn find<K, Q>(&self, key: &Q) -> Result<Option<usize>, DeserializeError>
+    where
+        K: Deserialize<'de, FK> + Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let _ = Self::ENTRY_SIZE;
+        let keys = self.de.clone().into_sized_iter::<(FK, FV), MapKey<K>>();
+        binary_search(keys, key, |entry| entry.0)
+    }
+}
+
+impl<'de, FK, FV> Deserialize<'de, Map<FK, FV>> for LazyMap<'de, FK, FV>
+where
+    FK: Formula,
+    FV: Formula,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        Ok(LazyMap {
+            de: de.deref::<[(FK, FV)]>()?,
+            marker: PhantomData,
+        })
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        self.de = de.deref::<[(FK, FV)]>()?;
+        Ok(())
+    }
+}
+
+/// Lazy view of a serialized set.
+///
+/// Checks presence of elements using binary search over serialized elements,
+/// deserializing only elements it visits.
+/// Requires element formula to be sized
+/// to access elements in constant time.
+#[derive(Clone)]
+pub struct LazySet<'de, F> {
+    de: Deserializer<'de>,
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+impl<'de, F> LazySet<'de, F>
+where
+    F: Formula,
+{
+    // Fail compilation for unsized elements.
+    const ELEMENT_SIZE: usize = unwrap_size(F::MAX_STACK_SIZE);
+
+    /// Returns number of elements in the set.
+    #[must_use]
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        let _ = Self::ELEMENT_SIZE;
+        self.de.sized_len::<F>()
+    }
+
+    /// Returns true if the set has no elements.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Produce iterator over lazy deserialized elements.
+    #[inline(always)]
+    pub fn iter<T>(&self) -> SizedDeIter<'de, F, T>
+    where
+        T: Deserialize<'de, F>,
+    {
+        let _ = Self::ELEMENT_SIZE;
+        self.de.clone().into_sized_iter()
+    }
+
+    /// Returns true if the value is in the set.
+    ///
+    /// Value is compared with elements in the set deserialized as `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    #[inline]
+    pub fn contains<T, Q>(&self, value: &Q) -> Result<bool, DeserializeError>
+    where
+        T: Deserialize<'de, F> + Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Ok(binary_search(self.iter::<T>(), value, |element| element)?.is_some())
+    }
+}
+
+impl<'de, F> Deserialize<'de, Set<F>> for LazySet<'de, F>
+where
+    F: Formula,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        Ok(LazySet {
+            de: de.deref::<[F]>()?,
+            marker: PhantomData,
+        })
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        self.de = de.deref::<[F]>()?;
+        Ok(())
+    }
+}
+
+impl<'de, FK, FV> Deserialize<'de, Map<FK, FV>> for Validated
+where
+    FK: Formula,
+    FV: Formula,
+    Validated: Deserialize<'de, FK> + Deserialize<'de, FV>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Validated as Deserialize<'de, [(FK, FV)]>>::deserialize(de.deref::<[(FK, FV)]>()?)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Map<FK, FV>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl<'de, F> Deserialize<'de, Set<F>> for Validated
+where
+    F: Formula,
+    Validated: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Validated as Deserialize<'de, [F]>>::deserialize(de.deref::<[F]>()?)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Set<F>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Finds index of the element with specified key in sorted sequence.
+/// Relies on `nth` of sized iterator to access elements in constant time.
+fn binary_search<'de, F, T, K, Q>(
+    iter: SizedDeIter<'de, F, T>,
+    key: &Q,
+    get_key: impl Fn(T) -> K,
+) -> Result<Option<usize>, DeserializeError>
+where
+    F: Formula,
+    T: Deserialize<'de, F>,
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let mut low = 0;
+    let mut high = iter.len();
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let element = match iter.clone().nth(mid) {
+            Some(element) => element?,
+            None => unreachable!(),
+        };
+
+        match get_key(element).borrow().cmp(key) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return Ok(Some(mid)),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Deserializes only key of the map entry.
+pub(crate) struct MapKey<K>(pub(crate) K);
+
+impl<'de, FK, FV, K> Deserialize<'de, (FK, FV)> for MapKey<K>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Deserialize<'de, FK>,
+{
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        Ok(MapKey(de.read_value::<FK, K>(false)?))
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, mut de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        de.read_in_place::<FK, K>(&mut self.0, false)
+    }
+}
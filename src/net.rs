@@ -0,0 +1,108 @@
+//!
+//! This module provides formulas for IP addresses and socket addresses.
+//!
+//! `Ipv4Addr` and `Ipv6Addr` are serialized as `u32` and `u128`
+//! numeric values of the address, the same way as other integers.
+//! `IpAddr` and `SocketAddr` are serialized as enums with `V4` and `V6`
+//! variants, the same way as derived enum formulas.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use crate::formula::impl_repr_formula;
+
+#[cfg(feature = "alloc")]
+use {
+    crate::{
+        descriptor::{Descriptor, Field, Variant},
+        formula::Formula,
+    },
+    alloc::{boxed::Box, vec},
+};
+
+/// Returns descriptor of enum with `V4` and `V6` variants.
+#[cfg(feature = "alloc")]
+fn v4_v6_descriptor<V4, V6>(name: &str) -> Descriptor
+where
+    V4: Formula,
+    V6: Formula,
+{
+    Descriptor::Enum {
+        name: name.into(),
+        variants: vec![
+            Variant::new(0, "V4", vec![Field::new("0", V4::descriptor())]),
+            Variant::new(1, "V6", vec![Field::new("0", V6::descriptor())]),
+        ],
+        non_exhaustive: false,
+        discriminant: Box::new(Descriptor::U32),
+    }
+}
+
+impl_repr_formula! {
+    Ipv4Addr as u32 {
+        value: Ipv4Addr,
+        repr: u32,
+        to: |addr| u32::from(*addr),
+        from: |bits| Ok(Ipv4Addr::from(bits)),
+    }
+}
+
+impl_repr_formula! {
+    Ipv6Addr as u128 {
+        value: Ipv6Addr,
+        repr: u128,
+        to: |addr| u128::from(*addr),
+        from: |bits| Ok(Ipv6Addr::from(bits)),
+    }
+}
+
+impl_repr_formula! {
+    IpAddr as Result<Ipv4Addr, Ipv6Addr> {
+        value: IpAddr,
+        repr: Result<Ipv4Addr, Ipv6Addr>,
+        to: |addr| match *addr {
+            IpAddr::V4(addr) => Ok(addr),
+            IpAddr::V6(addr) => Err(addr),
+        },
+        from: |repr| Ok(match repr {
+            Ok(addr) => IpAddr::V4(addr),
+            Err(addr) => IpAddr::V6(addr),
+        }),
+        descriptor: v4_v6_descriptor::<Ipv4Addr, Ipv6Addr>("IpAddr"),
+    }
+}
+
+impl_repr_formula! {
+    SocketAddrV4 as (Ipv4Addr, u16) {
+        value: SocketAddrV4,
+        repr: (Ipv4Addr, u16),
+        to: |addr| (*addr.ip(), addr.port()),
+        from: |(ip, port)| Ok(SocketAddrV4::new(ip, port)),
+    }
+}
+
+impl_repr_formula! {
+    SocketAddrV6 as (Ipv6Addr, u16, u32, u32) {
+        value: SocketAddrV6,
+        repr: (Ipv6Addr, u16, u32, u32),
+        to: |addr| (*addr.ip(), addr.port(), addr.flowinfo(), addr.scope_id()),
+        from: |(ip, port, flowinfo, scope_id)| {
+            Ok(SocketAddrV6::new(ip, port, flowinfo, scope_id))
+        },
+    }
+}
+
+impl_repr_formula! {
+    SocketAddr as Result<SocketAddrV4, SocketAddrV6> {
+        value: SocketAddr,
+        repr: Result<SocketAddrV4, SocketAddrV6>,
+        to: |addr| match *addr {
+            SocketAddr::V4(addr) => Ok(addr),
+            SocketAddr::V6(addr) => Err(addr),
+        },
+        from: |repr| Ok(match repr {
+            Ok(addr) => SocketAddr::V4(addr),
+            Err(addr) => SocketAddr::V6(addr),
+        }),
+        descriptor: v4_v6_descriptor::<SocketAddrV4, SocketAddrV6>("SocketAddr"),
+    }
+}
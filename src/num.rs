@@ -0,0 +1,236 @@
+use core::{
+    mem::size_of,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+        NonZeroU32, NonZeroU64, NonZeroU8, Saturating, Wrapping,
+    },
+};
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{BareFormula, Formula},
+    serialize::{write_bytes, Serialize, Sizes},
+    validate::Validated,
+};
+
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
+macro_rules! impl_non_zero {
+    ($($non_zero:ident($ty:ident)),* $(,)?) => {$(
+        impl Formula for $non_zero {
+            const MAX_STACK_SIZE: Option<usize> = Some(size_of::<$ty>());
+            const EXACT_SIZE: bool = true;
+            const HEAPLESS: bool = true;
+
+            #[cfg(feature = "alloc")]
+            #[inline(always)]
+            fn descriptor() -> Descriptor {
+                Descriptor::opaque::<Self>(stringify!($non_zero))
+            }
+        }
+
+        impl BareFormula for $non_zero {}
+
+        impl Serialize<$non_zero> for $non_zero {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_bytes(&self.get().to_le_bytes(), sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes::with_stack(size_of::<$ty>()))
+            }
+        }
+
+        impl Serialize<$non_zero> for &$non_zero {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_bytes(&self.get().to_le_bytes(), sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes::with_stack(size_of::<$ty>()))
+            }
+        }
+
+        impl Serialize<$ty> for $non_zero {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_bytes(&self.get().to_le_bytes(), sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes::with_stack(size_of::<$ty>()))
+            }
+        }
+
+        impl Serialize<$ty> for &$non_zero {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_bytes(&self.get().to_le_bytes(), sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes::with_stack(size_of::<$ty>()))
+            }
+        }
+
+        impl<T> Deserialize<'_, $non_zero> for T
+        where
+            T: From<$non_zero>,
+        {
+            #[inline(always)]
+            fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+                let input = de.read_byte_array::<{ size_of::<$ty>() }>()?;
+                match $non_zero::new(<$ty>::from_le_bytes(input)) {
+                    Some(value) => Ok(T::from(value)),
+                    None => Err(DeserializeError::ZeroValue),
+                }
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+                *self = <T as Deserialize<$non_zero>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+
+        impl Deserialize<'_, $non_zero> for Validated {
+            #[inline(always)]
+            fn deserialize(de: Deserializer) -> Result<Self, DeserializeError> {
+                <$non_zero as Deserialize<$non_zero>>::deserialize(de)?;
+                Ok(Validated)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+                <Self as Deserialize<$non_zero>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+    )*};
+}
+
+impl_non_zero! {
+    NonZeroU8(u8),
+    NonZeroU16(u16),
+    NonZeroU32(u32),
+    NonZeroU64(u64),
+    NonZeroU128(u128),
+    NonZeroI8(i8),
+    NonZeroI16(i16),
+    NonZeroI32(i32),
+    NonZeroI64(i64),
+    NonZeroI128(i128),
+}
+
+macro_rules! impl_num_wrapper {
+    ($($wrapper:ident),*) => {$(
+        impl<F> Formula for $wrapper<F>
+        where
+            F: Formula,
+        {
+            const MAX_STACK_SIZE: Option<usize> = F::MAX_STACK_SIZE;
+            const EXACT_SIZE: bool = F::EXACT_SIZE;
+            const HEAPLESS: bool = F::HEAPLESS;
+
+            #[cfg(feature = "alloc")]
+            #[inline(always)]
+            fn descriptor() -> Descriptor {
+                F::descriptor()
+            }
+        }
+
+        impl<F> BareFormula for $wrapper<F> where F: Formula {}
+
+        impl<F, T> Serialize<$wrapper<F>> for $wrapper<T>
+        where
+            F: Formula,
+            T: Serialize<F>,
+        {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                <T as Serialize<F>>::serialize(self.0, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                <T as Serialize<F>>::size_hint(&self.0)
+            }
+        }
+
+        impl<'ser, F, T> Serialize<$wrapper<F>> for &'ser $wrapper<T>
+        where
+            F: Formula,
+            &'ser T: Serialize<F>,
+        {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                <&'ser T as Serialize<F>>::serialize(&self.0, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                <&'ser T as Serialize<F>>::size_hint(&&self.0)
+            }
+        }
+
+        impl<'de, F, T> Deserialize<'de, $wrapper<F>> for $wrapper<T>
+        where
+            F: Formula,
+            T: Deserialize<'de, F>,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                Ok($wrapper(<T as Deserialize<'de, F>>::deserialize(de)?))
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+                <T as Deserialize<'de, F>>::deserialize_in_place(&mut self.0, de)
+            }
+        }
+
+        impl<'de, F> Deserialize<'de, $wrapper<F>> for Validated
+        where
+            F: Formula,
+            Validated: Deserialize<'de, F>,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                <Validated as Deserialize<'de, F>>::deserialize(de)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+                <Validated as Deserialize<'de, F>>::deserialize_in_place(self, de)
+            }
+        }
+    )*};
+}
+
+impl_num_wrapper!(Wrapping, Saturating);
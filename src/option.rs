@@ -3,8 +3,12 @@ use crate::{
     deserialize::{Deserialize, DeserializeError, Deserializer},
     formula::{sum_size, BareFormula, Formula},
     serialize::{field_size_hint, write_bytes, write_field, Serialize, Sizes},
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 impl<F> Formula for Option<F>
 where
     F: Formula,
@@ -12,6 +16,12 @@ where
     const MAX_STACK_SIZE: Option<usize> = sum_size(Some(1), F::MAX_STACK_SIZE);
     const EXACT_SIZE: bool = matches!(F::MAX_STACK_SIZE, Some(0));
     const HEAPLESS: bool = F::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Option(F::descriptor().into())
+    }
 }
 
 impl<F> BareFormula for Option<F> where F: Formula {}
@@ -93,20 +103,18 @@ where
 {
     #[inline(always)]
     fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
-        let is_some: u8 = de.read_bytes(1)?[0];
-        if is_some == 0 {
-            Ok(None)
+        let value = if read_is_some(&mut de)? {
+            Some(de.read_value::<F, T>(true)?)
         } else {
-            Ok(Some(de.read_value::<F, T>(true)?))
-        }
+            None
+        };
+        de.finish()?;
+        Ok(value)
     }
 
     #[inline(always)]
     fn deserialize_in_place(&mut self, mut de: Deserializer<'de>) -> Result<(), DeserializeError> {
-        let is_some: u8 = de.read_bytes(1)?[0];
-        if is_some == 0 {
-            *self = None;
-        } else {
+        if read_is_some(&mut de)? {
             match self {
                 Some(value) => {
                     de.read_in_place::<F, T>(value, true)?;
@@ -115,7 +123,42 @@ where
                     *self = Some(de.read_value::<F, T>(true)?);
                 }
             }
+        } else {
+            *self = None;
+        }
+        de.finish()
+    }
+}
+
+impl<'de, F> Deserialize<'de, Option<F>> for Validated
+where
+    F: Formula,
+    Validated: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        if read_is_some(&mut de)? {
+            de.read_value::<F, Validated>(true)?;
         }
+        de.finish()?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Option<F>>>::deserialize(de)?;
         Ok(())
     }
 }
+
+/// Reads flag of `Some` variant.
+/// Strict deserializer accepts only `0` and `1`.
+#[inline(always)]
+fn read_is_some(de: &mut Deserializer) -> Result<bool, DeserializeError> {
+    match de.read_byte()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ if de.is_strict() => Err(DeserializeError::NonCanonical),
+        _ => Ok(true),
+    }
+}
@@ -0,0 +1,161 @@
+//!
+//! This module provides formulas for file system paths.
+//!
+//! Paths are serialized as bytes of the path without terminator,
+//! the same way as `str`.
+//! Paths that are valid UTF-8 are portable.
+//! Other paths are written in platform-specific encoding of `OsStr`
+//! and can be deserialized only on unix platforms,
+//! where paths are arbitrary byte sequences.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{reference_size, BareFormula, Formula},
+    reference::Ref,
+    serialize::{write_bytes, write_ref, Serialize, Sizes},
+    validate::Validated,
+};
+
+use crate::descriptor::Descriptor;
+
+impl Formula for Path {
+    const MAX_STACK_SIZE: Option<usize> = None;
+    const EXACT_SIZE: bool = true;
+    const HEAPLESS: bool = true;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("Path")
+    }
+}
+
+impl BareFormula for Path {}
+
+impl Formula for PathBuf {
+    const MAX_STACK_SIZE: Option<usize> = <Ref<Path> as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <Ref<Path> as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <Ref<Path> as Formula>::HEAPLESS;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<Path> as Formula>::descriptor()
+    }
+}
+
+impl<T> Serialize<PathBuf> for T
+where
+    T: Serialize<Path>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_ref::<Path, T, _>(self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        let mut sizes = <Self as Serialize<Path>>::size_hint(self)?;
+        sizes.to_heap(0);
+        sizes.add_stack(reference_size::<Path>());
+        Some(sizes)
+    }
+}
+
+impl<'de, T> Deserialize<'de, PathBuf> for T
+where
+    T: Deserialize<'de, Path>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<T, DeserializeError> {
+        let de = de.deref::<Path>()?;
+        <T as Deserialize<Path>>::deserialize(de)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        let de = de.deref::<Path>()?;
+        <T as Deserialize<Path>>::deserialize_in_place(self, de)
+    }
+}
+
+macro_rules! impl_serialize_path {
+    ($($ty:ty),*) => {$(
+        impl Serialize<Path> for $ty {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_bytes(self.as_os_str().as_encoded_bytes(), sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes::with_stack(self.as_os_str().len()))
+            }
+        }
+    )*};
+}
+
+impl_serialize_path!(&Path, PathBuf, &PathBuf);
+
+/// Converts serialized bytes to path.
+#[inline(always)]
+fn bytes_to_path(bytes: &[u8]) -> Result<&Path, DeserializeError> {
+    match core::str::from_utf8(bytes) {
+        Ok(s) => Ok(Path::new(s)),
+        #[cfg(unix)]
+        Err(_) => Ok(Path::new(
+            <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(bytes),
+        )),
+        #[cfg(not(unix))]
+        Err(error) => Err(DeserializeError::NonUtf8(error)),
+    }
+}
+
+impl<'de, 'fe: 'de> Deserialize<'fe, Path> for &'de Path {
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'fe>) -> Result<Self, DeserializeError> {
+        bytes_to_path(de.read_all_bytes())
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'fe>) -> Result<(), DeserializeError> {
+        *self = bytes_to_path(de.read_all_bytes())?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_, Path> for PathBuf {
+    #[inline(always)]
+    fn deserialize(de: Deserializer) -> Result<Self, DeserializeError> {
+        Ok(bytes_to_path(de.read_all_bytes())?.to_path_buf())
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        let path = bytes_to_path(de.read_all_bytes())?;
+        self.clear();
+        self.push(path);
+        Ok(())
+    }
+}
+
+impl Deserialize<'_, Path> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer) -> Result<Self, DeserializeError> {
+        bytes_to_path(de.read_all_bytes())?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        bytes_to_path(de.read_all_bytes())?;
+        Ok(())
+    }
+}
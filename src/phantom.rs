@@ -0,0 +1,98 @@
+use core::marker::PhantomData;
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{BareFormula, Formula},
+    serialize::{Serialize, Sizes},
+    validate::Validated,
+};
+
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
+impl<F> Formula for PhantomData<F>
+where
+    F: ?Sized,
+{
+    const MAX_STACK_SIZE: Option<usize> = Some(0);
+    const EXACT_SIZE: bool = true;
+    const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Unit
+    }
+}
+
+impl<F> BareFormula for PhantomData<F> where F: ?Sized {}
+
+impl<F, T> Serialize<PhantomData<F>> for PhantomData<T>
+where
+    F: ?Sized,
+    T: ?Sized,
+{
+    #[inline(always)]
+    fn serialize<B>(self, _sizes: &mut Sizes, _buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::ZERO)
+    }
+}
+
+impl<F, T> Serialize<PhantomData<F>> for &PhantomData<T>
+where
+    F: ?Sized,
+    T: ?Sized,
+{
+    #[inline(always)]
+    fn serialize<B>(self, _sizes: &mut Sizes, _buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::ZERO)
+    }
+}
+
+impl<F, T> Deserialize<'_, PhantomData<F>> for PhantomData<T>
+where
+    F: ?Sized,
+    T: ?Sized,
+{
+    #[inline(always)]
+    fn deserialize(_de: Deserializer) -> Result<Self, DeserializeError> {
+        Ok(PhantomData)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, _de: Deserializer) -> Result<(), DeserializeError> {
+        Ok(())
+    }
+}
+
+impl<F> Deserialize<'_, PhantomData<F>> for Validated
+where
+    F: ?Sized,
+{
+    #[inline(always)]
+    fn deserialize(_de: Deserializer) -> Result<Self, DeserializeError> {
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, _de: Deserializer) -> Result<(), DeserializeError> {
+        Ok(())
+    }
+}
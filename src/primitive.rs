@@ -1,12 +1,175 @@
-use core::{borrow::Borrow, mem::size_of};
+use core::{cmp::Ordering, mem::size_of};
 
 use crate::{
     buffer::Buffer,
     deserialize::{Deserialize, DeserializeError, Deserializer},
+    endian::{Be, Ne},
     formula::{BareFormula, Formula},
     serialize::{write_bytes, Serialize, Sizes},
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use {crate::descriptor::Descriptor, alloc::boxed::Box};
+
+#[cfg(feature = "alloc")]
+macro_rules! primitive_descriptor {
+    (u8) => {
+        Descriptor::U8
+    };
+    (u16) => {
+        Descriptor::U16
+    };
+    (u32) => {
+        Descriptor::U32
+    };
+    (u64) => {
+        Descriptor::U64
+    };
+    (u128) => {
+        Descriptor::U128
+    };
+    (i8) => {
+        Descriptor::I8
+    };
+    (i16) => {
+        Descriptor::I16
+    };
+    (i32) => {
+        Descriptor::I32
+    };
+    (i64) => {
+        Descriptor::I64
+    };
+    (i128) => {
+        Descriptor::I128
+    };
+    (f32) => {
+        Descriptor::F32
+    };
+    (f64) => {
+        Descriptor::F64
+    };
+}
+
+macro_rules! impl_endian {
+    ($endian:ident, $to_bytes:ident, $from_bytes:ident, $big_endian:expr, $ty:ident $($from:ident)*) => {
+        impl Formula for $endian<$ty> {
+            const MAX_STACK_SIZE: Option<usize> = Some(size_of::<$ty>());
+            const EXACT_SIZE: bool = true;
+            const HEAPLESS: bool = true;
+
+            #[cfg(feature = "alloc")]
+            #[inline(always)]
+            fn descriptor() -> Descriptor {
+                if $big_endian {
+                    Descriptor::BigEndian(Box::new(primitive_descriptor!($ty)))
+                } else {
+                    primitive_descriptor!($ty)
+                }
+            }
+        }
+
+        impl BareFormula for $endian<$ty> {}
+
+        impl Serialize<$endian<$ty>> for $ty {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_bytes(&self.$to_bytes(), sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes{ heap: 0, stack: size_of::<$ty>()})
+            }
+        }
+
+        impl Serialize<$endian<$ty>> for &$ty {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_bytes(&self.$to_bytes(), sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                Some(Sizes{ heap: 0, stack: size_of::<$ty>()})
+            }
+        }
+
+        $(
+            impl Serialize<$endian<$ty>> for $from {
+                #[inline(always)]
+                fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+                where
+                    B: Buffer,
+                {
+                    write_bytes(&$ty::from(self).$to_bytes(), sizes, buffer)
+                }
+
+                #[inline(always)]
+                fn size_hint(&self) -> Option<Sizes> {
+                    Some(Sizes{ heap: 0, stack: size_of::<$ty>()})
+                }
+            }
+
+            impl Serialize<$endian<$ty>> for &$from {
+                #[inline(always)]
+                fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+                where
+                    B: Buffer,
+                {
+                    write_bytes(&$ty::from(*self).$to_bytes(), sizes, buffer)
+                }
+
+                #[inline(always)]
+                fn size_hint(&self) -> Option<Sizes> {
+                    Some(Sizes{ heap: 0, stack: size_of::<$ty>()})
+                }
+            }
+        )*
+
+        impl<T> Deserialize<'_, $endian<$ty>> for T
+        where
+            T: From<$ty>,
+        {
+            #[inline(always)]
+            fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+                let input = de.read_byte_array::<{size_of::<$ty>()}>()?;
+                let value = <$ty>::$from_bytes(input);
+                return Ok(From::from(value));
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, mut de: Deserializer) -> Result<(), DeserializeError> {
+                let input = de.read_byte_array::<{size_of::<$ty>()}>()?;
+                let value = <$ty>::$from_bytes(input);
+                *self = From::from(value);
+                Ok(())
+            }
+        }
+
+        impl Deserialize<'_, $endian<$ty>> for Validated {
+            #[inline(always)]
+            fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+                de.read_byte_array::<{size_of::<$ty>()}>()?;
+                Ok(Validated)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+                <Self as Deserialize<$endian<$ty>>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+    };
+}
+
 macro_rules! impl_primitive {
     () => {};
 
@@ -26,6 +189,12 @@ macro_rules! impl_primitive {
             const MAX_STACK_SIZE: Option<usize> = Some(size_of::<$ty>());
             const EXACT_SIZE: bool = true;
             const HEAPLESS: bool = true;
+
+            #[cfg(feature = "alloc")]
+            #[inline(always)]
+            fn descriptor() -> Descriptor {
+                primitive_descriptor!($ty)
+            }
         }
 
         impl BareFormula for $ty {}
@@ -101,7 +270,7 @@ macro_rules! impl_primitive {
             #[inline(always)]
             fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
                 let input = de.read_byte_array::<{size_of::<$ty>()}>()?;
-                // de.finish()?;
+                de.finish()?;
                 let value = <$ty>::from_le_bytes(input);
                 return Ok(From::from(value));
             }
@@ -109,12 +278,29 @@ macro_rules! impl_primitive {
             #[inline(always)]
             fn deserialize_in_place(&mut self, mut de: Deserializer) -> Result<(), DeserializeError> {
                 let input = de.read_byte_array::<{size_of::<$ty>()}>()?;
-                // de.finish()?;
+                de.finish()?;
                 let value = <$ty>::from_le_bytes(input);
                 *self = From::from(value);
                 Ok(())
             }
         }
+
+        impl Deserialize<'_, $ty> for Validated {
+            #[inline(always)]
+            fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+                de.read_byte_array::<{size_of::<$ty>()}>()?;
+                Ok(Validated)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+                <Self as Deserialize<$ty>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+
+        impl_endian!(Be, to_be_bytes, from_be_bytes, true, $ty $($from)*);
+        impl_endian!(Ne, to_ne_bytes, from_ne_bytes, cfg!(target_endian = "big"), $ty $($from)*);
     };
 }
 
@@ -128,6 +314,12 @@ impl Formula for bool {
     const MAX_STACK_SIZE: Option<usize> = Some(1);
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Bool
+    }
 }
 
 impl BareFormula for bool {}
@@ -157,7 +349,7 @@ impl Serialize<bool> for &bool {
     where
         B: Buffer,
     {
-        <u8 as Serialize<u8>>::serialize(u8::from(*self.borrow()), sizes, buffer)
+        <u8 as Serialize<u8>>::serialize(u8::from(*self), sizes, buffer)
     }
 
     #[inline(always)]
@@ -175,14 +367,192 @@ where
 {
     #[inline(always)]
     fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
-        let byte = de.read_byte()?;
-        Ok(T::from(byte != 0))
+        Ok(T::from(read_bool(&mut de)?))
     }
 
     #[inline(always)]
     fn deserialize_in_place(&mut self, mut de: Deserializer) -> Result<(), DeserializeError> {
-        let byte = de.read_byte()?;
-        *self = From::from(byte != 0);
+        *self = From::from(read_bool(&mut de)?);
+        Ok(())
+    }
+}
+
+impl Deserialize<'_, bool> for Validated {
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+        read_bool(&mut de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<bool>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Reads `bool` value, any non-zero byte is `true`.
+/// Strict deserializer accepts only `0` and `1`.
+#[inline(always)]
+fn read_bool(de: &mut Deserializer) -> Result<bool, DeserializeError> {
+    match de.read_byte()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ if de.is_strict() => Err(DeserializeError::NonCanonical),
+        _ => Ok(true),
+    }
+}
+
+impl Formula for char {
+    const MAX_STACK_SIZE: Option<usize> = Some(size_of::<u32>());
+    const EXACT_SIZE: bool = true;
+    const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("char")
+    }
+}
+
+impl BareFormula for char {}
+
+impl Serialize<char> for char {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(&u32::from(self).to_le_bytes(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(size_of::<u32>()))
+    }
+}
+
+impl Serialize<char> for &char {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        <char as Serialize<char>>::serialize(*self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(size_of::<u32>()))
+    }
+}
+
+impl<T> Deserialize<'_, char> for T
+where
+    T: From<char>,
+{
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+        let value = u32::from_le_bytes(de.read_byte_array::<4>()?);
+        match char::from_u32(value) {
+            Some(c) => Ok(T::from(c)),
+            None => Err(DeserializeError::InvalidChar(value)),
+        }
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        *self = <T as Deserialize<char>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_, char> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer) -> Result<Self, DeserializeError> {
+        <char as Deserialize<char>>::deserialize(de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<char>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl Formula for Ordering {
+    const MAX_STACK_SIZE: Option<usize> = Some(size_of::<i8>());
+    const EXACT_SIZE: bool = true;
+    const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::opaque::<Self>("Ordering")
+    }
+}
+
+impl BareFormula for Ordering {}
+
+impl Serialize<Ordering> for Ordering {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_bytes(&(self as i8).to_le_bytes(), sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(size_of::<i8>()))
+    }
+}
+
+impl Serialize<Ordering> for &Ordering {
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        <Ordering as Serialize<Ordering>>::serialize(*self, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        Some(Sizes::with_stack(size_of::<i8>()))
+    }
+}
+
+impl Deserialize<'_, Ordering> for Ordering {
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+        match de.read_byte()? {
+            0xFF => Ok(Ordering::Less),
+            0 => Ok(Ordering::Equal),
+            1 => Ok(Ordering::Greater),
+            invalid => Err(DeserializeError::WrongVariant(u32::from(invalid))),
+        }
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        *self = <Ordering as Deserialize<Ordering>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_, Ordering> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer) -> Result<Self, DeserializeError> {
+        <Ordering as Deserialize<Ordering>>::deserialize(de)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<Ordering>>::deserialize(de)?;
         Ok(())
     }
 }
@@ -0,0 +1,214 @@
+use alloc::{rc::Rc, sync::Arc, vec::Vec};
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{BareFormula, Formula},
+    reference::Ref,
+    serialize::{write_ref, Serialize, Sizes},
+    validate::Validated,
+};
+
+use crate::descriptor::Descriptor;
+
+macro_rules! impl_shared {
+    ($ptr:ident) => {
+        impl<F> Formula for $ptr<F>
+        where
+            F: BareFormula + ?Sized,
+        {
+            const MAX_STACK_SIZE: Option<usize> = <Ref<F> as Formula>::MAX_STACK_SIZE;
+            const EXACT_SIZE: bool = <Ref<F> as Formula>::EXACT_SIZE;
+            const HEAPLESS: bool = <Ref<F> as Formula>::HEAPLESS;
+
+            #[inline(always)]
+            fn descriptor() -> Descriptor {
+                <Ref<F> as Formula>::descriptor()
+            }
+        }
+
+        impl<F, T> Serialize<$ptr<F>> for T
+        where
+            F: BareFormula + ?Sized,
+            T: Serialize<F>,
+        {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                write_ref::<F, T, _>(self, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                <Self as Serialize<Ref<F>>>::size_hint(self)
+            }
+        }
+
+        impl<'de, F, T> Deserialize<'de, $ptr<F>> for $ptr<T>
+        where
+            F: BareFormula + ?Sized,
+            T: Deserialize<'de, F>,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let de = de.deref::<F>()?;
+                Ok($ptr::new(<T as Deserialize<F>>::deserialize(de)?))
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                let de = de.deref::<F>()?;
+                match $ptr::get_mut(self) {
+                    Some(value) => <T as Deserialize<F>>::deserialize_in_place(value, de),
+                    None => {
+                        *self = $ptr::new(<T as Deserialize<F>>::deserialize(de)?);
+                        Ok(())
+                    }
+                }
+            }
+        }
+
+        impl<'de, F> Deserialize<'de, $ptr<F>> for Validated
+        where
+            F: BareFormula + ?Sized,
+            Validated: Deserialize<'de, F>,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                <Validated as Deserialize<'de, Ref<F>>>::deserialize(de)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                <Validated as Deserialize<'de, Ref<F>>>::deserialize_in_place(self, de)
+            }
+        }
+
+        impl<'de> Deserialize<'de, $ptr<str>> for $ptr<str> {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let de = de.deref::<str>()?;
+                <Self as Deserialize<str>>::deserialize(de)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                let de = de.deref::<str>()?;
+                <Self as Deserialize<str>>::deserialize_in_place(self, de)
+            }
+        }
+
+        impl<'de, F, T> Deserialize<'de, $ptr<[F]>> for $ptr<[T]>
+        where
+            F: Formula,
+            T: Deserialize<'de, F>,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let de = de.deref::<[F]>()?;
+                <Self as Deserialize<[F]>>::deserialize(de)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                let de = de.deref::<[F]>()?;
+                <Self as Deserialize<[F]>>::deserialize_in_place(self, de)
+            }
+        }
+
+        impl<F, T> Serialize<F> for $ptr<T>
+        where
+            F: BareFormula + ?Sized,
+            T: ?Sized,
+            for<'ser> &'ser T: Serialize<F>,
+        {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                <&T as Serialize<F>>::serialize(&*self, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                <&T as Serialize<F>>::size_hint(&&**self)
+            }
+        }
+
+        impl<'ser, F, T> Serialize<F> for &'ser $ptr<T>
+        where
+            F: BareFormula + ?Sized,
+            T: ?Sized,
+            &'ser T: Serialize<F>,
+        {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                <&'ser T as Serialize<F>>::serialize(self, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                <&'ser T as Serialize<F>>::size_hint(&&***self)
+            }
+        }
+
+        impl<'de> Deserialize<'de, str> for $ptr<str> {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let string = <&str as Deserialize<'de, str>>::deserialize(de)?;
+                Ok($ptr::from(string))
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                *self = <Self as Deserialize<'de, str>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+
+        impl<'de, F, T> Deserialize<'de, [F]> for $ptr<[T]>
+        where
+            F: Formula,
+            T: Deserialize<'de, F>,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let vec = <Vec<T> as Deserialize<'de, [F]>>::deserialize(de)?;
+                Ok($ptr::from(vec))
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                *self = <Self as Deserialize<'de, [F]>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_shared!(Rc);
+impl_shared!(Arc);
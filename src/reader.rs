@@ -0,0 +1,287 @@
+use std::io::{self, BufRead, Read};
+
+use alloc::vec::Vec;
+
+use crate::{
+    deserialize::{deserialize, deserialize_in_place, Deserialize, DeserializeError},
+    formula::{reference_size, Formula},
+    size::{FixedUsize, SIZE_STACK},
+};
+
+/// Reads serialized values one by one from [`std::io::Read`].
+///
+/// Each value is expected to be serialized as a separate message,
+/// e.g. with [`serialize_into_writer`](crate::serialize_into_writer).
+/// Messages are length-delimited by the root reference
+/// that is written in front of the value, so no additional framing is required.
+///
+/// Bytes of the message are read into internal buffer
+/// that is reused for subsequent messages.
+/// Values can be deserialized into owned types or borrow from that buffer.
+///
+/// Exactly the bytes of the message are read from the reader.
+/// Wrapping slow readers into [`std::io::BufReader`] is recommended.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// let mut data = Vec::new();
+/// serialize_into_writer::<str, _, _>("Hello", &mut data).unwrap();
+/// serialize_into_writer::<str, _, _>("World", &mut data).unwrap();
+///
+/// let mut reader = Reader::new(&data[..]);
+/// assert_eq!(reader.read::<str, String>().unwrap().unwrap(), "Hello");
+/// assert_eq!(reader.read_borrowed::<str, &str>().unwrap().unwrap(), "World");
+/// assert!(reader.read::<str, String>().unwrap().is_none());
+/// ```
+pub struct Reader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    max_size: usize,
+}
+
+impl<R> Reader<R>
+where
+    R: Read,
+{
+    /// Creates new reader.
+    #[inline(always)]
+    pub const fn new(reader: R) -> Self {
+        Reader {
+            reader,
+            buffer: Vec::new(),
+            max_size: usize::MAX,
+        }
+    }
+
+    /// Limits size of messages this reader accepts.
+    /// Reading a message that is larger than `max_size` bytes fails
+    /// before the message body is read.
+    ///
+    /// Use it with untrusted sources to avoid allocation
+    /// of arbitrary large buffers.
+    #[inline(always)]
+    pub fn set_max_message_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+    }
+
+    /// Returns reference to the underlying reader.
+    #[inline(always)]
+    pub const fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns mutable reference to the underlying reader.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads next message and returns its bytes.
+    /// Returned slice can be passed to [`deserialize`](crate::deserialize)
+    /// or forwarded as is.
+    ///
+    /// Returns `Ok(None)` if the reader is exhausted before the message starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if reading fails.
+    /// Returns error of [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) kind
+    /// if the reader is exhausted in the middle of the message.
+    /// Returns error of [`InvalidData`](io::ErrorKind::InvalidData) kind
+    /// if message header is invalid or message is too large.
+    pub fn read_bytes<F>(&mut self) -> io::Result<Option<&[u8]>>
+    where
+        F: Formula + ?Sized,
+    {
+        match read_message::<F>(&mut self.reader, &mut self.buffer, self.max_size)? {
+            false => Ok(None),
+            true => Ok(Some(&self.buffer)),
+        }
+    }
+
+    /// Reads next message and deserializes value from it.
+    ///
+    /// Returns `Ok(None)` if the reader is exhausted before the message starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns same errors as [`Reader::read_bytes`].
+    /// Returns error of [`InvalidData`](io::ErrorKind::InvalidData) kind
+    /// if deserialization fails.
+    #[inline]
+    pub fn read<F, T>(&mut self) -> io::Result<Option<T>>
+    where
+        F: Formula + ?Sized,
+        T: for<'de> Deserialize<'de, F>,
+    {
+        self.read_borrowed::<F, T>()
+    }
+
+    /// Reads next message and deserializes value from it.
+    /// Deserialized value may borrow from the internal buffer
+    /// and must be dropped before next message is read.
+    ///
+    /// Returns `Ok(None)` if the reader is exhausted before the message starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns same errors as [`Reader::read`].
+    pub fn read_borrowed<'de, F, T>(&'de mut self) -> io::Result<Option<T>>
+    where
+        F: Formula + ?Sized,
+        T: Deserialize<'de, F>,
+    {
+        match self.read_bytes::<F>()? {
+            None => Ok(None),
+            Some(bytes) => match deserialize::<F, T>(bytes) {
+                Ok((value, _)) => Ok(Some(value)),
+                Err(err) => Err(invalid_data(err)),
+            },
+        }
+    }
+
+    /// Reads next message and deserializes value from it into specified place.
+    ///
+    /// Returns `Ok(false)` if the reader is exhausted before the message starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns same errors as [`Reader::read`].
+    pub fn read_in_place<F, T>(&mut self, place: &mut T) -> io::Result<bool>
+    where
+        F: Formula + ?Sized,
+        T: for<'de> Deserialize<'de, F> + ?Sized,
+    {
+        match self.read_bytes::<F>()? {
+            None => Ok(false),
+            Some(bytes) => match deserialize_in_place::<F, T>(place, bytes) {
+                Ok(_) => Ok(true),
+                Err(err) => Err(invalid_data(err)),
+            },
+        }
+    }
+}
+
+/// Reads one message from [`std::io::BufRead`] and deserializes value from it.
+///
+/// If whole message is already buffered by the reader,
+/// value is deserialized directly from reader's buffer without copying.
+/// Otherwise message is copied into temporary vector.
+/// Use [`Reader`] to reuse allocation across messages.
+///
+/// Returns `Ok(None)` if the reader is exhausted before the message starts.
+///
+/// # Errors
+///
+/// Returns same errors as [`Reader::read`].
+pub fn deserialize_from_buf_read<F, T, R>(mut reader: R) -> io::Result<Option<T>>
+where
+    F: Formula + ?Sized,
+    T: for<'de> Deserialize<'de, F>,
+    R: BufRead,
+{
+    if reference_size::<F>() > 0 {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(None);
+        }
+        if available.len() >= SIZE_STACK {
+            let address = read_address::<F>(available)?;
+            if available.len() >= address {
+                let value = match deserialize::<F, T>(&available[..address]) {
+                    Ok((value, _)) => value,
+                    Err(err) => return Err(invalid_data(err)),
+                };
+                reader.consume(address);
+                return Ok(Some(value));
+            }
+        }
+    }
+
+    let mut buffer = Vec::new();
+    match read_message::<F>(&mut reader, &mut buffer, usize::MAX)? {
+        false => Ok(None),
+        true => match deserialize::<F, T>(&buffer) {
+            Ok((value, _)) => Ok(Some(value)),
+            Err(err) => Err(invalid_data(err)),
+        },
+    }
+}
+
+/// Reads whole message into the buffer.
+/// Returns `false` if the reader is exhausted before the message starts.
+fn read_message<F>(
+    reader: &mut impl Read,
+    buffer: &mut Vec<u8>,
+    max_size: usize,
+) -> io::Result<bool>
+where
+    F: Formula + ?Sized,
+{
+    buffer.clear();
+
+    if reference_size::<F>() == 0 {
+        // Value of zero-sized formula occupies no bytes.
+        return Ok(true);
+    }
+
+    buffer.resize(SIZE_STACK, 0);
+
+    let mut filled = 0;
+    while filled < SIZE_STACK {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let address = read_address::<F>(buffer)?;
+    if address > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message size exceeds the limit",
+        ));
+    }
+
+    buffer.resize(address, 0);
+    reader.read_exact(&mut buffer[SIZE_STACK..])?;
+    Ok(true)
+}
+
+/// Reads size of the message from its header.
+fn read_address<F>(input: &[u8]) -> io::Result<usize>
+where
+    F: Formula + ?Sized,
+{
+    let mut bytes = [0u8; SIZE_STACK];
+    bytes.copy_from_slice(&input[..SIZE_STACK]);
+
+    let address: usize = match FixedUsize::from_le_bytes(bytes) {
+        Ok(address) => address.into(),
+        Err(err) => return Err(invalid_data(err)),
+    };
+
+    if address < reference_size::<F>() {
+        return Err(invalid_data(DeserializeError::WrongAddress));
+    }
+    Ok(address)
+}
+
+#[inline(always)]
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<alloc::boxed::Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
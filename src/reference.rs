@@ -11,6 +11,9 @@ use crate::{
     serialize::{field_size_hint, write_ref, Serialize, Sizes},
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 /// `Ref` is a formula wrapper.
 /// It serializes the value in dynamic payload
 /// and stores relative offset and the ref metadata.
@@ -29,6 +32,12 @@ where
     const MAX_STACK_SIZE: Option<usize> = Some(reference_size::<F>());
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = matches!(F::MAX_STACK_SIZE, Some(0));
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Ref(F::descriptor().into())
+    }
 }
 
 impl<F, T> Serialize<Ref<F>> for T
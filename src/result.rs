@@ -0,0 +1,191 @@
+use core::mem::size_of;
+
+use crate::{
+    buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
+    formula::{max_size, sum_size, BareFormula, Formula},
+    serialize::{
+        field_size_hint, formula_fast_sizes, write_exact_size_field, write_field, Serialize, Sizes,
+    },
+    validate::Validated,
+};
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec};
+
+#[cfg(feature = "alloc")]
+use crate::descriptor::{Descriptor, Field, Variant};
+
+const OK: u32 = 0;
+const ERR: u32 = 1;
+
+/// `Result<T, E>` is serialized the same way as derived enum
+/// with `Ok` and `Err` variants with single field each.
+impl<T, E> Formula for Result<T, E>
+where
+    T: Formula,
+    E: Formula,
+{
+    const MAX_STACK_SIZE: Option<usize> = sum_size(
+        Some(size_of::<u32>()),
+        max_size(T::MAX_STACK_SIZE, E::MAX_STACK_SIZE),
+    );
+
+    const EXACT_SIZE: bool = T::EXACT_SIZE
+        && E::EXACT_SIZE
+        && matches!((T::MAX_STACK_SIZE, E::MAX_STACK_SIZE), (Some(t), Some(e)) if t == e);
+
+    const HEAPLESS: bool = T::HEAPLESS && E::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Enum {
+            name: "Result".into(),
+            variants: vec![
+                Variant::new(OK, "Ok", vec![Field::new("0", T::descriptor())]),
+                Variant::new(ERR, "Err", vec![Field::new("0", E::descriptor())]),
+            ],
+            non_exhaustive: false,
+            discriminant: Box::new(Descriptor::U32),
+        }
+    }
+}
+
+impl<T, E> BareFormula for Result<T, E>
+where
+    T: Formula,
+    E: Formula,
+{
+}
+
+impl<FT, FE, T, E> Serialize<Result<FT, FE>> for Result<T, E>
+where
+    FT: Formula,
+    FE: Formula,
+    T: Serialize<FT>,
+    E: Serialize<FE>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, mut buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        match self {
+            Ok(value) => {
+                write_exact_size_field::<u32, u32, _>(OK, sizes, buffer.reborrow())?;
+                write_field::<FT, T, _>(value, sizes, buffer, true)
+            }
+            Err(err) => {
+                write_exact_size_field::<u32, u32, _>(ERR, sizes, buffer.reborrow())?;
+                write_field::<FE, E, _>(err, sizes, buffer, true)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        if let Some(sizes) = formula_fast_sizes::<Result<FT, FE>>() {
+            return Some(sizes);
+        }
+        let mut sizes = match self {
+            Ok(value) => field_size_hint::<FT>(value, true)?,
+            Err(err) => field_size_hint::<FE>(err, true)?,
+        };
+        sizes.add_stack(size_of::<u32>());
+        Some(sizes)
+    }
+}
+
+impl<'ser, FT, FE, T, E> Serialize<Result<FT, FE>> for &'ser Result<T, E>
+where
+    FT: Formula,
+    FE: Formula,
+    &'ser T: Serialize<FT>,
+    &'ser E: Serialize<FE>,
+{
+    #[inline(always)]
+    fn serialize<B>(self, sizes: &mut Sizes, mut buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        match self {
+            Ok(value) => {
+                write_exact_size_field::<u32, u32, _>(OK, sizes, buffer.reborrow())?;
+                write_field::<FT, &T, _>(value, sizes, buffer, true)
+            }
+            Err(err) => {
+                write_exact_size_field::<u32, u32, _>(ERR, sizes, buffer.reborrow())?;
+                write_field::<FE, &E, _>(err, sizes, buffer, true)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        if let Some(sizes) = formula_fast_sizes::<Result<FT, FE>>() {
+            return Some(sizes);
+        }
+        let mut sizes = match *self {
+            Ok(value) => field_size_hint::<FT>(&value, true)?,
+            Err(err) => field_size_hint::<FE>(&err, true)?,
+        };
+        sizes.add_stack(size_of::<u32>());
+        Some(sizes)
+    }
+}
+
+impl<'de, FT, FE, T, E> Deserialize<'de, Result<FT, FE>> for Result<T, E>
+where
+    FT: Formula,
+    FE: Formula,
+    T: Deserialize<'de, FT>,
+    E: Deserialize<'de, FE>,
+{
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let value = match de.read_value::<u32, u32>(false)? {
+            OK => Ok(de.read_value::<FT, T>(true)?),
+            ERR => Err(de.read_value::<FE, E>(true)?),
+            invalid => return Err(DeserializeError::WrongVariant(invalid)),
+        };
+        de.finish()?;
+        Ok(value)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, mut de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        match (de.read_value::<u32, u32>(false)?, self) {
+            (OK, Ok(value)) => de.read_in_place::<FT, T>(value, true)?,
+            (ERR, Err(err)) => de.read_in_place::<FE, E>(err, true)?,
+            (OK, me) => *me = Ok(de.read_value::<FT, T>(true)?),
+            (ERR, me) => *me = Err(de.read_value::<FE, E>(true)?),
+            (invalid, _) => return Err(DeserializeError::WrongVariant(invalid)),
+        }
+        de.finish()
+    }
+}
+
+impl<'de, FT, FE> Deserialize<'de, Result<FT, FE>> for Validated
+where
+    FT: Formula,
+    FE: Formula,
+    Validated: Deserialize<'de, FT> + Deserialize<'de, FE>,
+{
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        match de.read_value::<u32, u32>(false)? {
+            OK => de.read_value::<FT, Validated>(true)?,
+            ERR => de.read_value::<FE, Validated>(true)?,
+            invalid => return Err(DeserializeError::WrongVariant(invalid)),
+        };
+        de.finish()?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, Result<FT, FE>>>::deserialize(de)?;
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
-use core::{any::type_name, fmt, marker::PhantomData, ops};
+use core::{any::type_name, fmt, hash::Hasher, marker::PhantomData, ops};
 
 use crate::{
-    buffer::{Buffer, BufferExhausted, CheckedFixedBuffer, DryBuffer, MaybeFixedBuffer},
+    buffer::{
+        Buffer, BufferExhausted, CheckedFixedBuffer, DryBuffer, HashBuffer, MaybeFixedBuffer,
+    },
     formula::{reference_size, unwrap_size, BareFormula, Formula},
     size::{FixedUsize, SIZE_STACK},
 };
@@ -9,6 +11,9 @@ use crate::{
 #[cfg(feature = "alloc")]
 use crate::buffer::VecBuffer;
 
+#[cfg(feature = "std")]
+use crate::buffer::WriteBuffer;
+
 /// Heap and stack sizes.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Sizes {
@@ -423,8 +428,256 @@ where
     }
 }
 
+/// Serialize value into [`std::io::Write`].
+/// Returns the number of bytes written.
+///
+/// Heap of the value is streamed to the writer as it is serialized,
+/// only stack of the values being serialized is kept in memory.
+/// Data is written with many small writes, so buffered writer
+/// like [`std::io::BufWriter`] should be used for slow writers.
+///
+/// Root reference is written first, so sizes of the value must be known
+/// in advance from its size hint.
+/// Use [`serialize_into_writer_cloned`] for values without size hint.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+/// Returns error of [`InvalidInput`](std::io::ErrorKind::InvalidInput) kind
+/// if value does not provide size hint
+/// and of [`InvalidData`](std::io::ErrorKind::InvalidData) kind
+/// if size hint provided by the value is incorrect.
+#[cfg(feature = "std")]
+pub fn serialize_into_writer<F, T, W>(value: T, writer: W) -> std::io::Result<usize>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+    W: std::io::Write,
+{
+    match <T as Serialize<F>>::size_hint(&value) {
+        Some(promised) => write_streaming::<F, T, W>(value, promised, writer),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "value does not provide size hint",
+        )),
+    }
+}
+
+/// Serialize value into [`std::io::Write`].
+/// Returns the number of bytes written.
+///
+/// Same as [`serialize_into_writer`], but values without size hint
+/// are serialized twice, first time only to measure sizes.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+/// Returns error of [`InvalidData`](std::io::ErrorKind::InvalidData) kind
+/// if size hint provided by the value is incorrect.
+#[cfg(feature = "std")]
+pub fn serialize_into_writer_cloned<F, T, W>(value: T, writer: W) -> std::io::Result<usize>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F> + Clone,
+    W: std::io::Write,
+{
+    let promised = match <T as Serialize<F>>::size_hint(&value) {
+        Some(promised) => promised,
+        None => serialized_sizes::<F, T>(value.clone()),
+    };
+    write_streaming::<F, T, W>(value, promised, writer)
+}
+
+#[cfg(feature = "std")]
+fn write_streaming<F, T, W>(value: T, promised: Sizes, mut writer: W) -> std::io::Result<usize>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+    W: std::io::Write,
+{
+    let mut stack = alloc::vec::Vec::new();
+    let mut written = 0;
+    let mut error = None;
+    let buffer = WriteBuffer::new(&mut writer, &mut stack, &mut written, &mut error);
+
+    let sizes = serialize_streaming::<F, T, _>(value, promised, buffer)?;
+
+    if let Some(err) = error {
+        return Err(err);
+    }
+
+    if sizes != promised {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "size hint is incorrect",
+        ));
+    }
+
+    Ok(written)
+}
+
+/// Feeds serialized value into [`Hasher`] without writing it anywhere.
+/// Returns the number of bytes fed.
+///
+/// Hasher receives exactly the bytes [`serialize`] would write,
+/// possibly split into many small writes.
+/// Hash matches hash of serialized bytes if hasher
+/// does not depend on how bytes are split, like [`Crc32`](crate::Crc32).
+/// With [canonical encoding](crate::verify_canonical) equal values
+/// produce equal hashes.
+///
+/// Heap of the value is fed to the hasher as it is serialized,
+/// `scratch` holds only stack of the values being serialized,
+/// at most [`serialized_size`] bytes.
+/// Nothing is allocated.
+///
+/// Root reference is fed first, so sizes of the value must be known
+/// in advance from its size hint.
+/// Value without size hint is serialized into `scratch` as a whole
+/// and then fed to the hasher.
+/// Use [`serialize_to_hasher_cloned`] to measure such values instead.
+///
+/// # Errors
+///
+/// Returns [`BufferExhausted`] if `scratch` is too small.
+/// Hasher may have received part of the value in this case.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// type MyFormula = (u32, [As<str>]);
+/// let value = (1u32, ["alpha", "beta"]);
+///
+/// let mut buffer = [0u8; 128];
+/// let size = serialize::<MyFormula, _>(value, &mut buffer).unwrap();
+///
+/// let mut crc = Crc32::new();
+/// let mut scratch = [0u8; 64];
+/// let fed = serialize_to_hasher::<MyFormula, _, _>(value, &mut crc, &mut scratch).unwrap();
+/// assert_eq!(fed, size);
+///
+/// let mut expected = Crc32::new();
+/// expected.update(&buffer[..size]);
+/// assert_eq!(crc.checksum(), expected.checksum());
+/// ```
+pub fn serialize_to_hasher<F, T, H>(
+    value: T,
+    hasher: &mut H,
+    scratch: &mut [u8],
+) -> Result<usize, BufferExhausted>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+    H: Hasher + ?Sized,
+{
+    match <T as Serialize<F>>::size_hint(&value) {
+        Some(promised) => hash_streaming::<F, T, H>(value, promised, hasher, scratch),
+        None => {
+            let size = serialize::<F, T>(value, scratch)?;
+            hasher.write(&scratch[..size]);
+            Ok(size)
+        }
+    }
+}
+
+/// Feeds serialized value into [`Hasher`] without writing it anywhere.
+/// Returns the number of bytes fed.
+///
+/// Same as [`serialize_to_hasher`], but values without size hint
+/// are serialized twice, first time only to measure sizes,
+/// so `scratch` needs to fit only stack of the values being serialized.
+///
+/// # Errors
+///
+/// Returns [`BufferExhausted`] if `scratch` is too small.
+/// Hasher may have received part of the value in this case.
+pub fn serialize_to_hasher_cloned<F, T, H>(
+    value: T,
+    hasher: &mut H,
+    scratch: &mut [u8],
+) -> Result<usize, BufferExhausted>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F> + Clone,
+    H: Hasher + ?Sized,
+{
+    let promised = match <T as Serialize<F>>::size_hint(&value) {
+        Some(promised) => promised,
+        None => serialized_sizes::<F, T>(value.clone()),
+    };
+    hash_streaming::<F, T, H>(value, promised, hasher, scratch)
+}
+
+pub(crate) fn hash_streaming<F, T, H>(
+    value: T,
+    promised: Sizes,
+    hasher: &mut H,
+    scratch: &mut [u8],
+) -> Result<usize, BufferExhausted>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+    H: Hasher + ?Sized,
+{
+    let mut written = 0;
+    let buffer = HashBuffer::new(hasher, scratch, &mut written);
+    serialize_streaming::<F, T, _>(value, promised, buffer)?;
+    Ok(written)
+}
+
+/// Serializes value into buffer that can only append bytes,
+/// like [`WriteBuffer`] and [`HashBuffer`].
+/// Root reference is written first from promised sizes,
+/// then heap is appended as value is serialized.
+/// Returns actual sizes of the value.
+#[inline(always)]
+pub(crate) fn serialize_streaming<F, T, B>(
+    value: T,
+    promised: Sizes,
+    mut buffer: B,
+) -> Result<Sizes, B::Error>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+    B: Buffer,
+{
+    let reference_size = reference_size::<F>();
+    let total = reference_size + promised.heap + promised.stack;
+
+    write_reference::<F, _>(promised.stack, total, 0, 0, buffer.reborrow())?;
+    buffer.move_to_heap(0, reference_size, reference_size);
+
+    let mut sizes = Sizes {
+        heap: reference_size,
+        stack: 0,
+    };
+    <T as Serialize<F>>::serialize(value, &mut sizes, buffer.reborrow())?;
+    buffer.move_to_heap(sizes.heap, sizes.stack, sizes.stack);
+
+    debug_assert_eq!(
+        reference_size + promised.heap,
+        sizes.heap,
+        "<{} as Serialize<{}>>::size_hint() result is incorrect",
+        type_name::<T>(),
+        type_name::<F>()
+    );
+    debug_assert_eq!(
+        promised.stack,
+        sizes.stack,
+        "<{} as Serialize<{}>>::size_hint() result is incorrect",
+        type_name::<T>(),
+        type_name::<F>()
+    );
+
+    Ok(Sizes {
+        heap: sizes.heap - reference_size,
+        stack: sizes.stack,
+    })
+}
+
 #[inline(always)]
-fn serialized_sizes<F, T>(value: T) -> Sizes
+pub(crate) fn serialized_sizes<F, T>(value: T) -> Sizes
 where
     F: Formula + ?Sized,
     T: Serialize<F>,
@@ -614,7 +867,11 @@ where
 }
 
 #[cold]
-fn write_ref_slow<F, T, B>(value: T, sizes: &mut Sizes, mut buffer: B) -> Result<usize, B::Error>
+pub(crate) fn write_ref_slow<F, T, B>(
+    value: T,
+    sizes: &mut Sizes,
+    mut buffer: B,
+) -> Result<usize, B::Error>
 where
     F: Formula + ?Sized,
     T: Serialize<F>,
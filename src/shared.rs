@@ -0,0 +1,395 @@
+//!
+//! This module provides formula for shared values that are written once
+//! no matter how many times they occur in the serialized value.
+//!
+
+use core::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    marker::PhantomData,
+};
+
+use alloc::{boxed::Box, collections::BTreeMap, rc::Rc, sync::Arc, vec::Vec};
+
+use crate::{
+    buffer::{Buffer, BufferExhausted, CheckedFixedBuffer, VecBuffer},
+    deserialize::{deserialize_with, Deserialize, DeserializeError, Deserializer},
+    formula::{reference_size, BareFormula, Formula},
+    reference::Ref,
+    serialize::{serialize_into, write_ref, write_ref_slow, write_reference, Serialize, Sizes},
+    validate::Validated,
+};
+
+use crate::descriptor::Descriptor;
+
+/// `Shared` is a formula wrapper for `Rc` and `Arc` pointers.
+/// It is serialized the same way as `Ref<F>`.
+///
+/// When serialized with [`serialize_shared`] or [`serialize_shared_to_vec`]
+/// pointee of every pointer is written once,
+/// and all pointers to it refer to the same address.
+/// Other serialization functions write pointee for each occurrence.
+///
+/// When deserialized with `deserialize_shared`, all occurrences of the same
+/// pointee are deserialized into the same `Rc` or `Arc` instance.
+/// Other deserialization functions create new instance for each occurrence.
+///
+/// Pointee is deduplicated only if pointer is serialized by reference
+/// and has other strong references.
+/// Borrowed pointers outlive serialization, so address of the pointee
+/// can't be reused by another value while deduplication table exists.
+/// Pointers serialized by value are always written as `Ref<F>`.
+/// `Shared` never provides size hint, so values containing it
+/// are serialized without fast path.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use std::sync::Arc;
+/// use alkahest::*;
+///
+/// let name: Arc<str> = Arc::from("alkahest");
+/// let names = vec![name.clone(), name.clone(), name];
+///
+/// let mut plain = Vec::new();
+/// serialize_to_vec::<[Shared<str>], _>(&names, &mut plain);
+///
+/// let mut shared = Vec::new();
+/// serialize_shared_to_vec::<[Shared<str>], _>(&names, &mut shared);
+/// assert!(shared.len() < plain.len());
+///
+/// let (names, _) = deserialize_shared::<[Shared<str>], Vec<Arc<str>>>(&shared).unwrap();
+/// assert_eq!(&*names[0], "alkahest");
+/// assert!(Arc::ptr_eq(&names[0], &names[2]));
+/// # }
+/// ```
+pub struct Shared<F: ?Sized> {
+    marker: PhantomData<fn(&F) -> &F>,
+}
+
+impl<F> Formula for Shared<F>
+where
+    F: BareFormula + ?Sized,
+{
+    const MAX_STACK_SIZE: Option<usize> = <Ref<F> as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <Ref<F> as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <Ref<F> as Formula>::HEAPLESS;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<F> as Formula>::descriptor()
+    }
+}
+
+/// Addresses of shared values written to the buffer,
+/// keyed by pointee address and formula.
+type SharedAddresses = BTreeMap<(usize, TypeId), (usize, usize)>;
+
+/// Buffer adapter that deduplicates shared values.
+struct SharedBuffer<'a, B> {
+    buffer: B,
+    addresses: &'a mut SharedAddresses,
+}
+
+impl<'a, B> Buffer for SharedBuffer<'a, B>
+where
+    B: Buffer,
+{
+    type Error = B::Error;
+    type Reborrow<'b>
+        = SharedBuffer<'b, B::Reborrow<'b>>
+    where
+        Self: 'b;
+
+    #[inline(always)]
+    fn reborrow(&mut self) -> Self::Reborrow<'_> {
+        SharedBuffer {
+            buffer: self.buffer.reborrow(),
+            addresses: self.addresses,
+        }
+    }
+
+    #[inline(always)]
+    fn write_stack(&mut self, heap: usize, stack: usize, bytes: &[u8]) -> Result<(), B::Error> {
+        self.buffer.write_stack(heap, stack, bytes)
+    }
+
+    #[inline(always)]
+    fn pad_stack(&mut self, heap: usize, stack: usize, len: usize) -> Result<(), B::Error> {
+        self.buffer.pad_stack(heap, stack, len)
+    }
+
+    #[inline(always)]
+    fn move_to_heap(&mut self, heap: usize, stack: usize, len: usize) {
+        self.buffer.move_to_heap(heap, stack, len);
+    }
+
+    #[inline(always)]
+    fn reserve_heap(
+        &mut self,
+        heap: usize,
+        stack: usize,
+        len: usize,
+    ) -> Result<&mut [u8], B::Error> {
+        self.buffer.reserve_heap(heap, stack, len)
+    }
+
+    #[inline(always)]
+    fn find_shared(&mut self, key: (usize, TypeId)) -> Option<(usize, usize)> {
+        self.addresses.get(&key).copied()
+    }
+
+    #[inline(always)]
+    fn add_shared(&mut self, key: (usize, TypeId), stack: usize, address: usize) {
+        self.addresses.insert(key, (stack, address));
+    }
+}
+
+/// Serialize value into bytes slice, writing pointees of `Shared` formulas once.
+/// Returns the number of bytes written.
+///
+/// # Errors
+///
+/// Returns error if buffer is too small to fit serialized data.
+#[inline]
+pub fn serialize_shared<F, T>(value: T, output: &mut [u8]) -> Result<usize, BufferExhausted>
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+{
+    let mut addresses = SharedAddresses::new();
+    let buffer = SharedBuffer {
+        buffer: CheckedFixedBuffer::new(output),
+        addresses: &mut addresses,
+    };
+    serialize_into::<F, T, _>(value, buffer)
+}
+
+/// Serialize value into byte vector, writing pointees of `Shared` formulas once.
+/// Returns the number of bytes written.
+///
+/// Grows the vector if needed.
+/// Infallible except for allocation errors.
+#[inline]
+pub fn serialize_shared_to_vec<F, T>(value: T, output: &mut Vec<u8>) -> usize
+where
+    F: Formula + ?Sized,
+    T: Serialize<F>,
+{
+    let mut addresses = SharedAddresses::new();
+    let buffer = SharedBuffer {
+        buffer: VecBuffer::new(output),
+        addresses: &mut addresses,
+    };
+    match serialize_into::<F, T, _>(value, buffer) {
+        Ok(size) => size,
+        Err(never) => match never {},
+    }
+}
+
+/// Writes pointee once per buffer and reference to it for every occurrence.
+///
+/// Pointee must stay alive until the end of serialization,
+/// otherwise its address may be reused by different value.
+#[inline(always)]
+fn write_shared<F, T, B>(
+    pointee: usize,
+    value: T,
+    sizes: &mut Sizes,
+    mut buffer: B,
+) -> Result<(), B::Error>
+where
+    F: Formula + ?Sized + 'static,
+    T: Serialize<F>,
+    B: Buffer,
+{
+    let key = (pointee, TypeId::of::<F>());
+    let (stack, address) = match buffer.find_shared(key) {
+        Some(found) => found,
+        None => {
+            let stack = write_ref_slow::<F, T, _>(value, sizes, buffer.reborrow())?;
+            buffer.add_shared(key, stack, sizes.heap);
+            (stack, sizes.heap)
+        }
+    };
+    write_reference::<F, _>(stack, address, sizes.heap, sizes.stack, buffer)?;
+    sizes.stack += reference_size::<F>();
+    Ok(())
+}
+
+/// Pointers deserialized by `deserialize_shared` call,
+/// keyed by end of their stack bytes in the input, stack size and pointer type.
+pub(crate) type SharedValues = Rc<RefCell<BTreeMap<(usize, usize, TypeId), Box<dyn Any>>>>;
+
+/// Deserialize value from bytes slice,
+/// restoring sharing of `Rc` and `Arc` pointers with `Shared` formulas.
+/// Returns deserialized value and number of bytes consumed.
+///
+/// Works with data serialized by any serialization function,
+/// but only pointees written once by [`serialize_shared`]
+/// or [`serialize_shared_to_vec`] are shared.
+///
+/// # Errors
+///
+/// Returns `DeserializeError` if deserialization fails.
+#[inline]
+pub fn deserialize_shared<'de, F, T>(input: &'de [u8]) -> Result<(T, usize), DeserializeError>
+where
+    F: Formula + ?Sized,
+    T: Deserialize<'de, F>,
+{
+    deserialize_with::<F, T>(input, |de| de.with_shared(SharedValues::default()))
+}
+
+/// Deserializes pointer with `make`, or reuses pointer deserialized
+/// from the same location by the same `deserialize_shared` call.
+#[inline(always)]
+fn read_shared<'de, P>(
+    de: Deserializer<'de>,
+    make: impl FnOnce(Deserializer<'de>) -> Result<P, DeserializeError>,
+) -> Result<P, DeserializeError>
+where
+    P: Clone + 'static,
+{
+    if let Some(ptr) = de.find_shared::<P>() {
+        return Ok(ptr);
+    }
+
+    let shared = de.clone();
+    let ptr = make(de)?;
+    shared.add_shared(&ptr);
+    Ok(ptr)
+}
+
+/// Shared value is validated at every reference to it.
+impl<'de, F> Deserialize<'de, Shared<F>> for Validated
+where
+    F: BareFormula + ?Sized,
+    Validated: Deserialize<'de, F>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Validated as Deserialize<'de, Ref<F>>>::deserialize(de)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Validated as Deserialize<'de, Ref<F>>>::deserialize_in_place(self, de)
+    }
+}
+
+macro_rules! impl_shared_formula {
+    ($ptr:ident) => {
+        impl<'ser, F, T> Serialize<Shared<F>> for &'ser $ptr<T>
+        where
+            F: BareFormula + ?Sized + 'static,
+            T: ?Sized,
+            &'ser T: Serialize<F>,
+        {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                if $ptr::strong_count(self) > 1 {
+                    let pointee = $ptr::as_ptr(self).cast::<u8>() as usize;
+                    write_shared::<F, &'ser T, _>(pointee, self, sizes, buffer)
+                } else {
+                    write_ref::<F, &'ser T, _>(self, sizes, buffer)
+                }
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                None
+            }
+        }
+
+        impl<F, T> Serialize<Shared<F>> for $ptr<T>
+        where
+            F: BareFormula + ?Sized + 'static,
+            T: ?Sized,
+            for<'ser> &'ser T: Serialize<F>,
+        {
+            #[inline(always)]
+            fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+            where
+                B: Buffer,
+            {
+                // Pointer may be dropped before serialization ends,
+                // so its address is not recorded.
+                write_ref::<F, &T, _>(&*self, sizes, buffer)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> Option<Sizes> {
+                None
+            }
+        }
+
+        impl<'de, F, T> Deserialize<'de, Shared<F>> for $ptr<T>
+        where
+            F: BareFormula + ?Sized,
+            T: Deserialize<'de, F> + 'static,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let de = de.deref::<F>()?;
+                read_shared(de, |de| {
+                    Ok($ptr::new(<T as Deserialize<F>>::deserialize(de)?))
+                })
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                *self = <Self as Deserialize<'de, Shared<F>>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+
+        impl<'de> Deserialize<'de, Shared<str>> for $ptr<str> {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let de = de.deref::<str>()?;
+                read_shared(de, <Self as Deserialize<str>>::deserialize)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                *self = <Self as Deserialize<'de, Shared<str>>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+
+        impl<'de, F, T> Deserialize<'de, Shared<[F]>> for $ptr<[T]>
+        where
+            F: Formula,
+            T: Deserialize<'de, F> + 'static,
+        {
+            #[inline(always)]
+            fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                let de = de.deref::<[F]>()?;
+                read_shared(de, <Self as Deserialize<[F]>>::deserialize)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(
+                &mut self,
+                de: Deserializer<'de>,
+            ) -> Result<(), DeserializeError> {
+                *self = <Self as Deserialize<'de, Shared<[F]>>>::deserialize(de)?;
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_shared_formula!(Rc);
+impl_shared_formula!(Arc);
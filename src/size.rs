@@ -5,18 +5,35 @@ use crate::{
     deserialize::{Deserialize, DeserializeError, Deserializer},
     formula::{BareFormula, Formula},
     serialize::{Serialize, Sizes},
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
+// Features are unified across the dependency graph,
+// so the widest enabled `fixed*` feature wins.
+// Without any of them `FixedUsize` is 32 bits wide.
+
 /// Type used to represent sizes and offsets in serialized data.
-#[cfg(feature = "fixed8")]
+#[cfg(all(
+    feature = "fixed8",
+    not(any(feature = "fixed16", feature = "fixed32", feature = "fixed64"))
+))]
 pub type FixedUsizeType = u8;
 
 /// Type used to represent sizes and offsets in serialized data.
-#[cfg(feature = "fixed16")]
+#[cfg(all(
+    feature = "fixed16",
+    not(any(feature = "fixed32", feature = "fixed64"))
+))]
 pub type FixedUsizeType = u16;
 
 /// Type used to represent sizes and offsets in serialized data.
-#[cfg(feature = "fixed32")]
+#[cfg(all(
+    not(feature = "fixed64"),
+    any(feature = "fixed32", not(any(feature = "fixed8", feature = "fixed16")))
+))]
 pub type FixedUsizeType = u32;
 
 /// Type used to represent sizes and offsets in serialized data.
@@ -24,15 +41,24 @@ pub type FixedUsizeType = u32;
 pub type FixedUsizeType = u64;
 
 /// Type used to represent sizes and offsets in serialized data.
-#[cfg(feature = "fixed8")]
+#[cfg(all(
+    feature = "fixed8",
+    not(any(feature = "fixed16", feature = "fixed32", feature = "fixed64"))
+))]
 pub type FixedIsizeType = i8;
 
 /// Type used to represent sizes and offsets in serialized data.
-#[cfg(feature = "fixed16")]
+#[cfg(all(
+    feature = "fixed16",
+    not(any(feature = "fixed32", feature = "fixed64"))
+))]
 pub type FixedIsizeType = i16;
 
 /// Type used to represent sizes and offsets in serialized data.
-#[cfg(feature = "fixed32")]
+#[cfg(all(
+    not(feature = "fixed64"),
+    any(feature = "fixed32", not(any(feature = "fixed8", feature = "fixed16")))
+))]
 pub type FixedIsizeType = i32;
 
 /// Type used to represent sizes and offsets in serialized data.
@@ -110,6 +136,12 @@ impl Formula for FixedUsize {
     const MAX_STACK_SIZE: Option<usize> = Some(size_of::<FixedUsizeType>());
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <FixedUsizeType as Formula>::descriptor()
+    }
 }
 
 impl BareFormula for FixedUsize {}
@@ -221,6 +253,23 @@ impl Deserialize<'_, FixedUsize> for usize {
     }
 }
 
+impl Deserialize<'_, FixedUsize> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer) -> Result<Self, DeserializeError> {
+        let value = <FixedUsizeType as Deserialize<FixedUsizeType>>::deserialize(de)?;
+        if usize::try_from(value).is_err() {
+            return Err(DeserializeError::InvalidUsize(value));
+        }
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<FixedUsize>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
 /// Type used to represent sizes and offsets in serialized data.
 /// This places limitation on sequence sizes which practically is never hit.
 /// `usize` itself is not portable and cannot be written into alkahest package.
@@ -292,6 +341,12 @@ impl Formula for FixedIsize {
     const MAX_STACK_SIZE: Option<usize> = Some(size_of::<FixedIsizeType>());
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <FixedIsizeType as Formula>::descriptor()
+    }
 }
 
 impl BareFormula for FixedIsize {}
@@ -403,5 +458,22 @@ impl Deserialize<'_, FixedIsize> for isize {
     }
 }
 
+impl Deserialize<'_, FixedIsize> for Validated {
+    #[inline(always)]
+    fn deserialize(de: Deserializer) -> Result<Self, DeserializeError> {
+        let value = <FixedIsizeType as Deserialize<FixedIsizeType>>::deserialize(de)?;
+        if isize::try_from(value).is_err() {
+            return Err(DeserializeError::InvalidIsize(value));
+        }
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<FixedIsize>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
 /// Stack space occupied by sizes and addresses.
 pub const SIZE_STACK: usize = size_of::<FixedUsize>();
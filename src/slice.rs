@@ -1,11 +1,16 @@
 use crate::{
     buffer::Buffer,
+    deserialize::{Deserialize, DeserializeError, Deserializer},
     formula::{BareFormula, Formula},
     iter::owned_iter_fast_sizes,
     serialize::{write_slice, Serialize, Sizes},
     size::SIZE_STACK,
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 impl<F> Formula for [F]
 where
     F: Formula,
@@ -16,6 +21,12 @@ where
     };
     const EXACT_SIZE: bool = true; // All elements are padded.
     const HEAPLESS: bool = F::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Slice(F::descriptor().into())
+    }
 }
 
 impl<F> BareFormula for [F] where F: Formula {}
@@ -39,3 +50,32 @@ where
         owned_iter_fast_sizes::<F, _, _>(self.iter())
     }
 }
+
+impl<'de, F> Deserialize<'de, [F]> for Validated
+where
+    F: Formula,
+    Validated: Deserialize<'de, F>,
+{
+    #[inline]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let mut iter = de.into_unsized_iter::<F, Validated>();
+
+        // Elements of zero-sized formula are all read from the same empty stack,
+        // so checking one of them is enough.
+        if F::MAX_STACK_SIZE == Some(0) {
+            iter.next().transpose()?;
+        } else {
+            for element in iter.by_ref() {
+                element?;
+            }
+        }
+        iter.finish()?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, [F]>>::deserialize(de)?;
+        Ok(())
+    }
+}
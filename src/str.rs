@@ -3,12 +3,22 @@ use crate::{
     deserialize::{Deserialize, DeserializeError, Deserializer},
     formula::{BareFormula, Formula},
     serialize::{write_bytes, Serialize, Sizes},
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 impl Formula for str {
     const MAX_STACK_SIZE: Option<usize> = None;
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Str
+    }
 }
 
 impl BareFormula for str {}
@@ -56,3 +66,17 @@ impl<'de, 'fe: 'de> Deserialize<'fe, str> for &'de str {
         }
     }
 }
+
+impl Deserialize<'_, str> for Validated {
+    #[inline(always)]
+    fn deserialize(deserializer: Deserializer) -> Result<Self, DeserializeError> {
+        <&str as Deserialize<str>>::deserialize(deserializer)?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, deserializer: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<str>>::deserialize(deserializer)?;
+        Ok(())
+    }
+}
@@ -8,10 +8,17 @@ use crate::{
     serialize::{write_bytes, write_ref, Serialize, Sizes},
 };
 
+use crate::descriptor::Descriptor;
+
 impl Formula for String {
     const MAX_STACK_SIZE: Option<usize> = <Ref<str> as Formula>::MAX_STACK_SIZE;
     const EXACT_SIZE: bool = <Ref<str> as Formula>::EXACT_SIZE;
     const HEAPLESS: bool = <Ref<str> as Formula>::HEAPLESS;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<str> as Formula>::descriptor()
+    }
 }
 
 impl<T> Serialize<String> for T
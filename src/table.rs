@@ -0,0 +1,809 @@
+//!
+//! This module provides hash table formula with perfect hashing.
+//!
+
+use core::{borrow::Borrow, hash::Hasher, marker::PhantomData};
+
+use crate::{
+    buffer::BufferExhausted,
+    deserialize::{Deserialize, DeserializeError, Deserializer, SizedDeIter},
+    formula::{reference_size, unwrap_size, BareFormula, Formula},
+    map::MapKey,
+    reference::Ref,
+    serialize::{serialize_to_hasher, Serialize},
+    validate::Validated,
+};
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+};
+
+#[cfg(feature = "alloc")]
+use crate::{
+    buffer::Buffer,
+    descriptor::Descriptor,
+    iter::{deserialize_extend_iter, SerIter},
+    serialize::{serialize_to_vec, write_ref, Sizes},
+};
+
+/// Layout of the hash table behind the reference.
+/// Seed of the hash function, maximum number of extra probes,
+/// displacements of the buckets and entries placed into their slots.
+type TableRepr<K, V> = (u64, u64, Ref<[(u32, u32)]>, [(K, V)]);
+
+/// Formula for read-only hash tables with keys of formula `K`
+/// and values of formula `V`.
+///
+/// Minimal perfect hash function over the keys is built at serialization time.
+/// Table stores parameters of the function along with entries
+/// placed into slots assigned to their keys.
+/// [`LazyHashTable`] finds any key with a single key comparison,
+/// without deserializing other entries and without allocations.
+///
+/// Can be serialized from `HashMap` and `BTreeMap`.
+/// Can be deserialized into `HashMap`, `BTreeMap` and [`LazyHashTable`].
+///
+/// Keys are hashed by their serialization with formula `K`,
+/// so hashes do not depend on platform, toolchain or [`Hash`](core::hash::Hash)
+/// implementations.
+/// Hash is 64-bit FNV-1a over serialized bytes of the key,
+/// finalized with SplitMix64, and seeded by xoring the seed
+/// finalized with SplitMix64 into it.
+/// Looked up key must serialize to the same bytes as the serialized one,
+/// which holds for types related by [`Borrow`], e.g. `String` and `str`.
+/// Keys that serialize into more than 256 bytes of stack
+/// can be looked up only with "alloc" feature.
+///
+/// Building the table takes expected linear time.
+/// If keys cannot be separated with bounded effort,
+/// e.g. when different keys serialize into the same bytes,
+/// table falls back to linear probing.
+/// Use [`Map`](crate::Map) when tables are built often
+/// and logarithmic lookups are acceptable.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// # #[cfg(feature = "std")] {
+/// # use std::collections::HashMap;
+/// let items = HashMap::from([(1001u32, 15u16), (1002, 40), (2001, 7)]);
+///
+/// let mut buffer = [0u8; 1024];
+/// serialize::<HashTable<u32, u16>, _>(&items, &mut buffer).unwrap();
+///
+/// let (table, _) = deserialize::<HashTable<u32, u16>, LazyHashTable<u32, u16>>(&buffer).unwrap();
+/// assert_eq!(table.len(), 3);
+/// assert_eq!(table.get::<u32, u16, _>(&1002).unwrap(), Some(40));
+/// assert_eq!(table.get::<u32, u16, _>(&1003).unwrap(), None);
+/// # }
+/// ```
+pub struct HashTable<K, V> {
+    marker: PhantomData<Ref<[(K, V)]>>,
+}
+
+impl<K, V> Formula for HashTable<K, V>
+where
+    K: Formula,
+    V: Formula,
+{
+    const MAX_STACK_SIZE: Option<usize> = <Ref<TableRepr<K, V>> as Formula>::MAX_STACK_SIZE;
+    const EXACT_SIZE: bool = <Ref<TableRepr<K, V>> as Formula>::EXACT_SIZE;
+    const HEAPLESS: bool = <Ref<TableRepr<K, V>> as Formula>::HEAPLESS;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<TableRepr<K, V>> as Formula>::descriptor()
+    }
+}
+
+impl<K, V> BareFormula for HashTable<K, V>
+where
+    K: Formula,
+    V: Formula,
+{
+}
+
+#[cfg(feature = "alloc")]
+impl<FK, FV, K, V> Serialize<HashTable<FK, FV>> for BTreeMap<K, V>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Serialize<FK>,
+    for<'a> &'a K: Serialize<FK>,
+    V: Serialize<FV>,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_table::<FK, FV, _, _, _>(
+            self.into_iter().collect(),
+            |key: &K| key_hash::<FK, &K>(key),
+            sizes,
+            buffer,
+        )
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        None
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'ser, FK, FV, K, V> Serialize<HashTable<FK, FV>> for &'ser BTreeMap<K, V>
+where
+    FK: Formula,
+    FV: Formula,
+    &'ser K: Serialize<FK>,
+    &'ser V: Serialize<FV>,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_table::<FK, FV, _, _, _>(
+            self.iter().collect(),
+            |key: &&K| key_hash::<FK, &K>(key),
+            sizes,
+            buffer,
+        )
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl<FK, FV, K, V, S> Serialize<HashTable<FK, FV>> for HashMap<K, V, S>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Serialize<FK>,
+    for<'a> &'a K: Serialize<FK>,
+    V: Serialize<FV>,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_table::<FK, FV, _, _, _>(
+            self.into_iter().collect(),
+            |key: &K| key_hash::<FK, &K>(key),
+            sizes,
+            buffer,
+        )
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'ser, FK, FV, K, V, S> Serialize<HashTable<FK, FV>> for &'ser HashMap<K, V, S>
+where
+    FK: Formula,
+    FV: Formula,
+    &'ser K: Serialize<FK>,
+    &'ser V: Serialize<FV>,
+{
+    #[inline]
+    fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        write_table::<FK, FV, _, _, _>(
+            self.iter().collect(),
+            |key: &&K| key_hash::<FK, &K>(key),
+            sizes,
+            buffer,
+        )
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> Option<Sizes> {
+        None
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, FK, FV, K, V> Deserialize<'de, HashTable<FK, FV>> for BTreeMap<K, V>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Deserialize<'de, FK> + Ord,
+    V: Deserialize<'de, FV>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let (_, _, _, entries) = split_table::<FK, FV>(de)?;
+        let mut map = BTreeMap::new();
+        deserialize_extend_iter(&mut map, entries.into_unsized_iter::<(FK, FV), _>())?;
+        Ok(map)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        let (_, _, _, entries) = split_table::<FK, FV>(de)?;
+        self.clear();
+        deserialize_extend_iter(self, entries.into_unsized_iter::<(FK, FV), _>())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, FK, FV, K, V, S> Deserialize<'de, HashTable<FK, FV>> for HashMap<K, V, S>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Deserialize<'de, FK> + Eq + Hash,
+    V: Deserialize<'de, FV>,
+    S: BuildHasher + Default,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let (_, _, _, entries) = split_table::<FK, FV>(de)?;
+        let iter = entries.into_unsized_iter::<(FK, FV), _>();
+        let (lower, _) = Iterator::size_hint(&iter);
+        let mut map = HashMap::with_capacity_and_hasher(lower, S::default());
+        deserialize_extend_iter(&mut map, iter)?;
+        Ok(map)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        let (_, _, _, entries) = split_table::<FK, FV>(de)?;
+        self.clear();
+        deserialize_extend_iter(self, entries.into_unsized_iter::<(FK, FV), _>())
+    }
+}
+
+/// Lazy view of a serialized hash table.
+///
+/// Looks up values by key in constant time,
+/// deserializing single key and the found value.
+/// Requires key and value formulas to be sized
+/// to access entries in constant time.
+#[derive(Clone)]
+pub struct LazyHashTable<'de, K, V> {
+    seed: u64,
+    probes: u64,
+    displacements: Deserializer<'de>,
+    entries: Deserializer<'de>,
+    marker: PhantomData<Ref<[(K, V)]>>,
+}
+
+impl<'de, FK, FV> LazyHashTable<'de, FK, FV>
+where
+    FK: Formula,
+    FV: Formula,
+{
+    // Fail compilation for unsized entries.
+    const ENTRY_SIZE: usize = unwrap_size(<(FK, FV)>::MAX_STACK_SIZE);
+
+    /// Returns number of entries in the table.
+    #[must_use]
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        let _ = Self::ENTRY_SIZE;
+        self.entries.sized_len::<(FK, FV)>()
+    }
+
+    /// Returns true if the table has no entries.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Produce iterator over lazy deserialized entries.
+    /// Entries are yielded in order of their slots in the table.
+    #[inline(always)]
+    pub fn iter<K, V>(&self) -> SizedDeIter<'de, (FK, FV), (K, V)>
+    where
+        K: Deserialize<'de, FK>,
+        V: Deserialize<'de, FV>,
+    {
+        let _ = Self::ENTRY_SIZE;
+        self.entries.clone().into_sized_iter()
+    }
+
+    /// Deserializes value associated with the key.
+    /// Returns `Ok(None)` if key is not in the table.
+    ///
+    /// Key is serialized with formula `FK` to find its slot
+    /// and compared with the key in the slot deserialized as `K`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    /// Returns [`DeserializeError::KeyTooLarge`] if key cannot be hashed
+    /// without allocation and "alloc" feature is disabled.
+    pub fn get<'q, K, V, Q>(&self, key: &'q Q) -> Result<Option<V>, DeserializeError>
+    where
+        K: Deserialize<'de, FK> + Borrow<Q>,
+        V: Deserialize<'de, FV>,
+        Q: Eq + ?Sized,
+        &'q Q: Serialize<FK>,
+    {
+        match self.find::<K, Q>(key)? {
+            None => Ok(None),
+            Some(slot) => match self.iter::<K, V>().nth(slot) {
+                Some(Ok((_, value))) => Ok(Some(value)),
+                Some(Err(err)) => Err(err),
+                None => Err(DeserializeError::WrongLength),
+            },
+        }
+    }
+
+    /// Returns true if the key is in the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    /// Returns [`DeserializeError::KeyTooLarge`] if key cannot be hashed
+    /// without allocation and "alloc" feature is disabled.
+    #[inline]
+    pub fn contains_key<'q, K, Q>(&self, key: &'q Q) -> Result<bool, DeserializeError>
+    where
+        K: Deserialize<'de, FK> + Borrow<Q>,
+        Q: Eq + ?Sized,
+        &'q Q: Serialize<FK>,
+    {
+        Ok(self.find::<K, Q>(key)?.is_some())
+    }
+
+    fn find<'q, K, Q>(&self, key: &'q Q) -> Result<Option<usize>, DeserializeError>
+    where
+        K: Deserialize<'de, FK> + Borrow<Q>,
+        Q: Eq + ?Sized,
+        &'q Q: Serialize<FK>,
+    {
+        let len = self.len();
+        let buckets = self.displacements.sized_len::<(u32, u32)>();
+        if len == 0 || buckets == 0 {
+            return Ok(None);
+        }
+
+        let hashes = KeyHashes::new(lookup_key_hash::<FK, Q>(key)?, self.seed);
+        let bucket = hashes.bucket(buckets);
+
+        let mut displacements = self
+            .displacements
+            .clone()
+            .into_sized_iter::<(u32, u32), (u32, u32)>();
+        let displacement = match displacements.nth(bucket) {
+            Some(displacement) => displacement?,
+            None => return Err(DeserializeError::WrongLength),
+        };
+
+        let first = hashes.slot(displacement, len);
+        let probes = usize::try_from(self.probes).map_or(len, |probes| probes.min(len - 1));
+        for probe in 0..=probes {
+            let slot = (first + probe) % len;
+            let mut keys = self
+                .entries
+                .clone()
+                .into_sized_iter::<(FK, FV), MapKey<K>>();
+            let found = match keys.nth(slot) {
+                Some(found) => found?,
+                None => return Err(DeserializeError::WrongLength),
+            };
+
+            if found.0.borrow() == key {
+                return Ok(Some(slot));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'de, FK, FV> Deserialize<'de, HashTable<FK, FV>> for LazyHashTable<'de, FK, FV>
+where
+    FK: Formula,
+    FV: Formula,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let (seed, probes, displacements, entries) = split_table::<FK, FV>(de)?;
+        Ok(LazyHashTable {
+            seed,
+            probes,
+            displacements,
+            entries,
+            marker: PhantomData,
+        })
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        *self = <Self as Deserialize<'de, HashTable<FK, FV>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl<'de, FK, FV> Deserialize<'de, HashTable<FK, FV>> for Validated
+where
+    FK: Formula,
+    FV: Formula,
+    Validated: Deserialize<'de, FK> + Deserialize<'de, FV>,
+{
+    #[inline(always)]
+    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        <Validated as Deserialize<'de, Ref<TableRepr<FK, FV>>>>::deserialize(de)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+        <Self as Deserialize<'de, HashTable<FK, FV>>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Splits serialized table into seed, number of extra probes,
+/// deserializer of displacements and deserializer of entries.
+#[inline(always)]
+fn split_table<'de, FK, FV>(
+    de: Deserializer<'de>,
+) -> Result<(u64, u64, Deserializer<'de>, Deserializer<'de>), DeserializeError>
+where
+    FK: Formula,
+    FV: Formula,
+{
+    let mut de = de.deref::<TableRepr<FK, FV>>()?;
+    let seed = de.read_value::<u64, u64>(false)?;
+    let probes = de.read_value::<u64, u64>(false)?;
+    let displacements = de
+        .sub(reference_size::<[(u32, u32)]>())?
+        .deref::<[(u32, u32)]>()?;
+    Ok((seed, probes, displacements, de))
+}
+
+/// Size of the scratch to serialize looked up key into.
+const KEY_SCRATCH_SIZE: usize = 256;
+
+/// Hashes key serialized with formula `F`.
+#[cfg(feature = "alloc")]
+fn key_hash<F, K>(key: K) -> u64
+where
+    F: Formula,
+    K: Serialize<F>,
+{
+    let mut bytes = Vec::new();
+    let size = serialize_to_vec::<F, K>(key, &mut bytes);
+    let mut hasher = KeyHasher::new();
+    hasher.write(&bytes[..size]);
+    hasher.finish()
+}
+
+/// Hashes looked up key serialized with formula `F`.
+/// Key is serialized into the scratch on stack,
+/// larger keys require allocation.
+fn lookup_key_hash<'q, F, Q>(key: &'q Q) -> Result<u64, DeserializeError>
+where
+    F: Formula,
+    Q: ?Sized,
+    &'q Q: Serialize<F>,
+{
+    let mut hasher = KeyHasher::new();
+    let mut scratch = [0; KEY_SCRATCH_SIZE];
+    match serialize_to_hasher::<F, &Q, _>(key, &mut hasher, &mut scratch) {
+        Ok(_) => Ok(hasher.finish()),
+        #[cfg(feature = "alloc")]
+        Err(BufferExhausted) => Ok(key_hash::<F, &Q>(key)),
+        #[cfg(not(feature = "alloc"))]
+        Err(BufferExhausted) => Err(DeserializeError::KeyTooLarge),
+    }
+}
+
+/// Average number of keys per bucket.
+#[cfg(feature = "alloc")]
+const LAMBDA: usize = 5;
+
+/// Number of seeds to try before falling back to linear probing.
+#[cfg(feature = "alloc")]
+const MAX_SEEDS: u64 = 16;
+
+/// Number of displacements to try for a bucket with many keys
+/// before trying next seed.
+#[cfg(feature = "alloc")]
+const MAX_DISPLACEMENTS: usize = 1 << 12;
+
+/// Builds perfect hash table and writes it as a reference.
+#[cfg(feature = "alloc")]
+fn write_table<FK, FV, K, V, B>(
+    entries: Vec<(K, V)>,
+    key_hash: impl Fn(&K) -> u64,
+    sizes: &mut Sizes,
+    buffer: B,
+) -> Result<(), B::Error>
+where
+    FK: Formula,
+    FV: Formula,
+    K: Serialize<FK>,
+    V: Serialize<FV>,
+    B: Buffer,
+{
+    let key_hashes = entries
+        .iter()
+        .map(|(key, _)| key_hash(key))
+        .collect::<Vec<_>>();
+    let table = build_table(&key_hashes);
+
+    let mut slotted = table.slots.into_iter().zip(entries).collect::<Vec<_>>();
+    slotted.sort_unstable_by_key(|(slot, _)| *slot);
+
+    write_ref::<TableRepr<FK, FV>, _, _>(
+        (
+            table.seed,
+            table.probes,
+            SerIter(table.displacements.into_iter()),
+            SerIter(slotted.into_iter().map(|(_, entry)| entry)),
+        ),
+        sizes,
+        buffer,
+    )
+}
+
+/// Parameters of the built table and slot of each key.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+struct Table {
+    seed: u64,
+    probes: u64,
+    displacements: Vec<(u32, u32)>,
+    slots: Vec<usize>,
+}
+
+/// Finds seed and displacements of the buckets
+/// that map all keys to distinct slots.
+///
+/// Uses "hash and displace" algorithm.
+/// Keys are distributed into buckets and biggest buckets are placed first,
+/// searching for displacement that maps all keys of the bucket to free slots.
+/// Buckets with single key are placed directly into remaining free slots.
+/// Search for each bucket is bounded, next seed is tried when it fails.
+///
+/// Falls back to linear probing when no seed works,
+/// e.g. when keys have same hash, or when table is too large for displacements.
+#[cfg(feature = "alloc")]
+fn build_table(key_hashes: &[u64]) -> Table {
+    let len = key_hashes.len();
+    if len == 0 {
+        return Table {
+            seed: 0,
+            probes: 0,
+            displacements: Vec::new(),
+            slots: Vec::new(),
+        };
+    }
+
+    let buckets_len = len.div_ceil(LAMBDA);
+
+    if u32::try_from(len).is_ok() {
+        for seed in 0..MAX_SEEDS {
+            let hashes = key_hashes
+                .iter()
+                .map(|&hash| KeyHashes::new(hash, seed))
+                .collect::<Vec<_>>();
+
+            let mut buckets = vec![Vec::new(); buckets_len];
+            for (idx, hashes) in hashes.iter().enumerate() {
+                buckets[hashes.bucket(buckets_len)].push(idx);
+            }
+
+            let mut order = (0..buckets_len).collect::<Vec<_>>();
+            order.sort_by_key(|&bucket| core::cmp::Reverse(buckets[bucket].len()));
+
+            if let Some((displacements, slots)) = displace(&hashes, &buckets, &order) {
+                return Table {
+                    seed,
+                    probes: 0,
+                    displacements,
+                    slots,
+                };
+            }
+        }
+    }
+
+    probe(key_hashes, buckets_len)
+}
+
+/// Displacements of the buckets and slots of the keys.
+#[cfg(feature = "alloc")]
+type Placement = (Vec<(u32, u32)>, Vec<usize>);
+
+/// Searches displacements for buckets in specified order.
+/// Returns `None` if some bucket cannot be placed.
+#[cfg(feature = "alloc")]
+fn displace(hashes: &[KeyHashes], buckets: &[Vec<usize>], order: &[usize]) -> Option<Placement> {
+    let len = hashes.len();
+
+    let mut displacements = vec![(0, 0); buckets.len()];
+    let mut slots = vec![0; len];
+    let mut occupied = vec![false; len];
+
+    // Marks slots taken by the current attempt.
+    let mut attempts = vec![0u64; len];
+    let mut attempt = 0u64;
+    let mut placed = Vec::new();
+
+    let split = order
+        .iter()
+        .position(|&bucket| buckets[bucket].len() < 2)
+        .unwrap_or(order.len());
+    let (many, single) = order.split_at(split);
+
+    'buckets: for &bucket in many {
+        let keys = &buckets[bucket];
+
+        let tries = len.saturating_mul(len - 1).min(MAX_DISPLACEMENTS);
+        'displacements: for idx in 0..tries {
+            // `d1` is never zero, so keys of the bucket
+            // with different `f1` land in different slots.
+            let d1 = (1 + idx / len) as u32;
+            let d2 = (idx % len) as u32;
+
+            placed.clear();
+            attempt += 1;
+
+            for &key in keys {
+                let slot = hashes[key].slot((d1, d2), len);
+                if occupied[slot] || attempts[slot] == attempt {
+                    continue 'displacements;
+                }
+                attempts[slot] = attempt;
+                placed.push((key, slot));
+            }
+
+            for &(key, slot) in &placed {
+                occupied[slot] = true;
+                slots[key] = slot;
+            }
+            displacements[bucket] = (d1, d2);
+            continue 'buckets;
+        }
+
+        return None;
+    }
+
+    // Single key goes to a free slot with zero `d1`.
+    let mut free = (0..len).filter(|&slot| !occupied[slot]);
+    for &bucket in single {
+        let [key] = buckets[bucket][..] else {
+            break;
+        };
+        let slot = free.next()?;
+        let offset = hashes[key].slot((0, 0), len);
+        let d2 = (slot + len - offset) % len;
+        displacements[bucket] = (0, d2 as u32);
+        slots[key] = slot;
+    }
+
+    Some((displacements, slots))
+}
+
+/// Places keys with linear probing starting from their slot
+/// with zero displacement and seed.
+/// Keys are placed in order of their hashes,
+/// so placement depends only on the set of keys.
+#[cfg(feature = "alloc")]
+fn probe(key_hashes: &[u64], buckets_len: usize) -> Table {
+    let len = key_hashes.len();
+    let hashes = key_hashes
+        .iter()
+        .map(|&hash| KeyHashes::new(hash, 0))
+        .collect::<Vec<_>>();
+
+    let mut order = (0..len).collect::<Vec<_>>();
+    order.sort_by_key(|&key| key_hashes[key]);
+
+    let mut slots = vec![0; len];
+    let mut occupied = vec![false; len];
+    let mut probes = 0;
+
+    for key in order {
+        let first = hashes[key].slot((0, 0), len);
+        let mut distance = 0;
+        while occupied[(first + distance) % len] {
+            distance += 1;
+        }
+        let slot = (first + distance) % len;
+        occupied[slot] = true;
+        slots[key] = slot;
+        probes = probes.max(distance);
+    }
+
+    Table {
+        seed: 0,
+        probes: probes as u64,
+        displacements: vec![(0, 0); buckets_len],
+        slots,
+    }
+}
+
+/// Hashes of the key that select its bucket and slot.
+#[derive(Clone, Copy)]
+struct KeyHashes {
+    bucket: u64,
+    f1: u64,
+    f2: u64,
+}
+
+impl KeyHashes {
+    /// Derives hashes from the hash of the key and the seed.
+    #[inline]
+    fn new(hash: u64, seed: u64) -> Self {
+        let bucket = mix(hash ^ mix(seed));
+        let f1 = mix(bucket);
+        let f2 = mix(f1);
+        KeyHashes { bucket, f1, f2 }
+    }
+
+    /// Returns bucket of the key.
+    #[inline(always)]
+    fn bucket(&self, buckets: usize) -> usize {
+        (self.bucket % buckets as u64) as usize
+    }
+
+    /// Returns slot of the key with bucket's displacement.
+    #[inline(always)]
+    fn slot(&self, (d1, d2): (u32, u32), len: usize) -> usize {
+        let hash = u128::from(self.f1) * u128::from(d1) + u128::from(self.f2) + u128::from(d2);
+        (hash % len as u128) as usize
+    }
+}
+
+/// FNV-1a hasher of serialized keys.
+struct KeyHasher {
+    state: u64,
+}
+
+impl KeyHasher {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    #[inline(always)]
+    const fn new() -> Self {
+        KeyHasher {
+            state: Self::OFFSET,
+        }
+    }
+}
+
+impl Hasher for KeyHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= u64::from(byte);
+            self.state = self.state.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    #[inline(always)]
+    fn finish(&self) -> u64 {
+        mix(self.state)
+    }
+}
+
+/// Finalizer of SplitMix64 generator.
+#[inline(always)]
+const fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
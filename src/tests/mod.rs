@@ -2,7 +2,7 @@
 mod net;
 
 #[cfg(feature = "alloc")]
-use alloc::{collections::VecDeque, vec, vec::Vec};
+use alloc::{collections::VecDeque, vec::Vec};
 
 #[cfg(all(feature = "derive", feature = "alloc"))]
 use alkahest_proc::{Deserialize, Formula, Serialize};
@@ -55,24 +55,24 @@ where
 
     let size1 = serialize_or_size::<F, _>(value, buffer).expect("expected success");
     assert_eq!(size, size1);
-    assert_eq!(size, value_size::<F>(&buffer).expect("expected success"));
+    assert_eq!(size, value_size::<F>(buffer).expect("expected success"));
     let buffer2 = &mut buffer[size..];
 
     match serialize::<F, _>(value, &mut []) {
         Ok(_) => assert_eq!(size, 0),
-        Err(BufferExhausted) => {}
+        Err(BufferExhausted) => assert_ne!(size, 0),
     }
 
     if size > 0 {
-        match serialize::<F, _>(value, &mut buffer2[..size - 1]) {
-            Ok(_) => panic!("expected error"),
-            Err(BufferExhausted) => {}
-        }
+        assert!(matches!(
+            serialize::<F, _>(value, &mut buffer2[..size - 1]),
+            Err(BufferExhausted)
+        ));
     }
 
     let size2 = serialize::<F, _>(value, buffer2).expect("expected success");
     assert_eq!(size, size2);
-    assert_eq!(size, value_size::<F>(&buffer2).expect("expected success"));
+    assert_eq!(size, value_size::<F>(buffer2).expect("expected success"));
 
     let buffer = &buffer[..];
     let buffer2 = &buffer[size..];
@@ -116,6 +116,61 @@ fn test_primitives() {
     test_primitive!(buffer, i128 = 0);
 }
 
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_byte_order() {
+    use crate::endian::{Be, Ne};
+
+    #[derive(Formula)]
+    struct HeaderFormula {
+        magic: Be<u32>,
+        version: Be<u16>,
+        flags: u16,
+        length: Ne<u64>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[alkahest(HeaderFormula)]
+    struct Header {
+        magic: u32,
+        version: u16,
+        flags: u16,
+        length: u64,
+    }
+
+    let header = Header {
+        magic: 0xCAFE_BABE,
+        version: 0x0102,
+        flags: 0x0304,
+        length: 0x0506_0708_090A_0B0C,
+    };
+
+    let mut buffer = [0u8; 64];
+    let size = serialize::<HeaderFormula, _>(&header, &mut buffer).unwrap();
+    let bytes = &buffer[..size];
+
+    let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(&[0xCA, 0xFE, 0xBA, 0xBE]));
+    assert!(contains(&[0x01, 0x02]));
+    assert!(contains(&[0x04, 0x03]));
+    assert!(contains(&0x0506_0708_090A_0B0Cu64.to_ne_bytes()));
+
+    let (de, _) = deserialize::<HeaderFormula, Header>(bytes).unwrap();
+    assert_eq!(de, header);
+
+    let size = serialize::<Be<i16>, i8>(-2, &mut buffer).unwrap();
+    assert_eq!(buffer[size - 2..size], [0xFF, 0xFE]);
+    let (de, _) = deserialize::<Be<i16>, i64>(&buffer[..size]).unwrap();
+    assert_eq!(de, -2);
+
+    let size = serialize::<Be<f64>, f32>(1.5, &mut buffer).unwrap();
+    assert_eq!(buffer[size - 8..size], 1.5f64.to_be_bytes());
+    let (de, _) = deserialize::<Be<f64>, f64>(&buffer[..size]).unwrap();
+    assert_eq!(de, 1.5);
+
+    assert!(crate::check_compatibility(&<Be<u32>>::descriptor(), &u32::descriptor()).is_err());
+}
+
 #[test]
 fn test_array() {
     macro_rules! test_primitive {
@@ -162,6 +217,86 @@ fn test_slice() {
     test_primitive!(buffer, i128 = 0);
 }
 
+#[test]
+fn test_std_types() {
+    use core::{
+        cmp::Ordering,
+        marker::PhantomData,
+        num::{NonZeroI64, NonZeroU16, NonZeroU32, Saturating, Wrapping},
+    };
+
+    use crate::deserialize::DeserializeError;
+
+    let mut buffer = [0u8; 64];
+    test_type::<char, char, char>(&'ß', &mut buffer, |x, y| x == y);
+    test_type::<char, char, u32>(&'q', &mut buffer, |x, y| u32::from(*x) == *y);
+    test_type::<NonZeroU16, NonZeroU16, NonZeroU16>(
+        &NonZeroU16::new(7).unwrap(),
+        &mut buffer,
+        |x, y| x == y,
+    );
+    test_type::<NonZeroI64, NonZeroI64, i64>(&NonZeroI64::new(-7).unwrap(), &mut buffer, |x, y| {
+        x.get() == *y
+    });
+    test_type::<u32, NonZeroU32, u32>(&NonZeroU32::new(3).unwrap(), &mut buffer, |x, y| {
+        x.get() == *y
+    });
+    test_type::<Wrapping<u8>, Wrapping<u8>, Wrapping<u8>>(&Wrapping(5), &mut buffer, |x, y| x == y);
+    test_type::<Saturating<i16>, Saturating<i16>, Saturating<i16>>(
+        &Saturating(-5),
+        &mut buffer,
+        |x, y| x == y,
+    );
+    test_type::<PhantomData<str>, PhantomData<u8>, PhantomData<u8>>(
+        &PhantomData,
+        &mut buffer,
+        |x, y| x == y,
+    );
+    for ordering in [Ordering::Less, Ordering::Equal, Ordering::Greater] {
+        test_type::<Ordering, Ordering, Ordering>(&ordering, &mut buffer, |x, y| x == y);
+    }
+    test_type::<Result<u32, As<str>>, Result<u32, &str>, Result<u32, &str>>(
+        &Ok(42),
+        &mut buffer,
+        |x, y| x == y,
+    );
+    test_type::<Result<u32, As<str>>, Result<u32, &str>, Result<u32, &str>>(
+        &Err("failed"),
+        &mut buffer,
+        |x, y| x == y,
+    );
+    test_type::<Result<u16, (u8, u8)>, Result<u16, (u8, u8)>, Result<u16, (u8, u8)>>(
+        &Err((1, 2)),
+        &mut buffer,
+        |x, y| x == y,
+    );
+
+    // Malformed data is rejected with errors.
+    let size = serialize::<u32, _>(0xD800u32, &mut buffer).unwrap();
+    assert!(matches!(
+        deserialize::<char, char>(&buffer[..size]),
+        Err(DeserializeError::InvalidChar(0xD800))
+    ));
+
+    let size = serialize::<u32, _>(0u32, &mut buffer).unwrap();
+    assert!(matches!(
+        deserialize::<NonZeroU32, NonZeroU32>(&buffer[..size]),
+        Err(DeserializeError::ZeroValue)
+    ));
+
+    let size = serialize::<u8, _>(2u8, &mut buffer).unwrap();
+    assert!(matches!(
+        deserialize::<Ordering, Ordering>(&buffer[..size]),
+        Err(DeserializeError::WrongVariant(2))
+    ));
+
+    let size = serialize::<(u32, u32), _>((2u32, 0u32), &mut buffer).unwrap();
+    assert!(matches!(
+        deserialize::<Result<u32, u32>, Result<u32, u32>>(&buffer[..size]),
+        Err(DeserializeError::WrongVariant(2))
+    ));
+}
+
 #[test]
 fn test_ref() {
     let mut buffer = [0u8; 256];
@@ -198,6 +333,458 @@ fn test_vec() {
     test_type::<Vec<u8>, Vec<u8>, Vec<u8>>(&vec![1, 2, 3, 4], &mut buffer, |x, y| x == y);
 }
 
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_smart_pointers() {
+    use alloc::{
+        borrow::Cow,
+        boxed::Box,
+        rc::Rc,
+        string::{String, ToString},
+        sync::Arc,
+        vec,
+    };
+
+    use crate::{serialize::serialize_to_vec, Deserialize, Formula, Serialize};
+
+    let mut buffer = [0u8; 256];
+    test_type::<Box<u32>, Box<u32>, Box<u32>>(&Box::new(42), &mut buffer, |x, y| x == y);
+    test_type::<Box<str>, Box<str>, Box<str>>(&Box::from("qwe"), &mut buffer, |x, y| x == y);
+    test_type::<Box<[u16]>, Box<[u16]>, Box<[u16]>>(
+        &vec![1, 2, 3].into_boxed_slice(),
+        &mut buffer,
+        |x, y| x == y,
+    );
+    test_type::<Rc<u64>, Rc<u64>, Rc<u64>>(&Rc::new(42), &mut buffer, |x, y| x == y);
+    test_type::<Arc<str>, Arc<str>, Arc<str>>(&Arc::from("qwe"), &mut buffer, |x, y| x == y);
+    test_type::<Arc<[u32]>, Arc<[u32]>, Arc<[u32]>>(
+        &Arc::from(vec![1, 2, 3]),
+        &mut buffer,
+        |x, y| x == y,
+    );
+
+    // Pointers forward to the formula of the pointee.
+    test_type::<String, Arc<str>, Arc<str>>(&Arc::from("asd"), &mut buffer, |x, y| x == y);
+    test_type::<str, Rc<str>, Rc<str>>(&Rc::from("asd"), &mut buffer, |x, y| x == y);
+    test_type::<Vec<u8>, Box<[u8]>, Box<[u8]>>(
+        &vec![1, 2, 3].into_boxed_slice(),
+        &mut buffer,
+        |x, y| x == y,
+    );
+    test_type::<Arc<str>, &str, Arc<str>>(&"zxc", &mut buffer, |x, y| **x == **y);
+
+    // `Cow` borrows from the input.
+    let mut data = Vec::new();
+    let size = serialize_to_vec::<String, _>(Cow::<str>::Owned("hello".to_string()), &mut data);
+    let (cow, _) = deserialize::<String, Cow<str>>(&data[..size]).unwrap();
+    assert!(matches!(cow, Cow::Borrowed("hello")));
+
+    let size = serialize_to_vec::<Bytes, _>(Cow::Borrowed(&[1u8, 2, 3][..]), &mut data);
+    let (cow, _) = deserialize::<Bytes, Cow<[u8]>>(&data[..size]).unwrap();
+    assert!(matches!(cow, Cow::Borrowed([1, 2, 3])));
+
+    let size = serialize_to_vec::<Vec<u16>, _>(Cow::<[u16]>::Owned(vec![4, 5]), &mut data);
+    let (cow, _) = deserialize::<Vec<u16>, Cow<[u16]>>(&data[..size]).unwrap();
+    assert_eq!(*cow, [4, 5]);
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    struct Item {
+        name: Arc<str>,
+        tags: Box<[u32]>,
+        price: Option<Box<u64>>,
+    }
+
+    let item = Item {
+        name: Arc::from("sword"),
+        tags: vec![1, 2].into_boxed_slice(),
+        price: Some(Box::new(100)),
+    };
+
+    let size = serialize_to_vec::<Item, _>(&item, &mut data);
+    let (de, _) = deserialize::<Item, Item>(&data[..size]).unwrap();
+    assert_eq!(de, item);
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_shared() {
+    use alloc::{rc::Rc, sync::Arc, vec};
+
+    use crate::{
+        iter::SerIter,
+        serialize::serialize_to_vec,
+        shared::{deserialize_shared, serialize_shared, serialize_shared_to_vec, Shared},
+        Deserialize, Formula, Serialize,
+    };
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    struct Config {
+        name: Arc<str>,
+        limits: Arc<[u32]>,
+    }
+
+    #[derive(Formula)]
+    struct NodeFormula {
+        name: Shared<str>,
+        config: Shared<Config>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[alkahest(NodeFormula)]
+    struct Node {
+        name: Arc<str>,
+        config: Arc<Config>,
+    }
+
+    let name: Arc<str> = Arc::from("node");
+    let config = Arc::new(Config {
+        name: Arc::from("default"),
+        limits: Arc::from(vec![1, 2, 3]),
+    });
+    let nodes: Vec<Node> = (0..4)
+        .map(|_| Node {
+            name: name.clone(),
+            config: config.clone(),
+        })
+        .collect();
+
+    let mut plain = Vec::new();
+    let plain_size = serialize_to_vec::<[NodeFormula], _>(&nodes, &mut plain);
+
+    let mut shared = Vec::new();
+    let shared_size = serialize_shared_to_vec::<[NodeFormula], _>(&nodes, &mut shared);
+    assert!(shared_size < plain_size);
+
+    let mut buffer = [0u8; 256];
+    let size = serialize_shared::<[NodeFormula], _>(&nodes, &mut buffer).unwrap();
+    assert_eq!(buffer[..size], shared[..shared_size]);
+
+    // Plain deserialization reads shared pointees for every occurrence.
+    let (de, _) = deserialize::<[NodeFormula], Vec<Node>>(&shared[..shared_size]).unwrap();
+    assert_eq!(de.len(), 4);
+    assert!(de
+        .iter()
+        .all(|node| *node.name == *name && *node.config == *config));
+    assert!(!Arc::ptr_eq(&de[0].config, &de[1].config));
+
+    // Sharing is restored.
+    let (de, _) = deserialize_shared::<[NodeFormula], Vec<Node>>(&shared[..shared_size]).unwrap();
+    assert!(de
+        .iter()
+        .all(|node| *node.name == *name && *node.config == *config));
+    assert!(de.iter().all(|node| Arc::ptr_eq(&node.name, &de[0].name)));
+    assert!(de
+        .iter()
+        .all(|node| Arc::ptr_eq(&node.config, &de[0].config)));
+
+    // Data written without deduplication is compatible.
+    let (de, _) = deserialize_shared::<[NodeFormula], Vec<Node>>(&plain[..plain_size]).unwrap();
+    assert!(de.iter().all(|node| *node.config == *config));
+
+    // Pointer with no other references is written as is.
+    let unique = Rc::new(42u64);
+    let size = serialize_shared_to_vec::<Shared<u64>, _>(&unique, &mut shared);
+    let (de, _) = deserialize_shared::<Shared<u64>, Rc<u64>>(&shared[..size]).unwrap();
+    assert_eq!(*de, 42);
+
+    // Pointers passed by value are dropped during serialization
+    // and their addresses are reused by following allocations.
+    let pairs = (0..8u64).map(|n| {
+        let rc = Rc::new(n);
+        (rc.clone(), rc)
+    });
+    let size =
+        serialize_shared_to_vec::<[(Shared<u64>, Shared<u64>)], _>(SerIter(pairs), &mut shared);
+    let (de, _) = deserialize_shared::<[(Shared<u64>, Shared<u64>)], Vec<(Rc<u64>, Rc<u64>)>>(
+        &shared[..size],
+    )
+    .unwrap();
+    assert!(de.iter().zip(0..).all(|((a, b), n)| **a == n && **b == n));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_time_net_path() {
+    use std::{
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6},
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use crate::{deserialize::DeserializeError, time::VlqDuration};
+
+    let mut buffer = [0u8; 128];
+    let duration = Duration::new(5, 123_456_789);
+    test_type::<Duration, Duration, Duration>(&duration, &mut buffer, |x, y| x == y);
+    test_type::<VlqDuration, Duration, Duration>(&duration, &mut buffer, |x, y| x == y);
+    assert_eq!(<Duration as Formula>::MAX_STACK_SIZE, Some(12));
+    assert!(
+        serialized_size::<VlqDuration, _>(Duration::from_secs(1)) - reference_size::<VlqDuration>()
+            < serialized_size::<Duration, _>(Duration::from_secs(1)) - reference_size::<Duration>()
+    );
+
+    let size = serialize::<(u64, u32), _>((1u64, 1_000_000_000u32), &mut buffer).unwrap();
+    assert!(matches!(
+        deserialize::<Duration, Duration>(&buffer[..size]),
+        Err(DeserializeError::InvalidNanos(1_000_000_000))
+    ));
+
+    for time in [
+        UNIX_EPOCH,
+        UNIX_EPOCH + duration,
+        UNIX_EPOCH - duration,
+        UNIX_EPOCH - Duration::from_secs(3),
+        SystemTime::now(),
+    ] {
+        test_type::<SystemTime, SystemTime, SystemTime>(&time, &mut buffer, |x, y| x == y);
+    }
+
+    let size = serialize::<SystemTime, _>(UNIX_EPOCH - duration, &mut buffer).unwrap();
+    let (repr, _) = deserialize::<(i64, u32), (i64, u32)>(&buffer[..size]).unwrap();
+    assert_eq!(repr, (-6, 876_543_211));
+
+    let v4 = Ipv4Addr::new(192, 168, 0, 1);
+    let v6 = Ipv6Addr::new(0xfe80, 0, 0, 0, 1, 2, 3, 4);
+    test_type::<Ipv4Addr, Ipv4Addr, Ipv4Addr>(&v4, &mut buffer, |x, y| x == y);
+    test_type::<IpAddr, IpAddr, IpAddr>(&IpAddr::V4(v4), &mut buffer, |x, y| x == y);
+    test_type::<IpAddr, IpAddr, IpAddr>(&IpAddr::V6(v6), &mut buffer, |x, y| x == y);
+    test_type::<SocketAddr, SocketAddr, SocketAddr>(
+        &SocketAddr::new(IpAddr::V4(v4), 8080),
+        &mut buffer,
+        |x, y| x == y,
+    );
+    test_type::<SocketAddr, SocketAddr, SocketAddr>(
+        &SocketAddr::V6(SocketAddrV6::new(v6, 443, 7, 2)),
+        &mut buffer,
+        |x, y| x == y,
+    );
+
+    let size = serialize::<Ipv4Addr, _>(v4, &mut buffer).unwrap();
+    let (bits, _) = deserialize::<u32, u32>(&buffer[..size]).unwrap();
+    assert_eq!(bits, 0xC0A8_0001);
+
+    let path = PathBuf::from("some/dir/file.txt");
+    test_type::<PathBuf, PathBuf, PathBuf>(&path, &mut buffer, |x, y| x == y);
+    test_type::<Path, &Path, &Path>(&path.as_path(), &mut buffer, |x, y| x == y);
+
+    #[cfg(unix)]
+    {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = Path::new(OsStr::from_bytes(b"non-\xFFutf8"));
+        test_type::<Path, &Path, PathBuf>(&path, &mut buffer, |x, y| x == y);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_map() {
+    use alloc::{
+        collections::{BTreeMap, BTreeSet},
+        string::{String, ToString},
+    };
+    use std::collections::{HashMap, HashSet};
+
+    use crate::{
+        iter::SerIter,
+        map::{LazyMap, LazySet, Map, Set},
+        serialize::serialize_to_vec,
+    };
+
+    type MapFormula = Map<u32, String>;
+
+    let hash_map = (0..100u32)
+        .map(|n| (n * 3, n.to_string()))
+        .collect::<HashMap<_, _>>();
+    let btree_map = hash_map.clone().into_iter().collect::<BTreeMap<_, _>>();
+
+    let small_map = btree_map
+        .iter()
+        .take(5)
+        .map(|(k, v)| (*k, v.clone()))
+        .collect::<BTreeMap<u32, String>>();
+    let mut buffer = vec![0u8; 2 * serialized_size::<MapFormula, _>(&small_map)];
+    test_type::<MapFormula, BTreeMap<u32, String>, BTreeMap<u32, String>>(
+        &small_map,
+        &mut buffer,
+        |x, y| x == y,
+    );
+
+    let mut data = Vec::new();
+    let size = serialize_to_vec::<MapFormula, _>(&hash_map, &mut data);
+
+    // Hash map entries are sorted, so layout matches `BTreeMap`.
+    let mut data2 = Vec::new();
+    let size2 = serialize_to_vec::<MapFormula, _>(&btree_map, &mut data2);
+    assert_eq!(data[..size], data2[..size2]);
+
+    let size3 = serialize_to_vec::<MapFormula, _>(hash_map.clone(), &mut data2);
+    assert_eq!(data[..size], data2[..size3]);
+
+    let (de, _) = deserialize::<MapFormula, HashMap<u32, String>>(&data[..size]).unwrap();
+    assert_eq!(de, hash_map);
+
+    let (de, _) = deserialize::<MapFormula, BTreeMap<u32, &str>>(&data[..size]).unwrap();
+    assert!(de
+        .into_iter()
+        .eq(btree_map.iter().map(|(k, v)| (*k, v.as_str()))));
+
+    let (lazy, _) = deserialize::<MapFormula, LazyMap<u32, String>>(&data[..size]).unwrap();
+    assert_eq!(lazy.len(), 100);
+    for n in 0..300 {
+        let value = lazy.get::<u32, &str, _>(&n).unwrap();
+        assert_eq!(value, hash_map.get(&n).map(String::as_str));
+        assert_eq!(lazy.contains_key::<u32, _>(&n).unwrap(), n % 3 == 0);
+    }
+    assert!(lazy
+        .iter::<u32, &str>()
+        .map(Result::unwrap)
+        .eq(btree_map.iter().map(|(k, v)| (*k, v.as_str()))));
+
+    // Keys may be borrowed.
+    let names = ["alpha", "beta", "gamma"];
+    let size = serialize_to_vec::<Map<String, u8>, _>(
+        SerIter(names.iter().zip(0u8..).map(|(name, idx)| (*name, idx))),
+        &mut data,
+    );
+    let (lazy, _) = deserialize::<Map<String, u8>, LazyMap<String, u8>>(&data[..size]).unwrap();
+    assert_eq!(lazy.get::<&str, u8, str>("beta").unwrap(), Some(1));
+    assert_eq!(lazy.get::<&str, u8, str>("delta").unwrap(), None);
+
+    let empty = BTreeMap::<u32, u32>::new();
+    let size = serialize_to_vec::<Map<u32, u32>, _>(&empty, &mut data);
+    let (lazy, _) = deserialize::<Map<u32, u32>, LazyMap<u32, u32>>(&data[..size]).unwrap();
+    assert!(lazy.is_empty());
+    assert_eq!(lazy.get::<u32, u32, _>(&0).unwrap(), None);
+
+    // Sets.
+    let hash_set = (0..50u16).map(|n| n * 7).collect::<HashSet<_>>();
+    let btree_set = hash_set.iter().copied().collect::<BTreeSet<_>>();
+
+    let size = serialize_to_vec::<Set<u16>, _>(&hash_set, &mut data);
+    let size2 = serialize_to_vec::<Set<u16>, _>(btree_set.clone(), &mut data2);
+    assert_eq!(data[..size], data2[..size2]);
+
+    let (de, _) = deserialize::<Set<u16>, HashSet<u16>>(&data[..size]).unwrap();
+    assert_eq!(de, hash_set);
+    let (de, _) = deserialize::<Set<u16>, BTreeSet<u16>>(&data[..size]).unwrap();
+    assert_eq!(de, btree_set);
+
+    let (lazy, _) = deserialize::<Set<u16>, LazySet<u16>>(&data[..size]).unwrap();
+    assert_eq!(lazy.len(), 50);
+    for n in 0..400 {
+        assert_eq!(lazy.contains::<u16, _>(&n).unwrap(), n % 7 == 0 && n < 350);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hash_table() {
+    use alloc::{
+        collections::BTreeMap,
+        string::{String, ToString},
+    };
+    use std::collections::HashMap;
+
+    use crate::{
+        buffer::Buffer,
+        serialize::{serialize_to_vec, Serialize, Sizes},
+        table::{HashTable, LazyHashTable},
+    };
+
+    type TableFormula = HashTable<u64, String>;
+
+    let hash_map = (0..1000u64)
+        .map(|n| (n * n, n.to_string()))
+        .collect::<HashMap<_, _>>();
+    let btree_map = hash_map.clone().into_iter().collect::<BTreeMap<_, _>>();
+
+    let mut data = Vec::new();
+    let size = serialize_to_vec::<TableFormula, _>(&hash_map, &mut data);
+
+    // Table layout does not depend on iteration order.
+    let mut data2 = Vec::new();
+    let size2 = serialize_to_vec::<TableFormula, _>(btree_map.clone(), &mut data2);
+    assert_eq!(data[..size], data2[..size2]);
+
+    let (de, _) = deserialize::<TableFormula, HashMap<u64, String>>(&data[..size]).unwrap();
+    assert_eq!(de, hash_map);
+    let (de, _) = deserialize::<TableFormula, BTreeMap<u64, &str>>(&data[..size]).unwrap();
+    assert!(de
+        .into_iter()
+        .eq(btree_map.iter().map(|(k, v)| (*k, v.as_str()))));
+
+    let (lazy, _) = deserialize::<TableFormula, LazyHashTable<u64, String>>(&data[..size]).unwrap();
+    assert_eq!(lazy.len(), 1000);
+    for n in 0..2000u64 {
+        let value = lazy.get::<u64, &str, _>(&n).unwrap();
+        assert_eq!(value, hash_map.get(&n).map(String::as_str));
+        assert_eq!(
+            lazy.contains_key::<u64, _>(&n).unwrap(),
+            hash_map.contains_key(&n)
+        );
+    }
+    assert_eq!(lazy.iter::<u64, &str>().count(), 1000);
+
+    // Keys may be borrowed.
+    let names = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta"]
+        .iter()
+        .zip(0u8..)
+        .map(|(name, idx)| (name.to_string(), idx))
+        .collect::<HashMap<_, _>>();
+    let size = serialize_to_vec::<HashTable<String, u8>, _>(&names, &mut data);
+    let (lazy, _) =
+        deserialize::<HashTable<String, u8>, LazyHashTable<String, u8>>(&data[..size]).unwrap();
+    for (name, idx) in &names {
+        assert_eq!(lazy.get::<&str, u8, str>(name).unwrap(), Some(*idx));
+    }
+    assert_eq!(lazy.get::<&str, u8, str>("theta").unwrap(), None);
+
+    let empty = HashMap::<u32, u32>::new();
+    let size = serialize_to_vec::<HashTable<u32, u32>, _>(&empty, &mut data);
+    let (lazy, _) =
+        deserialize::<HashTable<u32, u32>, LazyHashTable<u32, u32>>(&data[..size]).unwrap();
+    assert!(lazy.is_empty());
+    assert_eq!(lazy.get::<u32, u32, _>(&0).unwrap(), None);
+
+    // Keys that serialize into same bytes cannot be separated
+    // by the hash function and fall back to linear probing.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    struct Parity(u32);
+
+    impl Serialize<u32> for &Parity {
+        #[inline]
+        fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+        where
+            B: Buffer,
+        {
+            <u32 as Serialize<u32>>::serialize(self.0 % 2, sizes, buffer)
+        }
+
+        #[inline]
+        fn size_hint(&self) -> Option<Sizes> {
+            Some(Sizes::with_stack(4))
+        }
+    }
+
+    let parities = (0..100u32)
+        .map(|n| (Parity(n), n))
+        .collect::<BTreeMap<_, _>>();
+    let size = serialize_to_vec::<HashTable<u32, u32>, _>(&parities, &mut data);
+    let (lazy, _) =
+        deserialize::<HashTable<u32, u32>, LazyHashTable<u32, u32>>(&data[..size]).unwrap();
+    assert_eq!(lazy.len(), 100);
+    assert_eq!(
+        lazy.iter::<u32, u32>()
+            .filter(|entry| entry.as_ref().unwrap().0 == 0)
+            .count(),
+        50
+    );
+    assert_eq!(lazy.get::<u32, u32, _>(&0).unwrap().map(|n| n % 2), Some(0));
+    assert_eq!(lazy.get::<u32, u32, _>(&1).unwrap().map(|n| n % 2), Some(1));
+    assert_eq!(lazy.get::<u32, u32, _>(&2).unwrap(), None);
+}
+
 #[cfg(all(feature = "alloc", feature = "derive"))]
 #[test]
 fn test_enums() {
@@ -219,6 +806,7 @@ fn test_enums() {
 
     #[derive(Deserialize)]
     #[alkahest(TestFormula)]
+    #[allow(dead_code)]
     enum TestDataLazy<'a> {
         Foo {
             a: u32,
@@ -304,10 +892,7 @@ fn test_slice_of_slice() {
 #[test]
 fn test_size() {
     const REFS: usize = 4;
-    const REF_SIZE: usize = cfg!(feature = "fixed8") as usize
-        + cfg!(feature = "fixed16") as usize * 2
-        + cfg!(feature = "fixed32") as usize * 4
-        + cfg!(feature = "fixed64") as usize * 8;
+    const REF_SIZE: usize = crate::size::SIZE_STACK;
 
     const PAYLOAD: usize = 6;
     const SIZE: usize = REFS * REF_SIZE + PAYLOAD;
@@ -319,6 +904,7 @@ fn test_size() {
 
 #[cfg(all(feature = "derive", feature = "alloc"))]
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_packet() {
     use alloc::{string::String, vec, vec::Vec};
 
@@ -471,6 +1057,61 @@ fn test_vlq() {
     }
 }
 
+#[test]
+fn test_zigzag() {
+    use crate::{deserialize::DeserializeError, vlq::ZigZag};
+
+    let mut buffer = [0u8; 1024];
+
+    let i8s = [0, 1, -1, 7, -8, 8, -9, i8::MIN, i8::MAX];
+    let i32s = [0, 1, -1, -8, 13415, -13415, i32::MIN, i32::MAX];
+    let i128s = [
+        0,
+        -1,
+        -451395861346,
+        784467440737095516151415,
+        i128::MIN,
+        i128::MAX,
+    ];
+
+    for i in i8s {
+        let size = serialize::<ZigZag, _>(i, &mut buffer).unwrap();
+        let (de, _) = deserialize::<ZigZag, i32>(&buffer[..size]).unwrap();
+        assert_eq!(de, i32::from(i));
+    }
+    for i in i32s {
+        let size = serialize::<ZigZag, _>(&i, &mut buffer).unwrap();
+        let (de, _) = deserialize::<ZigZag, i64>(&buffer[..size]).unwrap();
+        assert_eq!(de, i64::from(i));
+    }
+    for i in i128s {
+        let size = serialize::<ZigZag, _>(i, &mut buffer).unwrap();
+        let (de, _) = deserialize::<ZigZag, i128>(&buffer[..size]).unwrap();
+        assert_eq!(de, i);
+    }
+
+    // Values in -8..8 fit into the header byte.
+    for i in -8i64..8 {
+        assert_eq!(
+            serialized_size::<ZigZag, _>(i),
+            serialized_size::<ZigZag, _>(0i64)
+        );
+    }
+    assert!(serialized_size::<ZigZag, _>(-9i64) > serialized_size::<ZigZag, _>(-8i64));
+
+    // Signed and unsigned encodings differ.
+    #[cfg(feature = "alloc")]
+    assert!(crate::check_compatibility(&Vlq::descriptor(), &ZigZag::descriptor()).is_err());
+
+    let size = serialize::<ZigZag, _>(i64::MIN, &mut buffer).unwrap();
+    let err = deserialize::<ZigZag, i32>(&buffer[..size]).unwrap_err();
+    assert!(matches!(err, DeserializeError::IntegerOverflow));
+
+    let size = serialize::<ZigZag, _>(128i32, &mut buffer).unwrap();
+    let err = deserialize::<ZigZag, i8>(&buffer[..size]).unwrap_err();
+    assert!(matches!(err, DeserializeError::IntegerOverflow));
+}
+
 #[cfg(feature = "bincoded")]
 #[test]
 fn test_bincoded() {
@@ -503,6 +1144,208 @@ fn test_bincoded() {
     let size = serialize::<Bincode, _>(Value(102414), &mut buffer).unwrap();
     let (de, _) = deserialize::<Bincode, Value>(&buffer[..size]).unwrap();
     assert_eq!(de.0, 102414);
+
+    // Writer buffer cannot reserve heap.
+    let mut output = Vec::new();
+    let size =
+        crate::serialize_into_writer_cloned::<(Bincode, u8), _, _>((&Value(42), 1), &mut output)
+            .unwrap();
+    assert_eq!(size, output.len());
+    let (de, _) = deserialize::<(Bincode, u8), (Value, u8)>(&output).unwrap();
+    assert_eq!(de.0 .0, 42);
+    assert_eq!(de.1, 1);
+}
+
+#[cfg(all(feature = "std", feature = "derive"))]
+#[test]
+fn test_serialize_into_writer() {
+    use alloc::{
+        string::{String, ToString},
+        vec,
+    };
+    use std::io::{self, Write};
+
+    use crate::{
+        iter::SerIter,
+        serialize::{serialize_into_writer, serialize_into_writer_cloned, serialize_to_vec},
+    };
+
+    fn check<F, T>(value: T)
+    where
+        F: Formula + ?Sized,
+        T: Serialize<F> + Clone,
+    {
+        let mut expected = Vec::new();
+        let size = serialize_to_vec::<F, T>(value.clone(), &mut expected);
+        expected.truncate(size);
+
+        let mut output = Vec::new();
+        let size = serialize_into_writer_cloned::<F, T, _>(value.clone(), &mut output).unwrap();
+        assert_eq!(size, output.len());
+        assert_eq!(output, expected);
+
+        let mut output = Vec::new();
+        match <T as Serialize<F>>::size_hint(&value) {
+            Some(_) => {
+                let size = serialize_into_writer::<F, T, _>(value, &mut output).unwrap();
+                assert_eq!(size, output.len());
+                assert_eq!(output, expected);
+            }
+            None => {
+                let err = serialize_into_writer::<F, T, _>(value, &mut output).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+                assert!(output.is_empty());
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Formula, Serialize, Deserialize)]
+    struct Record {
+        id: u32,
+        name: String,
+        tags: Vec<String>,
+        scores: Vec<u64>,
+    }
+
+    let records: Vec<Record> = (0..10)
+        .map(|id| Record {
+            id,
+            name: id.to_string(),
+            tags: (0..id).map(|tag| tag.to_string()).collect(),
+            scores: (0..u64::from(id)).collect(),
+        })
+        .collect();
+
+    check::<u32, _>(42u32);
+    check::<(), _>(());
+    check::<[u32], _>([1u32, 2, 3]);
+    check::<Option<String>, _>(Some("qwerty"));
+    check::<(u8, Vec<u64>, String), _>((1u8, [2u64, 3], "hello"));
+    check::<Vec<Vec<String>>, _>(&vec![vec!["a".to_string(), "bc".to_string()], vec![]]);
+    check::<Vec<Record>, _>(&records);
+    check::<[u32], _>(SerIter(1u32..4));
+
+    let mut output = Vec::new();
+    serialize_into_writer_cloned::<Vec<Record>, _, _>(&records, &mut output).unwrap();
+    let (data, _) = deserialize::<Vec<Record>, Vec<Record>>(&output).unwrap();
+    assert_eq!(data, records);
+
+    /// Writer that fails after writing `limit` bytes.
+    struct Limited {
+        limit: usize,
+    }
+
+    impl Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.limit {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            self.limit -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let err = serialize_into_writer_cloned::<Vec<Record>, _, _>(&records, Limited { limit: 100 })
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+}
+
+#[cfg(all(feature = "std", feature = "derive"))]
+#[test]
+fn test_reader() {
+    use alloc::string::String;
+    use std::io::{self, BufReader};
+
+    use crate::{
+        reader::{deserialize_from_buf_read, Reader},
+        serialize::serialize_into_writer,
+    };
+
+    #[derive(Clone, Debug, PartialEq, Formula, Serialize, Deserialize)]
+    struct Message {
+        id: u32,
+        text: String,
+    }
+
+    #[derive(Deserialize)]
+    #[alkahest(Message)]
+    struct MessageRef<'a> {
+        id: u32,
+        text: &'a str,
+    }
+
+    let mut data = Vec::new();
+    for id in 0..5 {
+        let message = Message {
+            id,
+            text: "x".repeat(id as usize * 10),
+        };
+        serialize_into_writer::<Message, _, _>(message, &mut data).unwrap();
+    }
+
+    let mut reader = Reader::new(&data[..]);
+    for id in 0..5 {
+        let message = reader.read::<Message, Message>().unwrap().unwrap();
+        assert_eq!(message.id, id);
+        assert_eq!(message.text.len(), id as usize * 10);
+    }
+    assert!(reader.read::<Message, Message>().unwrap().is_none());
+
+    let mut reader = Reader::new(&data[..]);
+    let mut place = Message {
+        id: 0,
+        text: String::new(),
+    };
+    assert!(reader
+        .read_in_place::<Message, Message>(&mut place)
+        .unwrap());
+    assert_eq!(place.id, 0);
+    assert!(reader
+        .read_in_place::<Message, Message>(&mut place)
+        .unwrap());
+    assert_eq!(place.id, 1);
+    let message = reader
+        .read_borrowed::<Message, MessageRef>()
+        .unwrap()
+        .unwrap();
+    assert_eq!((message.id, message.text.len()), (2, 20));
+    let bytes = reader.read_bytes::<Message>().unwrap().unwrap();
+    assert_eq!(value_size::<Message>(bytes), Some(bytes.len()));
+
+    // Truncated message.
+    let mut reader = Reader::new(&data[..data.len() - 1]);
+    for _ in 0..4 {
+        reader.read::<Message, Message>().unwrap().unwrap();
+    }
+    let err = reader.read::<Message, Message>().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    // Message size limit.
+    let mut reader = Reader::new(&data[..]);
+    reader.set_max_message_size(value_size::<Message>(&data).unwrap());
+    reader.read::<Message, Message>().unwrap().unwrap();
+    let err = reader.read::<Message, Message>().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Small buffer forces copying, large buffer allows reading in place.
+    for capacity in [1, 7, 1024] {
+        let mut reader = BufReader::with_capacity(capacity, &data[..]);
+        for id in 0..5 {
+            let message = deserialize_from_buf_read::<Message, Message, _>(&mut reader)
+                .unwrap()
+                .unwrap();
+            assert_eq!(message.id, id);
+        }
+        assert!(
+            deserialize_from_buf_read::<Message, Message, _>(&mut reader)
+                .unwrap()
+                .is_none()
+        );
+    }
 }
 
 #[test]
@@ -519,3 +1362,1345 @@ fn test_zero_sized_arrays() {
         deserialize::<[u8; 0], VecDeque<u8>>(&[]).unwrap();
     }
 }
+
+#[cfg(feature = "alloc")]
+fn check_descriptor<F>()
+where
+    F: Formula + ?Sized,
+{
+    use crate::descriptor::Descriptor;
+
+    let descriptor = F::descriptor();
+    assert_eq!(descriptor.max_stack_size(), F::MAX_STACK_SIZE);
+    assert_eq!(descriptor.exact_size(), F::EXACT_SIZE);
+    assert_eq!(descriptor.heapless(), F::HEAPLESS);
+    assert_eq!(descriptor.reference_size(), reference_size::<F>());
+
+    let mut buffer = [0u8; 4096];
+    let size = serialize::<Descriptor, _>(&descriptor, &mut buffer).unwrap();
+    let (de, _) = deserialize::<Descriptor, Descriptor>(&buffer[..size]).unwrap();
+    assert_eq!(descriptor, de);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_descriptor() {
+    use alloc::{boxed::Box, string::String, vec};
+
+    use crate::{descriptor::Descriptor, serialize::serialize_to_vec};
+
+    check_descriptor::<()>();
+    check_descriptor::<bool>();
+    check_descriptor::<u8>();
+    check_descriptor::<i128>();
+    check_descriptor::<f64>();
+    check_descriptor::<Bytes>();
+    check_descriptor::<str>();
+    check_descriptor::<Vlq>();
+    check_descriptor::<crate::ZigZag>();
+    check_descriptor::<crate::Be<u32>>();
+    check_descriptor::<crate::Ne<f64>>();
+    check_descriptor::<[u32]>();
+    check_descriptor::<[()]>();
+    check_descriptor::<[u16; 3]>();
+    check_descriptor::<[As<str>]>();
+    check_descriptor::<Option<u32>>();
+    check_descriptor::<Option<()>>();
+    check_descriptor::<Ref<str>>();
+    check_descriptor::<Ref<u32>>();
+    check_descriptor::<Ref<()>>();
+    check_descriptor::<As<str>>();
+    check_descriptor::<(u8, Vec<u32>, String)>();
+    check_descriptor::<(u8, [u32])>();
+    check_descriptor::<VecDeque<Option<String>>>();
+    check_descriptor::<crate::FixedUsize>();
+    check_descriptor::<Descriptor>();
+
+    assert_eq!(
+        <Vec<u8>>::descriptor(),
+        Descriptor::Ref(Box::new(Descriptor::Slice(Box::new(Descriptor::U8))))
+    );
+    assert_eq!(
+        <crate::Be<u16>>::descriptor(),
+        Descriptor::BigEndian(Box::new(Descriptor::U16))
+    );
+    assert_eq!(<crate::Ne<u16>>::descriptor().max_stack_size(), Some(2));
+    assert_eq!(
+        <(String, As<str>)>::descriptor(),
+        Descriptor::Tuple(vec![
+            Descriptor::Ref(Box::new(Descriptor::Str)),
+            Descriptor::Str
+        ])
+    );
+
+    // Descriptors from peers can't nest deep enough to overflow the stack.
+    // Input is built by hand as serializing deep descriptors is slow.
+    let mut buffer = Vec::new();
+    let option = Descriptor::Option(Box::new(Descriptor::U8));
+    let size = serialize_to_vec::<Descriptor, _>(&option, &mut buffer);
+    let (u8_tag, option_tag) = buffer[size - 8..size].split_at(4);
+    let nested = |depth: usize| {
+        let stack = 4 * (depth + 1);
+        let mut input = Vec::new();
+        input.extend(crate::FixedUsize::truncate_unchecked(stack + size - 8).to_le_bytes());
+        input.extend(crate::FixedUsize::truncate_unchecked(stack).to_le_bytes());
+        input.extend(u8_tag);
+        for _ in 0..depth {
+            input.extend(option_tag);
+        }
+        input
+    };
+    assert_eq!(nested(1), buffer[..size]);
+    deserialize::<Descriptor, Descriptor>(&nested(100)).unwrap();
+    assert!(matches!(
+        deserialize::<Descriptor, Descriptor>(&nested(1000)),
+        Err(crate::DeserializeError::TooDeep)
+    ));
+
+    // Unnamed opaque formulas are not compatible even with themselves.
+    assert_eq!(
+        <core::num::NonZeroU32>::descriptor(),
+        Descriptor::opaque::<core::num::NonZeroU32>("NonZeroU32")
+    );
+    let unnamed = Descriptor::opaque::<u32>("");
+    assert!(crate::check_compatibility(&unnamed, &unnamed).is_err());
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_derived_descriptor() {
+    use alloc::{boxed::Box, string::String, vec};
+
+    use crate::descriptor::{Descriptor, Field, Variant};
+
+    #[derive(Formula)]
+    struct Unit;
+
+    #[derive(Formula)]
+    struct Tuple(u8, Vec<u32>);
+
+    #[derive(Formula)]
+    struct Struct {
+        a: Tuple,
+        b: Option<String>,
+        c: [u16],
+    }
+
+    #[derive(Formula)]
+    enum Enum {
+        A,
+        B(Unit, Tuple),
+        C { x: u64 },
+    }
+
+    check_descriptor::<Unit>();
+    check_descriptor::<Tuple>();
+    check_descriptor::<Struct>();
+    check_descriptor::<Enum>();
+
+    let tuple = Descriptor::Struct {
+        name: "Tuple".into(),
+        fields: vec![
+            Field::new("0", Descriptor::U8),
+            Field::new("1", <Vec<u32>>::descriptor()),
+        ],
+        extensible: false,
+    };
+
+    assert_eq!(
+        Struct::descriptor(),
+        Descriptor::Struct {
+            name: "Struct".into(),
+            fields: vec![
+                Field::new("a", tuple.clone()),
+                Field::new("b", <Option<String>>::descriptor()),
+                Field::new("c", Descriptor::Slice(Box::new(Descriptor::U16))),
+            ],
+            extensible: false,
+        }
+    );
+
+    assert_eq!(
+        Enum::descriptor(),
+        Descriptor::Enum {
+            name: "Enum".into(),
+            variants: vec![
+                Variant::new(0, "A", vec![]),
+                Variant::new(
+                    1,
+                    "B",
+                    vec![
+                        Field::new(
+                            "0",
+                            Descriptor::Struct {
+                                name: "Unit".into(),
+                                fields: vec![],
+                                extensible: false,
+                            }
+                        ),
+                        Field::new("1", tuple),
+                    ]
+                ),
+                Variant::new(2, "C", vec![Field::new("x", Descriptor::U64)]),
+            ],
+            non_exhaustive: false,
+            discriminant: Box::new(Descriptor::U32),
+        }
+    );
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_non_exhaustive_enum() {
+    use alloc::string::String;
+    use core::mem::size_of;
+
+    use crate::{compat::check_forward_compatibility, formula::sum_size};
+
+    mod old {
+        use alkahest_proc::Formula;
+
+        #[derive(Formula)]
+        #[alkahest(non_exhaustive)]
+        pub enum Message {
+            Ping(u32),
+            Text(alloc::string::String),
+        }
+    }
+
+    mod new {
+        use alkahest_proc::{Formula, Serialize};
+
+        #[derive(Formula, Serialize)]
+        #[alkahest(non_exhaustive)]
+        pub enum Message {
+            Ping(u32),
+            Text(alloc::string::String),
+            Data(u64, alloc::string::String),
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[alkahest(old::Message)]
+    enum OldMessage<'de> {
+        Ping(u32),
+        Text(String),
+        #[alkahest(unknown)]
+        Unknown {
+            variant: u32,
+            payload: Lazy<'de, Bytes>,
+        },
+    }
+
+    assert_eq!(<old::Message as Formula>::MAX_STACK_SIZE, None);
+    check_forward_compatibility::<old::Message, new::Message>().unwrap();
+
+    // Enum is followed by another field to check that
+    // unknown variant consumes exactly its own data.
+    let mut buffer = [0u8; 256];
+    let size = serialize::<(new::Message, u8), _>(
+        (new::Message::Data(42, String::from("qwerty")), 7),
+        &mut buffer,
+    )
+    .unwrap();
+
+    let ((message, tail), _) =
+        deserialize::<(old::Message, u8), (OldMessage, u8)>(&buffer[..size]).unwrap();
+    assert_eq!(tail, 7);
+
+    match message {
+        OldMessage::Unknown { variant, payload } => {
+            assert_eq!(variant, 2);
+            let bytes = payload.get::<&[u8]>().unwrap();
+            assert_eq!(
+                Some(bytes.len()),
+                sum_size(Some(size_of::<u64>()), <String as Formula>::MAX_STACK_SIZE)
+            );
+        }
+        _ => panic!("Expected unknown variant"),
+    }
+
+    let size = serialize::<(new::Message, u8), _>((new::Message::Ping(5), 7), &mut buffer).unwrap();
+    let ((message, tail), _) =
+        deserialize::<(old::Message, u8), (OldMessage, u8)>(&buffer[..size]).unwrap();
+    assert!(matches!(message, OldMessage::Ping(5)));
+    assert_eq!(tail, 7);
+
+    let size =
+        serialize::<new::Message, _>(new::Message::Data(1, String::from("asdf")), &mut buffer)
+            .unwrap();
+    let mut message = OldMessage::Ping(0);
+    deserialize_in_place::<old::Message, _>(&mut message, &buffer[..size]).unwrap();
+    assert!(matches!(message, OldMessage::Unknown { variant: 2, .. }));
+
+    // Payload may be read with any formula known to fit the variant.
+    #[derive(Debug, Deserialize)]
+    #[alkahest(old::Message)]
+    enum PeekMessage<'de> {
+        Ping(u32),
+        Text(String),
+        #[alkahest(unknown)]
+        Unknown(u32, Lazy<'de, (u64, String)>),
+    }
+
+    let (message, _) = deserialize::<old::Message, PeekMessage>(&buffer[..size]).unwrap();
+    match message {
+        PeekMessage::Unknown(2, payload) => {
+            let (number, text) = payload.get::<(u64, &str)>().unwrap();
+            assert_eq!(number, 1);
+            assert_eq!(text, "asdf");
+        }
+        _ => panic!("Expected unknown variant"),
+    }
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_extensible_struct() {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::compat::{check_backward_compatibility, check_forward_compatibility};
+
+    mod old {
+        use alkahest_proc::{Deserialize, Formula, Serialize};
+
+        #[derive(Clone, Debug, PartialEq, Formula, Serialize, Deserialize)]
+        #[alkahest(extensible)]
+        pub struct Player {
+            pub name: alloc::string::String,
+            pub level: u32,
+        }
+    }
+
+    mod new {
+        use alkahest_proc::{Deserialize, Formula, Serialize};
+
+        #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+        #[alkahest(extensible)]
+        pub struct Player {
+            pub name: alloc::string::String,
+            pub level: u32,
+            pub clan: Option<alloc::string::String>,
+            pub score: u64,
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[alkahest(new::Player)]
+    #[alkahest(extensible)]
+    struct PlayerView<'de> {
+        name: &'de str,
+        level: u32,
+        clan: Option<&'de str>,
+        score: u64,
+    }
+
+    assert_eq!(<old::Player as Formula>::MAX_STACK_SIZE, None);
+    check_forward_compatibility::<old::Player, new::Player>().unwrap();
+    check_backward_compatibility::<old::Player, new::Player>().unwrap();
+    check_forward_compatibility::<old::Player, (String, u32)>().unwrap_err();
+
+    let old = old::Player {
+        name: String::from("alice"),
+        level: 3,
+    };
+    let new = new::Player {
+        name: String::from("bob"),
+        level: 5,
+        clan: Some(String::from("red")),
+        score: 42,
+    };
+
+    // Structure is followed by another field to check that
+    // reader skips unknown fields.
+    let mut buffer = [0u8; 256];
+    let size = serialize::<(new::Player, u8), _>((&new, 7), &mut buffer).unwrap();
+    let ((player, tail), _) =
+        deserialize::<(old::Player, u8), (old::Player, u8)>(&buffer[..size]).unwrap();
+    assert_eq!(
+        player,
+        old::Player {
+            name: String::from("bob"),
+            level: 5,
+        }
+    );
+    assert_eq!(tail, 7);
+
+    // Missing fields are set to default.
+    let size = serialize::<(old::Player, u8), _>((&old, 7), &mut buffer).unwrap();
+    let ((player, tail), _) =
+        deserialize::<(new::Player, u8), (new::Player, u8)>(&buffer[..size]).unwrap();
+    assert_eq!(
+        player,
+        new::Player {
+            name: String::from("alice"),
+            level: 3,
+            clan: None,
+            score: 0,
+        }
+    );
+    assert_eq!(tail, 7);
+
+    let (view, _) = deserialize::<(new::Player, u8), (PlayerView, u8)>(&buffer[..size]).unwrap();
+    assert_eq!(
+        view.0,
+        PlayerView {
+            name: "alice",
+            level: 3,
+            clan: None,
+            score: 0,
+        }
+    );
+
+    let mut player = new::Player {
+        name: String::new(),
+        level: 0,
+        clan: Some(String::from("blue")),
+        score: 1,
+    };
+    let size = serialize::<old::Player, _>(&old, &mut buffer).unwrap();
+    deserialize_in_place::<new::Player, _>(&mut player, &buffer[..size]).unwrap();
+    assert_eq!(player.clan, None);
+    assert_eq!(player.score, 0);
+
+    let size = serialize::<[new::Player], _>([&new, &new], &mut buffer).unwrap();
+    let (players, _) = deserialize::<[old::Player], Vec<old::Player>>(&buffer[..size]).unwrap();
+    assert_eq!(
+        players,
+        vec![
+            old::Player {
+                name: String::from("bob"),
+                level: 5,
+            };
+            2
+        ]
+    );
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_compatibility() {
+    use alloc::{string::String, vec};
+
+    use crate::compat::{
+        check_backward_compatibility, check_compatibility, check_forward_compatibility,
+        IncompatibilityKind,
+    };
+
+    mod old {
+        use alkahest_proc::Formula;
+
+        #[derive(Formula)]
+        pub struct ClientData {
+            pub nickname: alloc::string::String,
+            pub level: u16,
+        }
+
+        #[derive(Formula)]
+        pub enum Message {
+            Client(ClientData),
+            Ping,
+        }
+    }
+
+    mod new {
+        use alkahest_proc::Formula;
+
+        #[derive(Formula)]
+        pub struct ClientData {
+            pub nickname: alloc::vec::Vec<u8>,
+            pub level: u16,
+            pub clan: alloc::string::String,
+        }
+
+        #[derive(Formula)]
+        pub enum Message {
+            Client(ClientData),
+            Ping,
+            Pong,
+        }
+    }
+
+    #[derive(Formula, Serialize)]
+    enum Flags {
+        A,
+        B,
+    }
+
+    #[derive(Formula, Deserialize)]
+    enum MoreFlags {
+        A,
+        B,
+        C,
+    }
+
+    check_backward_compatibility::<Flags, MoreFlags>().unwrap();
+    assert_eq!(
+        check_forward_compatibility::<Flags, MoreFlags>().unwrap_err()[0].kind,
+        IncompatibilityKind::UnknownVariant {
+            name: "C".into(),
+            index: 2,
+        }
+    );
+
+    // Verify that data is actually readable.
+    let mut buffer = [0u8; 64];
+    let size = serialize::<Flags, _>(Flags::B, &mut buffer).unwrap();
+    let (value, _) = deserialize::<MoreFlags, MoreFlags>(&buffer[..size]).unwrap();
+    assert!(matches!(value, MoreFlags::B));
+
+    let errors = check_backward_compatibility::<old::Message, new::Message>().unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].path, "Message::Client.0.nickname*");
+    assert!(matches!(
+        errors[0].kind,
+        IncompatibilityKind::Mismatch { .. }
+    ));
+    assert_eq!(errors[1].path, "Message::Client.0");
+    assert_eq!(
+        errors[1].kind,
+        IncompatibilityKind::MissingField {
+            name: "clan".into()
+        }
+    );
+
+    let errors = check_forward_compatibility::<old::Message, new::Message>().unwrap_err();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| alloc::format!("{error}"))
+            .collect::<Vec<_>>(),
+        vec![
+            String::from("Message::Client.0.nickname*: written as `[u8]` but read as `str`"),
+            String::from("Message::Client.0: field `clan` is unknown to reader"),
+            String::from("Message: variant `Pong` with index 2 is unknown to reader"),
+        ]
+    );
+
+    // `str` can be read as `Bytes`.
+    check_compatibility(&<Ref<str>>::descriptor(), &<Ref<Bytes>>::descriptor()).unwrap();
+    check_compatibility(&<Ref<Bytes>>::descriptor(), &<Ref<str>>::descriptor()).unwrap_err();
+
+    // Unsized last field is read with the rest of the stack.
+    check_compatibility(&<(u8, u32)>::descriptor(), &<(u8, [u8])>::descriptor()).unwrap_err();
+    check_compatibility(&<(u8, [u16])>::descriptor(), &<(u8, [u16])>::descriptor()).unwrap();
+
+    // Sizes of not last fields must match.
+    let errors = check_compatibility(
+        &<([u8; 2], u8)>::descriptor(),
+        &<([u8; 3], u8)>::descriptor(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        errors[0].kind,
+        IncompatibilityKind::ArrayLength {
+            writer: 2,
+            reader: 3
+        }
+    );
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_packed() {
+    use alloc::{vec, vec::Vec};
+
+    use crate::{
+        bits::{BitSlice, Bits, LazyBitSlice},
+        deserialize::DeserializeError,
+    };
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Formula, Serialize, Deserialize)]
+    #[alkahest(packed)]
+    enum Color {
+        Red,
+        Green,
+        Blue,
+    }
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    #[alkahest(packed)]
+    struct Flags {
+        visible: bool,
+        layer: u8,
+        color: Color,
+        locked: bool,
+    }
+
+    #[derive(Formula)]
+    #[alkahest(packed)]
+    struct Cell {
+        flags: Flags,
+        depth: Bits<3>,
+        value: Bits<9>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[alkahest(Cell)]
+    #[alkahest(packed)]
+    struct CellValue {
+        flags: Flags,
+        depth: u8,
+        value: u32,
+    }
+
+    assert_eq!(<Color as Formula>::MAX_STACK_SIZE, Some(1));
+    assert_eq!(<Flags as Formula>::MAX_STACK_SIZE, Some(2));
+    assert_eq!(<Cell as Formula>::MAX_STACK_SIZE, Some(3));
+
+    let flags = Flags {
+        visible: true,
+        layer: 5,
+        color: Color::Blue,
+        locked: false,
+    };
+
+    let mut buffer = [0u8; 256];
+    test_type::<Flags, Flags, Flags>(&flags, &mut buffer, |x, y| x == y);
+
+    let cell = CellValue {
+        flags: Flags {
+            visible: false,
+            layer: 7,
+            color: Color::Green,
+            locked: true,
+        },
+        depth: 6,
+        value: 500,
+    };
+    test_type::<Cell, CellValue, CellValue>(&cell, &mut buffer, |x, y| x == y);
+
+    // Bits that don't fit into value type.
+    let size = serialize::<Bits<12>, _>(4000u16, &mut buffer).unwrap();
+    let (value, _) = deserialize::<Bits<12>, u16>(&buffer[..size]).unwrap();
+    assert_eq!(value, 4000);
+    let err = deserialize::<Bits<12>, u8>(&buffer[..size]).unwrap_err();
+    assert!(matches!(err, DeserializeError::IntegerOverflow));
+
+    // Unused variant index of packed enum.
+    let size = serialize::<Bits<2>, _>(3u8, &mut buffer).unwrap();
+    let err = deserialize::<Color, Color>(&buffer[..size]).unwrap_err();
+    assert!(matches!(err, DeserializeError::WrongVariant(3)));
+
+    let bools = [
+        true, false, false, true, true, false, true, false, true, true,
+    ];
+    test_type::<BitSlice<bool>, [bool], Vec<bool>>(&bools, &mut buffer, |x, y| x == &y[..]);
+
+    let colors: Vec<Color> = (0..100)
+        .map(|idx| [Color::Red, Color::Green, Color::Blue][idx % 3])
+        .collect();
+    let size = serialize::<BitSlice<Color>, _>(&colors, &mut buffer).unwrap();
+    let (lazy, _) = deserialize::<BitSlice<Color>, LazyBitSlice<Color>>(&buffer[..size]).unwrap();
+    assert_eq!(lazy.len(), 100);
+    assert_eq!(lazy.get::<Color>(50).unwrap(), Some(Color::Blue));
+    assert_eq!(lazy.get::<Color>(100).unwrap(), None);
+    assert_eq!(
+        lazy.iter::<Color>()
+            .skip(10)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        colors[10..]
+    );
+
+    let values = vec![3u8, 0, 7, 1, 6];
+    let size = serialize::<BitSlice<Bits<3>>, _>(&values, &mut buffer).unwrap();
+    let (de, _) = deserialize::<BitSlice<Bits<3>>, Vec<u8>>(&buffer[..size]).unwrap();
+    assert_eq!(de, values);
+
+    check_descriptor::<Bits<5>>();
+    check_descriptor::<BitSlice<bool>>();
+    check_descriptor::<Color>();
+    check_descriptor::<Cell>();
+    assert_eq!(<Cell as Formula>::descriptor().bit_width(), Some(24));
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_variant_formula() {
+    use alloc::{boxed::Box, string::String, vec::Vec};
+
+    use crate::{compat::check_compatibility, descriptor::Descriptor};
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    #[alkahest(variant = u8)]
+    enum Small {
+        Ping,
+        Data(u16),
+        Text { text: String },
+    }
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    #[alkahest(variant = Vlq)]
+    enum Compact {
+        A(u32),
+        B,
+    }
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    #[alkahest(variant = _)]
+    enum Inferred {
+        A,
+        B(u8),
+    }
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    #[alkahest(variant = u16)]
+    #[alkahest(non_exhaustive)]
+    enum Wide {
+        A(u8),
+    }
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    enum Default {
+        A(u8),
+    }
+
+    #[derive(Serialize)]
+    #[alkahest(Small, @Data)]
+    struct DataSerialize(u8);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[alkahest(Small)]
+    enum SmallView<'de> {
+        Ping,
+        Data(u32),
+        Text { text: &'de str },
+    }
+
+    assert_eq!(
+        <Small as Formula>::MAX_STACK_SIZE,
+        Some(1 + <String as Formula>::MAX_STACK_SIZE.unwrap())
+    );
+    assert_eq!(<Compact as Formula>::MAX_STACK_SIZE, None);
+    assert_eq!(<Inferred as Formula>::MAX_STACK_SIZE, Some(2));
+    assert_eq!(<Default as Formula>::MAX_STACK_SIZE, Some(5));
+    assert_eq!(<(Compact, u8) as Formula>::MAX_STACK_SIZE, None);
+
+    let mut buffer = [0u8; 256];
+    for value in [
+        Small::Ping,
+        Small::Data(0x1234),
+        Small::Text {
+            text: String::from("hello"),
+        },
+    ] {
+        test_type::<Small, Small, Small>(&value, &mut buffer, |x, y| x == y);
+    }
+    for value in [Compact::A(7), Compact::B] {
+        test_type::<Compact, Compact, Compact>(&value, &mut buffer, |x, y| x == y);
+    }
+    for value in [Inferred::A, Inferred::B(42)] {
+        test_type::<Inferred, Inferred, Inferred>(&value, &mut buffer, |x, y| x == y);
+    }
+    test_type::<Wide, Wide, Wide>(&Wide::A(3), &mut buffer, |x, y| x == y);
+
+    // Variant index of `Vlq` formula takes single byte for small enums.
+    assert_eq!(
+        <Compact as Serialize<Compact>>::size_hint(&Compact::B)
+            .unwrap()
+            .stack,
+        1
+    );
+
+    let size = serialize::<Small, _>(DataSerialize(5), &mut buffer).unwrap();
+    let (value, _) = deserialize::<Small, SmallView>(&buffer[..size]).unwrap();
+    assert_eq!(value, SmallView::Data(5));
+
+    let size = serialize::<Small, _>(
+        &Small::Text {
+            text: String::from("text"),
+        },
+        &mut buffer,
+    )
+    .unwrap();
+    let (value, _) = deserialize::<Small, SmallView>(&buffer[..size]).unwrap();
+    assert_eq!(value, SmallView::Text { text: "text" });
+
+    check_descriptor::<Small>();
+    check_descriptor::<Compact>();
+    check_descriptor::<Inferred>();
+    check_descriptor::<Wide>();
+
+    match Inferred::descriptor() {
+        Descriptor::Enum { discriminant, .. } => assert_eq!(discriminant, Box::new(Descriptor::U8)),
+        _ => unreachable!(),
+    }
+
+    let errors = check_compatibility(&Default::descriptor(), &Wide::descriptor()).unwrap_err();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| alloc::format!("{error}"))
+            .collect::<Vec<_>>(),
+        [String::from("Default: written as `u32` but read as `u16`")]
+    );
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_variant_ids() {
+    use alloc::{string::String, vec::Vec};
+
+    use crate::{bits::BitFormula, compat::check_compatibility, descriptor::Descriptor};
+
+    mod old {
+        use alkahest_proc::{Deserialize, Formula, Serialize};
+
+        #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+        pub enum Message {
+            Ping,
+            Data(u32),
+            Pong,
+        }
+
+        #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+        pub enum Reordered {
+            Ping,
+            Data(u32),
+            Pong,
+        }
+    }
+
+    mod new {
+        use alkahest_proc::{Deserialize, Formula, Serialize};
+
+        // `Data` was removed and its id is reserved.
+        #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+        #[alkahest(reserved(1))]
+        pub enum Message {
+            #[alkahest(id = 2)]
+            Pong,
+            #[alkahest(id = 0)]
+            Ping,
+            #[alkahest(id = 3)]
+            Text(u64),
+        }
+
+        #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+        pub enum Reordered {
+            Pong,
+            Ping,
+            Data(u32),
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Formula, Serialize, Deserialize)]
+    #[alkahest(packed)]
+    enum Level {
+        Low = 1,
+        High = 6,
+    }
+
+    assert_eq!(old::Message::__ALKAHEST_FORMULA_VARIANT_Pong_IDX, 2);
+    assert_eq!(new::Message::__ALKAHEST_FORMULA_VARIANT_Pong_IDX, 2);
+    assert_eq!(new::Message::__ALKAHEST_FORMULA_VARIANT_Text_IDX, 3);
+
+    let mut buffer = [0u8; 256];
+    let size = serialize::<old::Message, _>(old::Message::Pong, &mut buffer).unwrap();
+    let (value, _) = deserialize::<new::Message, new::Message>(&buffer[..size]).unwrap();
+    assert_eq!(value, new::Message::Pong);
+
+    test_type::<new::Message, new::Message, new::Message>(
+        &new::Message::Text(7),
+        &mut buffer,
+        |x, y| x == y,
+    );
+
+    let errors =
+        check_compatibility(&old::Message::descriptor(), &new::Message::descriptor()).unwrap_err();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| alloc::format!("{error}"))
+            .collect::<Vec<_>>(),
+        [String::from(
+            "Message: variant `Data` with index 1 is unknown to reader"
+        )]
+    );
+    // Reordering variants without ids changes their indices.
+    check_compatibility(&old::Reordered::descriptor(), &new::Reordered::descriptor()).unwrap_err();
+
+    assert_eq!(<Level as BitFormula>::BITS, 3);
+    test_type::<Level, Level, Level>(&Level::High, &mut buffer, |x, y| x == y);
+
+    match new::Message::descriptor() {
+        Descriptor::Enum { variants, .. } => {
+            assert_eq!(variants.iter().map(|v| v.id).collect::<Vec<_>>(), [2, 0, 3]);
+        }
+        _ => unreachable!(),
+    }
+    check_descriptor::<new::Message>();
+    check_descriptor::<Level>();
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_validate() {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::{
+        deserialize::DeserializeError,
+        validate::{validate, Validated},
+    };
+
+    #[derive(Formula, Serialize, Deserialize)]
+    enum Shape {
+        Point,
+        Circle(u32),
+        Label { text: String, size: u8 },
+    }
+
+    #[derive(Formula, Serialize, Deserialize)]
+    struct Scene<T> {
+        name: String,
+        shapes: Vec<Shape>,
+        extra: T,
+    }
+
+    #[derive(Formula)]
+    #[alkahest(extensible)]
+    struct Header {
+        version: u16,
+        tags: Vec<String>,
+    }
+
+    #[derive(Formula, Serialize)]
+    #[alkahest(extensible)]
+    struct OldHeader {
+        version: u16,
+    }
+
+    #[derive(Formula, Serialize)]
+    #[alkahest(packed)]
+    enum Mode {
+        Off,
+        On,
+        Auto,
+    }
+
+    #[derive(Formula, Serialize)]
+    #[alkahest(packed)]
+    struct Packed {
+        mode: Mode,
+        level: u8,
+    }
+
+    let mut buffer = [0u8; 256];
+
+    let scene = Scene {
+        name: String::from("scene"),
+        shapes: vec![
+            Shape::Point,
+            Shape::Circle(3),
+            Shape::Label {
+                text: String::from("qwe"),
+                size: 2,
+            },
+        ],
+        extra: 42u64,
+    };
+    let size = serialize::<Scene<u64>, _>(scene, &mut buffer).unwrap();
+    assert_eq!(validate::<Scene<u64>>(&buffer[..size]).unwrap(), size);
+
+    // Lazy access to validated data does not fail.
+    let (lazy, _) = deserialize::<Scene<u64>, SceneLazy>(&buffer[..size]).unwrap();
+    assert_eq!(lazy.shapes.iter::<Shape>().filter(Result::is_ok).count(), 3);
+
+    #[derive(Deserialize)]
+    #[alkahest(Scene<u64>)]
+    struct SceneLazy<'de> {
+        #[allow(dead_code)]
+        name: &'de str,
+        shapes: Lazy<'de, [Shape]>,
+        #[allow(dead_code)]
+        extra: u64,
+    }
+
+    // Broken UTF-8 in nested string.
+    let mut broken = buffer;
+    let pos = broken[..size].windows(3).position(|w| w == b"qwe").unwrap();
+    broken[pos + 1] = 0xFF;
+    assert!(matches!(
+        validate::<Scene<u64>>(&broken[..size]),
+        Err(DeserializeError::NonUtf8(_))
+    ));
+
+    // Unknown variant index.
+    let size = serialize::<Shape, _>(Shape::Circle(5), &mut buffer).unwrap();
+    assert_eq!(validate::<Shape>(&buffer[..size]).unwrap(), size);
+    let mut broken = buffer;
+    let pos = broken[..size].iter().position(|&b| b == 1).unwrap();
+    broken[pos] = 7;
+    assert!(matches!(
+        validate::<Shape>(&broken[..size]),
+        Err(DeserializeError::WrongVariant(7))
+    ));
+
+    // Address pointing outside of the input.
+    let size = serialize::<As<str>, _>("qwerty", &mut buffer).unwrap();
+    let mut broken = buffer;
+    broken[size - crate::size::SIZE_STACK] = 0xFF;
+    assert!(validate::<As<str>>(&broken[..size]).is_err());
+
+    // Truncated input.
+    assert!(validate::<Scene<u64>>(&buffer[..1]).is_err());
+
+    let size = serialize::<(u8, As<str>), _>((1u8, "a"), &mut buffer).unwrap();
+    let (Validated, _) = deserialize::<(u8, As<str>), Validated>(&buffer[..size]).unwrap();
+
+    // Fields missing in data written with older formula are skipped.
+    let size = serialize::<OldHeader, _>(OldHeader { version: 1 }, &mut buffer).unwrap();
+    assert_eq!(validate::<Header>(&buffer[..size]).unwrap(), size);
+
+    let packed = Packed {
+        mode: Mode::Auto,
+        level: 3,
+    };
+    let size = serialize::<Packed, _>(packed, &mut buffer).unwrap();
+    assert_eq!(validate::<Packed>(&buffer[..size]).unwrap(), size);
+    let mut broken = buffer;
+    let pos = broken[..size].iter().position(|&b| b == 0b1110).unwrap();
+    broken[pos] = 0b1111;
+    assert!(matches!(
+        validate::<Packed>(&broken[..size]),
+        Err(DeserializeError::WrongVariant(3))
+    ));
+}
+
+#[cfg(all(feature = "error-context", feature = "derive"))]
+#[test]
+fn test_error_context() {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::{context::deserialize_with_context, deserialize::DeserializeError};
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    struct ClientData {
+        id: u32,
+        nickname: String,
+    }
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    enum GameMessage {
+        Server(Vec<u8>),
+        Client(ClientData),
+    }
+
+    let mut buffer = [0u8; 256];
+    let message = GameMessage::Client(ClientData {
+        id: 7,
+        nickname: String::from("alice"),
+    });
+    let size = serialize::<GameMessage, _>(&message, &mut buffer).unwrap();
+
+    let (value, _) = deserialize_with_context::<GameMessage, GameMessage>(&buffer[..size]).unwrap();
+    assert_eq!(value, message);
+
+    let mut broken = buffer;
+    let pos = broken[..size].iter().position(|&b| b == b'c').unwrap();
+    broken[pos] = 0xFF;
+    let err = deserialize_with_context::<GameMessage, GameMessage>(&broken[..size]).unwrap_err();
+    assert!(matches!(err.error(), DeserializeError::NonUtf8(_)));
+    assert_eq!(err.path(), "GameMessage::Client.0.ClientData.nickname");
+    // String bytes are read, but rejected by the string,
+    // so offset is where the nickname field ends.
+    let nickname_end = err.offset().unwrap();
+    assert!(nickname_end < size);
+    assert!(alloc::format!("{err}").starts_with(&alloc::format!(
+        "`GameMessage::Client.0.ClientData.nickname`: at offset {nickname_end}: "
+    )));
+
+    // Reference to nickname ends with its size,
+    // offset is where the read of the nickname bytes fails.
+    const REF: usize = crate::size::SIZE_STACK;
+    let mut broken = buffer;
+    broken[nickname_end - REF..nickname_end].fill(0xFF);
+    let address = broken[nickname_end - 2 * REF..nickname_end - REF]
+        .iter()
+        .rev()
+        .fold(0, |acc, &byte| acc << 8 | usize::from(byte));
+    let err = deserialize_with_context::<GameMessage, GameMessage>(&broken[..size]).unwrap_err();
+    assert!(matches!(err.error(), DeserializeError::OutOfBounds));
+    assert_eq!(err.path(), "GameMessage::Client.0.ClientData.nickname");
+    assert_eq!(err.offset(), Some(address));
+
+    let size = serialize::<GameMessage, _>(GameMessage::Server(vec![1, 2]), &mut buffer).unwrap();
+    let mut broken = buffer;
+    // Variant index is at the end of the value.
+    broken[size - 4] = 5;
+    let err = deserialize_with_context::<GameMessage, GameMessage>(&broken[..size]).unwrap_err();
+    assert!(matches!(err.error(), DeserializeError::WrongVariant(5)));
+    assert_eq!(err.path(), "GameMessage");
+    assert_eq!(err.offset(), Some(size));
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_deserialize_strict() {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::{
+        deserialize::{deserialize_strict, DeserializeError},
+        reference::Ref,
+        size::SIZE_STACK,
+    };
+
+    #[derive(Debug, PartialEq, Formula, Serialize, Deserialize)]
+    struct Block {
+        height: u64,
+        parent: Option<u32>,
+        miner: String,
+        txs: Vec<Vec<u8>>,
+    }
+
+    let block = Block {
+        height: 42,
+        parent: None,
+        miner: String::from("alice"),
+        txs: vec![vec![1, 2, 3], vec![], vec![4]],
+    };
+
+    let mut buffer = [0u8; 256];
+    let size = serialize::<Block, _>(&block, &mut buffer).unwrap();
+    assert_eq!(
+        deserialize_strict::<Block, Block>(&buffer[..size]).unwrap(),
+        block
+    );
+
+    // Garbage left in the buffer doesn't affect serialized bytes.
+    let mut dirty = [0xAAu8; 256];
+    let dirty_size = serialize::<Block, _>(&block, &mut dirty).unwrap();
+    assert_eq!(&dirty[..dirty_size], &buffer[..size]);
+
+    // Truncated and extended inputs are rejected.
+    assert!(deserialize_strict::<Block, Block>(&buffer[..size - 1]).is_err());
+    let mut longer = Vec::from(&buffer[..size]);
+    longer.insert(0, 0);
+    assert!(deserialize_strict::<Block, Block>(&longer).is_err());
+
+    // Both references point to the same string,
+    // leaving the other one unreferenced.
+    let size = serialize::<(Ref<str>, Ref<str>), _>(("ab", "ab"), &mut buffer).unwrap();
+    assert_eq!(
+        deserialize_strict::<(Ref<str>, Ref<str>), (&str, &str)>(&buffer[..size]).unwrap(),
+        ("ab", "ab")
+    );
+    // Point the first reference to the string of the second one.
+    let mut aliased = buffer;
+    aliased[SIZE_STACK + 4] = (SIZE_STACK + 2) as u8;
+    let (value, _) = deserialize::<(Ref<str>, Ref<str>), (&str, &str)>(&aliased[..size]).unwrap();
+    assert_eq!(value, ("ab", "ab"));
+    assert!(matches!(
+        deserialize_strict::<(Ref<str>, Ref<str>), (&str, &str)>(&aliased[..size]),
+        Err(DeserializeError::NonCanonical)
+    ));
+
+    // Any non-zero byte is `true`, but only `1` is canonical.
+    let size = serialize::<(bool, u8), _>((true, 3), &mut buffer).unwrap();
+    let mut flag = buffer;
+    flag[size - 1] = 2;
+    assert_eq!(
+        deserialize::<(bool, u8), (bool, u8)>(&flag[..size])
+            .unwrap()
+            .0,
+        (true, 3)
+    );
+    assert!(matches!(
+        deserialize_strict::<(bool, u8), (bool, u8)>(&flag[..size]),
+        Err(DeserializeError::NonCanonical)
+    ));
+}
+
+#[cfg(all(feature = "std", feature = "derive"))]
+#[test]
+fn test_canonical() {
+    use alloc::{string::String, vec, vec::Vec};
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::{
+        canonical::verify_canonical,
+        deserialize::{deserialize_strict, DeserializeError},
+        iter::SerIter,
+        map::Map,
+        r#as::As,
+        serialize::{serialize_into_writer_cloned, serialize_to_vec},
+        table::HashTable,
+        vlq::Vlq,
+    };
+
+    #[derive(Formula)]
+    struct BlockFormula {
+        height: u64,
+        parent: Option<u32>,
+        nonce: Vlq,
+        miner: As<str>,
+        balances: Map<As<str>, u64>,
+        txs: Vec<Vec<u8>>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[alkahest(BlockFormula)]
+    struct Block {
+        height: u64,
+        parent: Option<u32>,
+        nonce: u64,
+        miner: String,
+        balances: BTreeMap<String, u64>,
+        txs: Vec<Vec<u8>>,
+    }
+
+    let block = Block {
+        height: 42,
+        parent: None,
+        nonce: 1000,
+        miner: String::from("alice"),
+        balances: [(String::from("bob"), 5), (String::from("alice"), 7)].into(),
+        txs: vec![vec![1, 2, 3], vec![], vec![4]],
+    };
+
+    // All serialization functions produce the same bytes.
+    let mut buffer = [0u8; 512];
+    let size = serialize::<BlockFormula, _>(&block, &mut buffer).unwrap();
+    let mut vec = Vec::new();
+    serialize_to_vec::<BlockFormula, _>(&block, &mut vec);
+    let mut writer = Vec::new();
+    serialize_into_writer_cloned::<BlockFormula, _, _>(&block, &mut writer).unwrap();
+    assert_eq!(&buffer[..size], &vec[..]);
+    assert_eq!(&buffer[..size], &writer[..]);
+    verify_canonical::<BlockFormula>(&buffer[..size]).unwrap();
+    assert_eq!(
+        deserialize_strict::<BlockFormula, Block>(&buffer[..size]).unwrap(),
+        block
+    );
+
+    // Order of insertion into hash maps doesn't matter.
+    let forward = (0..100u32).map(|i| (i, i * 2)).collect::<HashMap<_, _>>();
+    let mut backward = HashMap::with_capacity(1000);
+    backward.extend((0..100u32).rev().map(|i| (i, i * 2)));
+
+    let mut forward_map = Vec::new();
+    let mut backward_map = Vec::new();
+    serialize_to_vec::<Map<u32, u32>, _>(&forward, &mut forward_map);
+    serialize_to_vec::<Map<u32, u32>, _>(&backward, &mut backward_map);
+    assert_eq!(forward_map, backward_map);
+    verify_canonical::<Map<u32, u32>>(&forward_map).unwrap();
+
+    let mut forward_table = Vec::new();
+    let mut backward_table = Vec::new();
+    serialize_to_vec::<HashTable<u32, u32>, _>(&forward, &mut forward_table);
+    serialize_to_vec::<HashTable<u32, u32>, _>(&backward, &mut backward_table);
+    assert_eq!(forward_table, backward_table);
+    verify_canonical::<HashTable<u32, u32>>(&forward_table).unwrap();
+
+    // Unsorted and duplicate keys are not canonical,
+    // strict deserialization into ordered map rejects them.
+    let mut unsorted = Vec::new();
+    serialize_to_vec::<Map<u32, u32>, _>(
+        SerIter([(2u32, 0u32), (1, 0)].into_iter()),
+        &mut unsorted,
+    );
+    assert!(matches!(
+        deserialize_strict::<Map<u32, u32>, BTreeMap<u32, u32>>(&unsorted),
+        Err(DeserializeError::NonCanonical)
+    ));
+
+    let mut duplicate = Vec::new();
+    serialize_to_vec::<Map<u32, u32>, _>(
+        SerIter([(1u32, 0u32), (1, 0)].into_iter()),
+        &mut duplicate,
+    );
+    assert!(matches!(
+        deserialize_strict::<Map<u32, u32>, BTreeMap<u32, u32>>(&duplicate),
+        Err(DeserializeError::NonCanonical)
+    ));
+}
+
+#[cfg(all(feature = "alloc", feature = "derive"))]
+#[test]
+fn test_serialize_to_hasher() {
+    use core::hash::Hasher;
+
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::{
+        buffer::BufferExhausted,
+        checksum::Crc32,
+        iter::SerIter,
+        serialize::{serialize_to_hasher, serialize_to_hasher_cloned, serialize_to_vec},
+    };
+
+    /// Hasher that records fed bytes.
+    #[derive(Default)]
+    struct Recorder {
+        bytes: Vec<u8>,
+    }
+
+    impl Hasher for Recorder {
+        fn write(&mut self, bytes: &[u8]) {
+            self.bytes.extend_from_slice(bytes);
+        }
+
+        fn finish(&self) -> u64 {
+            0
+        }
+    }
+
+    #[derive(Clone, Formula, Serialize, Deserialize)]
+    struct State {
+        tick: u64,
+        owner: Option<u32>,
+        players: Vec<String>,
+        grid: Vec<Vec<u8>>,
+    }
+
+    let state = State {
+        tick: 17,
+        owner: None,
+        players: vec![String::from("alice"), String::from("bob")],
+        grid: vec![vec![1, 2, 3], vec![], vec![4, 5]],
+    };
+
+    let mut expected = Vec::new();
+    serialize_to_vec::<State, _>(&state, &mut expected);
+
+    let mut recorder = Recorder::default();
+    let mut scratch = [0u8; 256];
+    let size = serialize_to_hasher::<State, _, _>(&state, &mut recorder, &mut scratch).unwrap();
+    assert_eq!(size, expected.len());
+    assert_eq!(recorder.bytes, expected);
+
+    let mut crc = Crc32::new();
+    serialize_to_hasher::<State, _, _>(&state, &mut crc, &mut scratch).unwrap();
+    let mut expected_crc = Crc32::new();
+    expected_crc.update(&expected);
+    assert_eq!(crc.finish(), expected_crc.finish());
+
+    // Values without size hint are serialized into scratch as a whole.
+    type Numbers = [(u32, As<str>)];
+    let numbers = || SerIter([(1u32, "one"), (2, "two"), (3, "three")].into_iter());
+
+    let mut expected = Vec::new();
+    let size = serialize_to_vec::<Numbers, _>(numbers(), &mut expected);
+
+    let mut recorder = Recorder::default();
+    serialize_to_hasher::<Numbers, _, _>(numbers(), &mut recorder, &mut scratch).unwrap();
+    assert_eq!(recorder.bytes, expected);
+
+    assert_eq!(
+        serialize_to_hasher::<Numbers, _, _>(numbers(), &mut recorder, &mut scratch[..size - 1]),
+        Err(BufferExhausted)
+    );
+
+    // Or measured first when they can be cloned.
+    let mut recorder = Recorder::default();
+    serialize_to_hasher_cloned::<Numbers, _, _>(numbers(), &mut recorder, &mut scratch[..size - 1])
+        .unwrap();
+    assert_eq!(recorder.bytes, expected);
+
+    // Scratch must fit the stack of values being serialized.
+    assert_eq!(
+        serialize_to_hasher::<State, _, _>(&state, &mut Crc32::new(), &mut [0u8; 4]),
+        Err(BufferExhausted)
+    );
+}
@@ -5,7 +5,9 @@ use rand::{
     Rng, SeedableRng,
 };
 
-use crate::{deserialize, serialize_to_vec, Deserialize, Formula, Lazy, SerIter, Serialize};
+use crate::{
+    deserialize, serialize_to_vec, value_size, Deserialize, Formula, Lazy, SerIter, Serialize,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Formula, Serialize, Deserialize)]
 pub enum GameMessage {
@@ -119,19 +121,252 @@ fn test_net_packet() {
     for message in packet.game_messages.iter::<GameMessageRead>() {
         match message.unwrap() {
             GameMessageRead::Client(ClientMessageRead::ClientData { nickname, clan }) => {
-                drop(nickname);
-                drop(clan);
+                let _ = nickname;
+                let _ = clan;
             }
             GameMessageRead::Client(ClientMessageRead::Chat(message)) => {
-                drop(message);
+                let _ = message;
             }
             GameMessageRead::Server(ServerMessageRead::ServerData(data)) => {
-                drop(data);
+                let _ = data;
             }
             GameMessageRead::Server(ServerMessageRead::ClientChat { client_id, message }) => {
-                drop(client_id);
-                drop(message);
+                let _ = client_id;
+                let _ = message;
             }
         }
     }
 }
+
+#[test]
+fn test_framed_stream() {
+    use crate::{
+        framing::{
+            split_frame, FrameDecoder, FrameEncoder, FrameError, FrameHeader,
+            DEFAULT_MAX_FRAME_SIZE,
+        },
+        BufferExhausted,
+    };
+
+    let mut rng = rand::rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+
+    let fingerprint = GameMessage::descriptor().fingerprint();
+    let encoder = FrameEncoder::new()
+        .with_fingerprint(fingerprint)
+        .with_checksum();
+
+    let sent = messages(rng.clone(), 100).collect::<Vec<_>>();
+
+    let mut stream = Vec::new();
+    for message in &sent {
+        let size = encoder.encode_to_vec::<GameMessage, _>(message, &mut stream);
+        assert_eq!(
+            value_size::<GameMessage>(&stream[stream.len() - size + encoder.header_size()..]),
+            Some(size - encoder.header_size())
+        );
+    }
+
+    // Frame encoded into a fixed buffer is identical.
+    let mut buffer = [0; 256];
+    let size = encoder
+        .encode::<GameMessage, _>(&sent[0], &mut buffer[..])
+        .unwrap();
+    assert_eq!(
+        encoder.encode::<GameMessage, _>(&sent[0], &mut buffer[..size - 1]),
+        Err(BufferExhausted)
+    );
+    let (frame, frame_size) = split_frame(&buffer[..size], usize::MAX).unwrap().unwrap();
+    assert_eq!(frame_size, size);
+    assert_eq!(frame.header().fingerprint, Some(fingerprint));
+    assert_eq!(stream[..size], buffer[..size]);
+
+    // Header is written before the body, so any buffer works.
+    #[cfg(feature = "std")]
+    for encoder in [encoder, FrameEncoder::new()] {
+        use crate::buffer::{CheckedFixedBuffer, WriteBuffer};
+
+        let mut expected = Vec::new();
+        let size = encoder.encode_to_vec::<GameMessage, _>(&sent[0], &mut expected);
+
+        let mut buffer = [0; 256];
+        let written = encoder
+            .encode_at::<GameMessage, _, _>(&sent[0], 3, CheckedFixedBuffer::new(&mut buffer[..]))
+            .unwrap();
+        assert_eq!(written, size);
+        assert_eq!(buffer[3..][..size], expected[..]);
+
+        let mut output = Vec::new();
+        let mut stack = Vec::new();
+        let mut written = 0;
+        let mut error = None;
+        encoder
+            .encode_at::<GameMessage, _, _>(
+                &sent[0],
+                0,
+                WriteBuffer::new(&mut output, &mut stack, &mut written, &mut error),
+            )
+            .unwrap();
+        assert!(error.is_none());
+        assert_eq!(written, size);
+        assert_eq!(output, expected);
+
+        // Values without size hint are measured first.
+        let numbers = SerIter(0..100u32);
+        let mut expected = Vec::new();
+        encoder.encode_to_vec::<[u32], _>(numbers.clone(), &mut expected);
+
+        let mut output = Vec::new();
+        let mut written = 0;
+        encoder
+            .encode_at::<[u32], _, _>(
+                numbers,
+                0,
+                WriteBuffer::new(&mut output, &mut stack, &mut written, &mut error),
+            )
+            .unwrap();
+        assert_eq!(output, expected);
+    }
+
+    // Feed the stream in random chunks.
+    let mut decoder = FrameDecoder::new().with_fingerprint(fingerprint);
+    let mut received = Vec::new();
+    let mut rest = &stream[..];
+    while !rest.is_empty() {
+        let chunk = rng.gen_range(1..=rest.len().min(64));
+        decoder.extend(&rest[..chunk]);
+        rest = &rest[chunk..];
+
+        while let Some(lazy) = decoder.next_lazy::<GameMessage>().unwrap() {
+            received.push(lazy.get::<GameMessage>().unwrap());
+        }
+    }
+    assert_eq!(received, sent);
+    assert!(decoder.pending().is_empty());
+
+    // Partial frame is kept.
+    decoder.extend(&stream[..5]);
+    assert!(decoder.next_frame().unwrap().is_none());
+    assert_eq!(decoder.pending(), &stream[..5]);
+
+    // Corrupted frame is skipped.
+    let mut corrupted = stream.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0xff;
+
+    let mut decoder = FrameDecoder::new();
+    decoder.extend(&corrupted);
+    for _ in 0..sent.len() - 1 {
+        decoder.next_frame().unwrap().unwrap();
+    }
+    assert!(matches!(
+        decoder.next_frame(),
+        Err(FrameError::ChecksumMismatch { .. })
+    ));
+    assert!(decoder.next_frame().unwrap().is_none());
+
+    // Fingerprint of different formula is rejected.
+    let mut decoder =
+        FrameDecoder::new().with_fingerprint(ClientMessage::descriptor().fingerprint());
+    decoder.extend(&stream);
+    assert!(matches!(
+        decoder.next_frame(),
+        Err(FrameError::FingerprintMismatch { found: Some(found), .. }) if found == fingerprint
+    ));
+
+    // Frames without fingerprint are rejected when it is expected.
+    let mut plain = Vec::new();
+    FrameEncoder::new().encode_to_vec::<GameMessage, _>(&sent[0], &mut plain);
+    let mut decoder = FrameDecoder::new().with_fingerprint(fingerprint);
+    decoder.extend(&plain);
+    assert!(matches!(
+        decoder.next_frame(),
+        Err(FrameError::FingerprintMismatch { found: None, .. })
+    ));
+
+    let mut decoder = FrameDecoder::new().with_max_frame_size(8);
+    decoder.extend(&plain);
+    assert!(matches!(decoder.next_frame(), Err(FrameError::TooLarge)));
+
+    // Frames are limited by default.
+    let header = FrameHeader {
+        length: DEFAULT_MAX_FRAME_SIZE,
+        fingerprint: None,
+        checksum: None,
+    };
+    let mut huge = [0; 9];
+    header.write(&mut huge);
+    let mut decoder = FrameDecoder::new();
+    decoder.extend(&huge);
+    assert!(matches!(decoder.next_frame(), Err(FrameError::TooLarge)));
+}
+
+#[cfg(feature = "tokio-codec")]
+#[tokio::test]
+async fn test_codec_duplex() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_util::codec::Framed;
+
+    use crate::codec::{Codec, FrameCodec};
+
+    let rng = rand::rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+    let sent = messages(rng, 100).collect::<Vec<_>>();
+
+    // Small duplex buffer splits frames across reads.
+    let (client, server) = tokio::io::duplex(64);
+
+    let write = async {
+        let codec = Codec::<GameMessage, GameMessage>::new()
+            .with_fingerprint()
+            .with_checksum();
+        let mut framed = Framed::new(client, codec);
+        for message in &sent {
+            framed.send(message).await.unwrap();
+        }
+    };
+
+    let read = async {
+        let codec = FrameCodec::<GameMessage>::new().with_fingerprint();
+        let mut framed = Framed::new(server, codec);
+        let mut received = Vec::new();
+        while let Some(frame) = framed.next().await {
+            let frame = frame.unwrap();
+            assert!(frame.header().checksum.is_some());
+
+            // Borrowed deserialization from the frame.
+            if let GameMessageRead::Client(ClientMessageRead::Chat(message)) =
+                frame.deserialize::<GameMessageRead>().unwrap()
+            {
+                assert_eq!(message.len(), 8);
+            }
+            received.push(frame.deserialize::<GameMessage>().unwrap());
+        }
+        received
+    };
+
+    let ((), received) = tokio::join!(write, read);
+    assert_eq!(received, sent);
+
+    // Owned values on both sides.
+    let (client, server) = tokio::io::duplex(1024);
+    let mut client = Framed::new(client, Codec::<GameMessage, GameMessage>::new());
+    let mut server = Framed::new(server, Codec::<GameMessage, GameMessage>::new());
+
+    for message in &sent[..10] {
+        client.send(message).await.unwrap();
+        let echo = server.next().await.unwrap().unwrap();
+        assert_eq!(echo, *message);
+        server.send(echo).await.unwrap();
+        assert_eq!(client.next().await.unwrap().unwrap(), *message);
+    }
+
+    // Frames without fingerprint are rejected.
+    let (client, server) = tokio::io::duplex(1024);
+    let mut client = Framed::new(client, Codec::<GameMessage, GameMessage>::new());
+    let mut server = Framed::new(
+        server,
+        Codec::<GameMessage, GameMessage>::new().with_fingerprint(),
+    );
+    client.send(&sent[0]).await.unwrap();
+    let err = server.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,145 @@
+//!
+//! This module provides formulas for `Duration` and `SystemTime`.
+//!
+
+use core::time::Duration;
+
+use crate::{deserialize::DeserializeError, formula::impl_repr_formula, vlq::Vlq};
+
+#[cfg(feature = "alloc")]
+use crate::descriptor::{Descriptor, Field};
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// Formula for `Duration` with variable-length encoding.
+///
+/// Duration is serialized as total number of nanoseconds with [`Vlq`] formula.
+/// Short durations take few bytes,
+/// unlike `Duration` formula that always takes 12 bytes
+/// for `u64` seconds and `u32` nanoseconds.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// # use core::time::Duration;
+/// let mut buffer = [0u8; 64];
+/// let duration = Duration::from_millis(1500);
+///
+/// let size = serialize::<VlqDuration, _>(duration, &mut buffer).unwrap();
+///
+/// let (de, _) = deserialize::<VlqDuration, Duration>(&buffer[..size]).unwrap();
+/// assert_eq!(de, duration);
+/// ```
+pub struct VlqDuration;
+
+#[inline(always)]
+fn duration_to_repr(duration: &Duration) -> (u64, u32) {
+    (duration.as_secs(), duration.subsec_nanos())
+}
+
+#[inline(always)]
+fn duration_from_repr((secs, nanos): (u64, u32)) -> Result<Duration, DeserializeError> {
+    if nanos >= NANOS_PER_SEC {
+        return Err(DeserializeError::InvalidNanos(nanos));
+    }
+    Ok(Duration::new(secs, nanos))
+}
+
+#[cfg(feature = "alloc")]
+fn time_descriptor(name: &str, secs: Descriptor) -> Descriptor {
+    Descriptor::Struct {
+        name: name.into(),
+        fields: vec![
+            Field::new("secs", secs),
+            Field::new("nanos", Descriptor::U32),
+        ],
+        extensible: false,
+    }
+}
+
+impl_repr_formula! {
+    Duration as (u64, u32) {
+        value: Duration,
+        repr: (u64, u32),
+        to: duration_to_repr,
+        from: duration_from_repr,
+        descriptor: time_descriptor("Duration", Descriptor::U64),
+    }
+}
+
+impl_repr_formula! {
+    VlqDuration as Vlq {
+        value: Duration,
+        repr: u128,
+        to: Duration::as_nanos,
+        from: |nanos| {
+            let secs = u64::try_from(nanos / u128::from(NANOS_PER_SEC))
+                .map_err(|_| DeserializeError::IntegerOverflow)?;
+            let subsec_nanos = (nanos % u128::from(NANOS_PER_SEC)) as u32;
+            Ok(Duration::new(secs, subsec_nanos))
+        },
+    }
+}
+
+#[cfg(feature = "std")]
+mod system_time {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::{deserialize::DeserializeError, formula::impl_repr_formula};
+
+    use super::NANOS_PER_SEC;
+
+    #[cfg(feature = "alloc")]
+    use crate::descriptor::Descriptor;
+
+    /// Returns seconds since unix epoch rounded down and nanoseconds.
+    /// Times too far from epoch saturate.
+    #[inline(always)]
+    fn system_time_to_repr(time: &SystemTime) -> (i64, u32) {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => (
+                i64::try_from(after.as_secs()).unwrap_or(i64::MAX),
+                after.subsec_nanos(),
+            ),
+            Err(err) => {
+                let before = err.duration();
+                let secs = i64::try_from(before.as_secs()).unwrap_or(i64::MAX);
+                match before.subsec_nanos() {
+                    0 => (-secs, 0),
+                    nanos => (-secs - 1, NANOS_PER_SEC - nanos),
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn system_time_from_repr((secs, nanos): (i64, u32)) -> Result<SystemTime, DeserializeError> {
+        if nanos >= NANOS_PER_SEC {
+            return Err(DeserializeError::InvalidNanos(nanos));
+        }
+
+        let time = if secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::new(secs.unsigned_abs(), nanos))
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+                .and_then(|time| time.checked_add(Duration::from_nanos(u64::from(nanos))))
+        };
+
+        time.ok_or(DeserializeError::IntegerOverflow)
+    }
+
+    impl_repr_formula! {
+        SystemTime as (i64, u32) {
+            value: SystemTime,
+            repr: (i64, u32),
+            to: system_time_to_repr,
+            from: system_time_from_repr,
+            descriptor: super::time_descriptor("SystemTime", Descriptor::I64),
+        }
+    }
+}
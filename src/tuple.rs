@@ -4,12 +4,22 @@ use crate::{
     formula::{sum_size, BareFormula, Formula},
     serialize::{field_size_hint, write_field, Serialize, Sizes},
     size::SIZE_STACK,
+    validate::Validated,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 impl Formula for () {
     const MAX_STACK_SIZE: Option<usize> = Some(0);
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Unit
+    }
 }
 
 impl BareFormula for () {}
@@ -56,6 +66,18 @@ impl Deserialize<'_, ()> for () {
     }
 }
 
+impl Deserialize<'_, ()> for Validated {
+    #[inline(always)]
+    fn deserialize(_de: Deserializer) -> Result<Self, DeserializeError> {
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, _de: Deserializer) -> Result<(), DeserializeError> {
+        Ok(())
+    }
+}
+
 macro_rules! for_tuple_2 {
     ($macro:ident) => {
         for_tuple_2!($macro for
@@ -90,6 +112,12 @@ macro_rules! formula_serialize {
 
             const EXACT_SIZE: bool = <$at as Formula>::EXACT_SIZE;
             const HEAPLESS: bool = $(<$a as Formula>::HEAPLESS &&)* <$at as Formula>::HEAPLESS;
+
+            #[cfg(feature = "alloc")]
+            #[inline(always)]
+            fn descriptor() -> Descriptor {
+                Descriptor::Tuple(alloc::vec![$(<$a as Formula>::descriptor(),)* <$at as Formula>::descriptor()])
+            }
         }
 
         impl<$($a,)* $at> BareFormula for ($($a,)* $at,)
@@ -196,7 +224,7 @@ macro_rules! formula_serialize {
                     let $b = de.read_value::<$a, $b>(false)?;
                 )*
                 let $bt = de.read_value::<$at, $bt>(true)?;
-                // de.finish()?;
+                de.finish()?;
 
                 let value = ($($b,)* $bt,);
                 Ok(value)
@@ -212,11 +240,37 @@ macro_rules! formula_serialize {
                     de.read_in_place::<$a, $b>($b, false)?;
                 )*
                 de.read_in_place::<$at, $bt>($bt, true)?;
-                // de.finish()?;
+                de.finish()?;
 
                 Ok(())
             }
         }
+
+        impl<'de, $($a,)* $at> Deserialize<'de, ($($a,)* $at,)> for Validated
+        where
+            $(
+                $a: Formula,
+                Validated: Deserialize<'de, $a>,
+            )*
+            $at: Formula + ?Sized,
+            Validated: Deserialize<'de, $at>,
+        {
+            #[inline(always)]
+            fn deserialize(mut de: Deserializer<'de>) -> Result<Validated, DeserializeError> {
+                $(
+                    de.read_value::<$a, Validated>(false)?;
+                )*
+                de.read_value::<$at, Validated>(true)?;
+                de.finish()?;
+                Ok(Validated)
+            }
+
+            #[inline(always)]
+            fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
+                <Self as Deserialize<'de, ($($a,)* $at,)>>::deserialize(de)?;
+                Ok(())
+            }
+        }
     };
 }
 
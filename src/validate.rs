@@ -0,0 +1,61 @@
+//!
+//! This module provides up-front validation of serialized data.
+//!
+
+use crate::{
+    deserialize::{deserialize, Deserialize, DeserializeError},
+    formula::Formula,
+};
+
+/// Deserialization target that checks serialized value
+/// without producing anything.
+///
+/// Deserializing `Validated` walks the whole value once
+/// and checks bounds, addresses and lengths of all nested values,
+/// UTF-8 of strings and indices of enum variants.
+/// Once it succeeds for a formula, lazy access to the same data
+/// with [`Lazy`](crate::Lazy), [`DeIter`](crate::DeIter)
+/// and other lazy views does not fail due to malformed data.
+/// Only errors specific to the target type remain possible,
+/// like [`DeserializeError::IntegerOverflow`]
+/// when target integer is too small for the value.
+///
+/// Implemented for formulas provided by the crate
+/// and for derived formulas whose fields can be validated.
+/// Custom formulas opt in by implementing `Deserialize<F>` for `Validated`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Validated;
+
+/// Validates serialized value against formula `F`
+/// before any part of it is deserialized.
+/// Returns number of bytes the value occupies, same as [`deserialize`].
+///
+/// # Errors
+///
+/// Returns `DeserializeError` if data is not a valid value of formula `F`.
+///
+/// # Example
+///
+/// ```
+/// # use alkahest::*;
+/// type MyFormula = [As<str>];
+///
+/// let mut buffer = [0u8; 64];
+/// let size = serialize::<MyFormula, _>(["qwe", "rty"], &mut buffer).unwrap();
+/// assert_eq!(validate::<MyFormula>(&buffer[..size]).unwrap(), size);
+///
+/// // Corrupt the last character of "rty".
+/// let pos = buffer.iter().position(|&b| b == b'y').unwrap();
+/// buffer[pos] = 0xFF;
+/// let err = validate::<MyFormula>(&buffer[..size]).unwrap_err();
+/// assert!(matches!(err, DeserializeError::NonUtf8(_)));
+/// ```
+#[inline]
+pub fn validate<'de, F>(input: &'de [u8]) -> Result<usize, DeserializeError>
+where
+    F: Formula + ?Sized,
+    Validated: Deserialize<'de, F>,
+{
+    let (Validated, size) = deserialize::<F, Validated>(input)?;
+    Ok(size)
+}
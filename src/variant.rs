@@ -0,0 +1,72 @@
+use crate::{
+    buffer::Buffer,
+    deserialize::{DeserializeError, Deserializer},
+    formula::Formula,
+    serialize::{write_exact_size_field, Sizes},
+};
+
+/// Formula of the variant index of an enum formula.
+///
+/// Derived enum formulas write variant index as `u32` by default.
+/// Use `#[alkahest(variant = u8)]`, `u16` or `Vlq` to choose another one,
+/// or `#[alkahest(variant = _)]` to pick the smallest integer
+/// that fits all variants.
+///
+/// Inferred formula changes when variants are added,
+/// so non-exhaustive enum formulas must specify it explicitly.
+pub trait VariantFormula: Formula {
+    /// Number of distinct variant indices the formula can represent.
+    const MAX_VARIANTS: u64;
+
+    /// Returns size of the serialized variant index on the stack.
+    fn variant_size(idx: u32) -> usize;
+
+    /// Serializes variant index.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if buffer write fails.
+    fn write_variant<B>(idx: u32, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer;
+
+    /// Deserializes variant index.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DeserializeError` if deserialization fails.
+    fn read_variant(de: &mut Deserializer) -> Result<u32, DeserializeError>;
+}
+
+macro_rules! impl_variant_formula {
+    ($($ty:ident)*) => {
+        $(
+            impl VariantFormula for $ty {
+                const MAX_VARIANTS: u64 = 1 << $ty::BITS;
+
+                #[inline(always)]
+                fn variant_size(_idx: u32) -> usize {
+                    core::mem::size_of::<$ty>()
+                }
+
+                #[inline(always)]
+                fn write_variant<B>(idx: u32, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+                where
+                    B: Buffer,
+                {
+                    debug_assert!(u64::from(idx) < Self::MAX_VARIANTS);
+
+                    #[allow(clippy::cast_possible_truncation)]
+                    write_exact_size_field::<$ty, $ty, B>(idx as $ty, sizes, buffer)
+                }
+
+                #[inline(always)]
+                fn read_variant(de: &mut Deserializer) -> Result<u32, DeserializeError> {
+                    de.read_value::<$ty, u32>(false)
+                }
+            }
+        )*
+    };
+}
+
+impl_variant_formula!(u8 u16 u32);
@@ -10,6 +10,8 @@ use crate::{
     serialize::{write_bytes, write_ref, write_slice, Serialize, Sizes},
 };
 
+use crate::descriptor::Descriptor;
+
 impl<F> Formula for Vec<F>
 where
     F: Formula,
@@ -17,6 +19,11 @@ where
     const MAX_STACK_SIZE: Option<usize> = <Ref<[F]> as Formula>::MAX_STACK_SIZE;
     const EXACT_SIZE: bool = <Ref<[F]> as Formula>::EXACT_SIZE;
     const HEAPLESS: bool = <Ref<[F]> as Formula>::HEAPLESS;
+
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        <Ref<[F]> as Formula>::descriptor()
+    }
 }
 
 impl<F, T> Serialize<Vec<F>> for T
@@ -104,20 +111,22 @@ where
 {
     #[inline(always)]
     fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
-        let iter = de.into_unsized_iter();
+        let mut iter = de.into_unsized_iter();
         let (lower, _) = Iterator::size_hint(&iter);
         let mut vec = Vec::with_capacity(lower);
-        deserialize_extend_iter(&mut vec, iter)?;
+        deserialize_extend_iter(&mut vec, iter.by_ref())?;
+        iter.finish()?;
         Ok(vec)
     }
 
     #[inline(always)]
     fn deserialize_in_place(&mut self, de: Deserializer<'de>) -> Result<(), DeserializeError> {
         self.clear();
-        let iter = de.into_unsized_iter();
+        let mut iter = de.into_unsized_iter();
         let (lower, _) = Iterator::size_hint(&iter);
         self.reserve(lower);
-        deserialize_extend_iter(self, iter)
+        deserialize_extend_iter(self, iter.by_ref())?;
+        iter.finish()
     }
 }
 
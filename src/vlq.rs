@@ -3,8 +3,13 @@ use crate::{
     deserialize::{Deserialize, DeserializeError, Deserializer},
     formula::Formula,
     serialize::{write_bytes, Serialize, Sizes},
+    validate::Validated,
+    variant::VariantFormula,
 };
 
+#[cfg(feature = "alloc")]
+use crate::descriptor::Descriptor;
+
 /// Formula for Variable-Length Quantity encoding.
 ///
 /// If bit 8 is set then bits 0-6 contain length of the value in bytes.
@@ -54,12 +59,59 @@ use crate::{
 /// let err = deserialize::<Vlq, u16>(&buffer[..size]).unwrap_err();
 /// assert!(matches!(err, DeserializeError::IntegerOverflow));
 /// ```
+///
+/// Signed integers are serialized with [`ZigZag`] formula.
 pub struct Vlq;
 
 impl Formula for Vlq {
     const MAX_STACK_SIZE: Option<usize> = None;
     const EXACT_SIZE: bool = true;
     const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::Vlq
+    }
+}
+
+/// Formula for signed integers with zigzag encoding.
+///
+/// Signed value is mapped to unsigned one,
+/// so that values with small magnitude are small regardless of sign:
+/// 0, -1, 1, -2, 2 ... become 0, 1, 2, 3, 4 ...
+/// The result is encoded with [`Vlq`] formula.
+///
+/// Like with [`Vlq`] the type of the value can be different
+/// than the type of the serialized value.
+///
+/// # Examples
+///
+/// ```
+/// # use alkahest::*;
+///
+/// let mut buffer = [0u8; 1024];
+///
+/// let size = serialize::<ZigZag, i64>(-7, &mut buffer).unwrap();
+/// let value = deserialize::<ZigZag, i8>(&buffer[..size]).unwrap().0;
+/// assert_eq!(-7, value);
+///
+/// let size = serialize::<ZigZag, i32>(-1000, &mut buffer).unwrap();
+/// let err = deserialize::<ZigZag, i8>(&buffer[..size]).unwrap_err();
+/// assert!(matches!(err, DeserializeError::IntegerOverflow));
+/// ```
+pub struct ZigZag;
+
+impl Formula for ZigZag {
+    const MAX_STACK_SIZE: Option<usize> = None;
+    const EXACT_SIZE: bool = true;
+    const HEAPLESS: bool = true;
+
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    fn descriptor() -> Descriptor {
+        Descriptor::ZigZag
+    }
 }
 
 trait VlqType: Copy {
@@ -157,13 +209,38 @@ where
     }
 }
 
+macro_rules! impl_vlq_ref {
+    ($($a:ident)*) => {
+        $(
+            impl Serialize<Vlq> for &$a {
+                #[inline(always)]
+                fn size_hint(&self) -> Option<Sizes> {
+                    Some(size_hint(**self))
+                }
+
+                #[inline(always)]
+                fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+                where
+                    B: Buffer,
+                {
+                    serialize(*self, sizes, buffer)
+                }
+            }
+        )*
+    };
+}
+
+impl_vlq_ref!(u8 u16 u32 u64 u128 usize);
+
 impl<'de, T> Deserialize<'de, Vlq> for T
 where
     T: VlqType,
 {
     #[inline(always)]
-    fn deserialize(de: Deserializer<'de>) -> Result<Self, DeserializeError> {
-        deserialize(de)
+    fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+        let value = deserialize(&mut de)?;
+        de.finish()?;
+        Ok(value)
     }
 
     #[inline(always)]
@@ -171,11 +248,128 @@ where
         &mut self,
         deserializer: Deserializer<'de>,
     ) -> Result<(), DeserializeError> {
-        *self = deserialize(deserializer)?;
+        *self = <T as Deserialize<'de, Vlq>>::deserialize(deserializer)?;
         Ok(())
     }
 }
 
+macro_rules! zigzag_encode {
+    ($value:expr, $s:ident => $u:ident) => {{
+        let value: $s = $value;
+        ((value << 1) ^ (value >> ($s::BITS - 1))) as $u
+    }};
+}
+
+macro_rules! impl_zigzag {
+    ($($s:ident => $u:ident)*) => {
+        $(
+            impl Serialize<ZigZag> for $s {
+                #[inline(always)]
+                fn size_hint(&self) -> Option<Sizes> {
+                    Some(size_hint(zigzag_encode!(*self, $s => $u)))
+                }
+
+                #[inline(always)]
+                fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+                where
+                    B: Buffer,
+                {
+                    serialize(zigzag_encode!(self, $s => $u), sizes, buffer)
+                }
+            }
+
+            impl Serialize<ZigZag> for &$s {
+                #[inline(always)]
+                fn size_hint(&self) -> Option<Sizes> {
+                    Some(size_hint(zigzag_encode!(**self, $s => $u)))
+                }
+
+                #[inline(always)]
+                fn serialize<B>(self, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+                where
+                    B: Buffer,
+                {
+                    serialize(zigzag_encode!(*self, $s => $u), sizes, buffer)
+                }
+            }
+
+            impl<'de> Deserialize<'de, ZigZag> for $s {
+                #[inline(always)]
+                fn deserialize(mut de: Deserializer<'de>) -> Result<Self, DeserializeError> {
+                    let value = deserialize::<$u>(&mut de)?;
+                    de.finish()?;
+                    Ok(((value >> 1) as $s) ^ -((value & 1) as $s))
+                }
+
+                #[inline(always)]
+                fn deserialize_in_place(
+                    &mut self,
+                    deserializer: Deserializer<'de>,
+                ) -> Result<(), DeserializeError> {
+                    *self = <$s as Deserialize<'de, ZigZag>>::deserialize(deserializer)?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_zigzag!(i8 => u8 i16 => u16 i32 => u32 i64 => u64 i128 => u128 isize => usize);
+
+impl Deserialize<'_, ZigZag> for Validated {
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+        read_parts(&mut de)?;
+        de.finish()?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<ZigZag>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+impl Deserialize<'_, Vlq> for Validated {
+    #[inline(always)]
+    fn deserialize(mut de: Deserializer) -> Result<Self, DeserializeError> {
+        read_parts(&mut de)?;
+        de.finish()?;
+        Ok(Validated)
+    }
+
+    #[inline(always)]
+    fn deserialize_in_place(&mut self, de: Deserializer) -> Result<(), DeserializeError> {
+        <Self as Deserialize<Vlq>>::deserialize(de)?;
+        Ok(())
+    }
+}
+
+/// Variant index is self-delimiting and is read directly
+/// from the enclosing deserializer.
+impl VariantFormula for Vlq {
+    const MAX_VARIANTS: u64 = 1 << u32::BITS;
+
+    #[inline(always)]
+    fn variant_size(idx: u32) -> usize {
+        size_hint(idx).stack
+    }
+
+    #[inline(always)]
+    fn write_variant<B>(idx: u32, sizes: &mut Sizes, buffer: B) -> Result<(), B::Error>
+    where
+        B: Buffer,
+    {
+        serialize(idx, sizes, buffer)
+    }
+
+    #[inline(always)]
+    fn read_variant(de: &mut Deserializer) -> Result<u32, DeserializeError> {
+        deserialize(de)
+    }
+}
+
 #[inline(always)]
 fn size_hint<T>(mut value: T) -> Sizes
 where
@@ -235,26 +429,48 @@ where
     }
 }
 
+/// Reads header byte and bytes that follow it.
+/// Returns the most significant bits of the value and the bytes.
+///
+/// Strict deserializer accepts only the shortest encoding,
+/// which is what serialization writes.
 #[inline(always)]
-fn deserialize<T>(mut de: Deserializer) -> Result<T, DeserializeError>
-where
-    T: VlqType,
-{
-    let header = de.read_bytes(1)?[0];
+fn read_parts<'de>(de: &mut Deserializer<'de>) -> Result<(u8, &'de [u8]), DeserializeError> {
+    let header = de.read_byte()?;
 
     let (tail, msb) = match header {
         0x00..=0x7F => (header >> 4, header & 0x0F),
         0x80..=0xBF => (header & 0x3F, 0),
-        0xC0..=0xFF => {
-            unimplemented!(
-                "Decoding for values that require more than 63 bytes is not implemented yet."
-            )
-        }
+        // Values that require more than 63 bytes are never written,
+        // and would not fit any integer type anyway.
+        0xC0..=0xFF => return Err(DeserializeError::IntegerOverflow),
     };
 
-    let mut value = T::from_lsb(msb);
+    let bytes = de.read_bytes(usize::from(tail))?;
+
+    if de.is_strict() {
+        let shortest = match (header, bytes.last()) {
+            (0x00..=0x7F, None) => true,
+            (0x00..=0x7F, Some(&top)) => msb != 0 || top > 0xF,
+            (_, None) => false,
+            (_, Some(&top)) => (tail == 8 && top > 0xF) || (tail > 8 && top != 0),
+        };
+        if !shortest {
+            return Err(DeserializeError::NonCanonical);
+        }
+    }
+
+    Ok((msb, bytes))
+}
 
-    let tail = de.read_bytes(usize::from(tail))?;
+#[inline(always)]
+fn deserialize<T>(de: &mut Deserializer) -> Result<T, DeserializeError>
+where
+    T: VlqType,
+{
+    let (msb, tail) = read_parts(de)?;
+
+    let mut value = T::from_lsb(msb);
 
     for byte in tail.iter().rev() {
         if !value.shl_byte_set(*byte) {